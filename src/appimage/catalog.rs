@@ -0,0 +1,117 @@
+use super::AppImagePackage;
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// How long a downloaded catalog is used before it is fetched again
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The catalog file: a list of `[[app]]` tables
+#[derive(Debug, Default, Deserialize)]
+struct Catalog {
+    #[serde(default)]
+    app: Vec<AppImagePackage>,
+}
+
+/// Where the catalog is read from: the configured path or URL, or
+/// ~/.config/khazaur/appimages.toml
+fn location(config: &Config) -> Option<String> {
+    match &config.appimage_catalog {
+        Some(location) => Some(location.clone()),
+        None => Some(dirs::config_dir()?.join("khazaur").join("appimages.toml").to_string_lossy().into_owned()),
+    }
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+fn cache_path() -> Result<PathBuf> {
+    Ok(crate::dirs::cache_dir()?.join("appimage").join("catalog.toml"))
+}
+
+/// Load the catalog. A remote catalog is cached for a day, or fetched again when
+/// `refresh` is set; the cached copy is used when it can't be downloaded.
+/// A missing local catalog is an empty one.
+pub async fn load(config: &Config, refresh: bool) -> Result<Vec<AppImagePackage>> {
+    let Some(location) = location(config) else {
+        return Ok(Vec::new());
+    };
+
+    let contents = if is_remote(&location) {
+        let cache = cache_path()?;
+        let fresh = std::fs::metadata(&cache)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age < MAX_AGE);
+
+        if fresh && !refresh {
+            std::fs::read_to_string(&cache)?
+        } else {
+            match crate::net::read_source(&location).await {
+                Ok(data) => {
+                    if let Some(parent) = cache.parent() {
+                        std::fs::create_dir_all(parent)?;
+                    }
+                    std::fs::write(&cache, &data)?;
+                    String::from_utf8_lossy(&data).into_owned()
+                }
+                Err(e) if cache.exists() => {
+                    tracing::warn!("Using cached AppImage catalog: {}", e);
+                    std::fs::read_to_string(&cache)?
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    } else {
+        let path = location.strip_prefix("file://").unwrap_or(&location);
+        if !std::path::Path::new(path).exists() {
+            return Ok(Vec::new());
+        }
+        std::fs::read_to_string(path)?
+    };
+
+    parse(&contents).map_err(|e| KhazaurError::AppImage(format!("{}: {}", location, e)))
+}
+
+/// Parse a catalog, leaving out entries whose name can't be used as a file name
+fn parse(contents: &str) -> std::result::Result<Vec<AppImagePackage>, toml::de::Error> {
+    Ok(toml::from_str::<Catalog>(contents)?
+        .app
+        .into_iter()
+        .filter(|app| match super::check_name(&app.name) {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Skipping AppImage catalog entry: {}", e);
+                false
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_skips_unsafe_names() {
+        let catalog = r#"
+            [[app]]
+            name = "obsidian"
+            version = "1.6.7"
+            url = "https://example.com/Obsidian-1.6.7.AppImage"
+
+            [[app]]
+            name = "../../.config/autostart/x"
+            version = "1.0"
+            url = "https://example.com/x.AppImage"
+        "#;
+        let apps = parse(catalog).unwrap();
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "obsidian");
+    }
+}
+
@@ -0,0 +1,90 @@
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// An AppImage khazaur installed into the managed directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledAppImage {
+    pub name: String,
+    pub version: String,
+    /// URL the AppImage was downloaded from, or the path of a local file
+    pub source_url: String,
+    /// Where the AppImage itself lives
+    pub path: PathBuf,
+    /// Desktop entry registered for it, if the AppImage shipped one
+    #[serde(default)]
+    pub desktop_file: Option<PathBuf>,
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    /// Update information embedded in the AppImage (`zsync|…`, `gh-releases-zsync|…`)
+    #[serde(default)]
+    pub update_info: Option<String>,
+    /// SHA-1 of the file, compared with the one in zsync files to find updates
+    #[serde(default)]
+    pub sha1: String,
+    /// When it was installed, RFC 3339
+    #[serde(default)]
+    pub installed_at: String,
+}
+
+/// Installed AppImages, keyed by name.
+/// Stored in ~/.local/share/khazaur/appimages.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AppImageDb {
+    #[serde(default)]
+    apps: BTreeMap<String, InstalledAppImage>,
+}
+
+impl AppImageDb {
+    pub fn load() -> Result<Self> {
+        let path = db_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&InstalledAppImage> {
+        self.apps.get(name)
+    }
+
+    pub fn insert(&mut self, app: InstalledAppImage) {
+        self.apps.insert(app.name.clone(), app);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<InstalledAppImage> {
+        self.apps.remove(name)
+    }
+
+    pub fn apps(&self) -> impl Iterator<Item = &InstalledAppImage> {
+        self.apps.values()
+    }
+}
+
+/// Record an AppImage after it was installed or updated
+pub fn record(app: InstalledAppImage) -> Result<()> {
+    let mut db = AppImageDb::load()?;
+    db.insert(app);
+    db.save()
+}
+
+fn db_path() -> Result<PathBuf> {
+    let mut path = dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?;
+    path.push("khazaur");
+    path.push("appimages.json");
+    Ok(path)
+}
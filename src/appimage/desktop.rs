@@ -0,0 +1,250 @@
+use crate::error::{KhazaurError, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Desktop entry and icon found inside an AppImage
+pub struct Extracted {
+    pub desktop: String,
+    /// Icon file and its extension (png, svg, xpm)
+    pub icon: Option<(PathBuf, String)>,
+    /// Keeps the extracted files until they are installed
+    _dir: tempfile::TempDir,
+}
+
+/// Pull the desktop entry and its icon out of an AppImage with its own
+/// `--appimage-extract`, which only unpacks the files matching a pattern
+pub fn extract(appimage: &Path) -> Result<Option<Extracted>> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().join("squashfs-root");
+
+    run_extract(appimage, dir.path(), "*.desktop")?;
+    let Some(desktop_path) = find_file(&root, |name| name.ends_with(".desktop")) else {
+        return Ok(None);
+    };
+    let desktop = std::fs::read_to_string(&desktop_path)?;
+
+    // .DirIcon is usually a symlink to the icon, so the icon goes first
+    let mut icon = None;
+    if let Some(icon_name) = entry_value(&desktop, "Icon") {
+        // Icon may be a name without extension, or a path in the image
+        let icon_name = icon_name.rsplit('/').next().unwrap_or(icon_name).to_string();
+        let stem = icon_name.rsplit_once('.').map_or(icon_name.as_str(), |(stem, _)| stem);
+        run_extract(appimage, dir.path(), &format!("{}.*", stem))?;
+        icon = ["png", "svg", "xpm"].into_iter().find_map(|ext| {
+            let path = root.join(format!("{}.{}", stem, ext));
+            path.exists().then(|| (path, ext.to_string()))
+        });
+    }
+    if icon.is_none() {
+        run_extract(appimage, dir.path(), ".DirIcon")?;
+        let path = root.join(".DirIcon");
+        if let Ok(data) = std::fs::read(&path) {
+            let ext = if data.starts_with(b"\x89PNG") { "png" } else { "svg" };
+            icon = Some((path, ext.to_string()));
+        }
+    }
+
+    Ok(Some(Extracted { desktop, icon, _dir: dir }))
+}
+
+fn run_extract(appimage: &Path, dir: &Path, pattern: &str) -> Result<()> {
+    let output = Command::new(appimage)
+        .args(["--appimage-extract", pattern])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| KhazaurError::AppImage(format!("Failed to run {}: {}", appimage.display(), e)))?;
+
+    if !output.status.success() {
+        return Err(KhazaurError::AppImage(format!(
+            "{} --appimage-extract failed: {}",
+            appimage.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(())
+}
+
+/// First file directly in `dir` whose name matches
+fn find_file(dir: &Path, matches: impl Fn(&str) -> bool) -> Option<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| matches(&entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    entries.into_iter().next()
+}
+
+/// Value of a key in the [Desktop Entry] group
+pub fn entry_value<'a>(desktop: &'a str, key: &str) -> Option<&'a str> {
+    let mut in_entry = false;
+    for line in desktop.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry
+            && let Some((k, value)) = line.split_once('=')
+            && k.trim() == key
+        {
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+/// Point a desktop entry at the installed AppImage: every Exec runs it with the
+/// original arguments, TryExec is dropped and Icon becomes the installed icon
+pub fn rewrite(desktop: &str, appimage: &Path, icon: Option<&Path>) -> String {
+    let program = quote_exec(&appimage.to_string_lossy());
+    let mut output = String::new();
+
+    for line in desktop.lines() {
+        let key = line.split_once('=').map(|(key, _)| key.trim());
+        match key {
+            Some("Exec") => {
+                let value = line.split_once('=').map_or("", |(_, value)| value.trim());
+                match exec_arguments(value) {
+                    "" => output.push_str(&format!("Exec={}", program)),
+                    args => output.push_str(&format!("Exec={} {}", program, args)),
+                }
+            }
+            Some("TryExec") => continue,
+            Some("Icon") => match icon {
+                Some(icon) => output.push_str(&format!("Icon={}", icon.display())),
+                None => output.push_str(line),
+            },
+            _ => output.push_str(line),
+        }
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Everything in an Exec value after the program
+fn exec_arguments(exec: &str) -> &str {
+    let rest = match exec.strip_prefix('"') {
+        // A quoted program ends at the first quote that isn't escaped
+        Some(quoted) => {
+            let mut escaped = false;
+            let end = quoted.char_indices().find(|&(_, c)| {
+                let is_end = c == '"' && !escaped;
+                escaped = c == '\\' && !escaped;
+                is_end
+            });
+            end.map_or("", |(i, _)| &quoted[i + 1..])
+        }
+        None => exec.split_once(char::is_whitespace).map_or("", |(_, rest)| rest),
+    };
+    rest.trim()
+}
+
+/// Quote a path for an Exec key. The desktop entry spec escapes reserved characters
+/// with a backslash inside quotes, and backslashes are escaped again by the string rules.
+fn quote_exec(path: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in path.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push_str("\\\\");
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// ~/.local/share/applications
+fn applications_dir() -> Result<PathBuf> {
+    Ok(dirs::data_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?
+        .join("applications"))
+}
+
+/// Where icons of installed AppImages are kept
+fn icons_dir() -> Result<PathBuf> {
+    Ok(dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?
+        .join("khazaur")
+        .join("appimage-icons"))
+}
+
+/// Install the icon and a desktop entry that launches the AppImage,
+/// returning (desktop file, icon)
+pub fn register(name: &str, appimage: &Path, extracted: &Extracted) -> Result<(PathBuf, Option<PathBuf>)> {
+    let icon = match &extracted.icon {
+        Some((source, ext)) => {
+            let dir = icons_dir()?;
+            std::fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("{}.{}", name, ext));
+            std::fs::copy(source, &path)?;
+            Some(path)
+        }
+        None => None,
+    };
+
+    let dir = applications_dir()?;
+    std::fs::create_dir_all(&dir)?;
+    let desktop_file = dir.join(format!("khazaur-appimage-{}.desktop", name));
+    std::fs::write(&desktop_file, rewrite(&extracted.desktop, appimage, icon.as_deref()))?;
+    refresh_database(&dir);
+
+    Ok((desktop_file, icon))
+}
+
+/// Remove the desktop entry and icon of an AppImage
+pub fn unregister(desktop_file: Option<&Path>, icon: Option<&Path>) {
+    for path in [desktop_file, icon].into_iter().flatten() {
+        if let Err(e) = std::fs::remove_file(path)
+            && e.kind() != std::io::ErrorKind::NotFound
+        {
+            tracing::warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+    if let Some(dir) = desktop_file.and_then(Path::parent) {
+        refresh_database(dir);
+    }
+}
+
+/// Let menus pick up the change; only needed when update-desktop-database is installed
+fn refresh_database(dir: &Path) {
+    if which::which("update-desktop-database").is_ok() {
+        let _ = Command::new("update-desktop-database").arg(dir).output();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite() {
+        let desktop = "[Desktop Entry]\n\
+            Name=My App\n\
+            Exec=AppRun --no-sandbox %U\n\
+            TryExec=AppRun\n\
+            Icon=myapp\n\
+            \n\
+            [Desktop Action new]\n\
+            Exec=\"App Run\" --new-window\n";
+
+        let rewritten = rewrite(
+            desktop,
+            Path::new("/home/u/Applications/my$app.AppImage"),
+            Some(Path::new("/home/u/icons/myapp.png")),
+        );
+
+        assert_eq!(
+            rewritten,
+            "[Desktop Entry]\n\
+            Name=My App\n\
+            Exec=\"/home/u/Applications/my\\\\$app.AppImage\" --no-sandbox %U\n\
+            Icon=/home/u/icons/myapp.png\n\
+            \n\
+            [Desktop Action new]\n\
+            Exec=\"/home/u/Applications/my\\\\$app.AppImage\" --new-window\n"
+        );
+        assert_eq!(entry_value(desktop, "Exec"), Some("AppRun --no-sandbox %U"));
+        assert_eq!(entry_value(desktop, "Version"), None);
+    }
+}
@@ -0,0 +1,371 @@
+pub mod catalog;
+pub mod db;
+pub mod desktop;
+pub mod update;
+
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use db::{AppImageDb, InstalledAppImage};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// An AppImage listed in the catalog, or a newer build of an installed one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppImagePackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    pub url: String,
+    /// SHA256 the download is checked against, when the catalog has one
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// SHA-1 the download is checked against, for builds found through a zsync file
+    #[serde(skip)]
+    pub sha1: Option<String>,
+}
+
+/// Where an AppImage being installed came from
+struct Origin<'a> {
+    name: String,
+    /// Version known before looking inside the AppImage (catalog or update)
+    version: Option<String>,
+    source_url: &'a str,
+}
+
+/// Names become file names in the AppImage, desktop entry and icon directories,
+/// so only plain names are accepted: `[A-Za-z0-9._+-]`, but not `.` or `..`
+pub fn check_name(name: &str) -> Result<()> {
+    let plain = !matches!(name, "" | "." | "..")
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '+' | '-'));
+    if plain {
+        Ok(())
+    } else {
+        Err(KhazaurError::AppImage(format!("invalid AppImage name '{}'", name)))
+    }
+}
+
+/// Whether an install argument is an AppImage file or URL rather than a package name
+pub fn is_appimage_ref(arg: &str) -> bool {
+    arg.to_lowercase().ends_with(".appimage")
+}
+
+/// Directory AppImages are installed into (~/Applications unless configured)
+pub fn install_dir(config: &Config) -> Result<PathBuf> {
+    match &config.appimage_dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(dirs::home_dir()
+            .ok_or(KhazaurError::Config("Could not determine home directory".into()))?
+            .join("Applications")),
+    }
+}
+
+/// Name an AppImage is installed under, from its file name:
+/// `Obsidian-1.6.7-x86_64.AppImage` -> `obsidian`
+pub fn name_from_file(file_name: &str) -> String {
+    let stem = strip_extension(file_name);
+    let parts: Vec<&str> = stem
+        .split(['-', '_'])
+        .take_while(|part| !is_version(part) && !is_arch(part))
+        .collect();
+
+    let name = if parts.is_empty() { stem.to_string() } else { parts.join("-") };
+    name.to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '+' { c } else { '-' })
+        .collect()
+}
+
+/// Version in an AppImage file name: `Obsidian-1.6.7-x86_64.AppImage` -> `1.6.7`
+pub fn version_from_file(file_name: &str) -> Option<String> {
+    strip_extension(file_name)
+        .split(['-', '_'])
+        .find(|part| is_version(part))
+        .map(|part| part.trim_start_matches(['v', 'V']).to_string())
+}
+
+fn strip_extension(file_name: &str) -> &str {
+    match file_name.len().checked_sub(".appimage".len()) {
+        Some(end) if file_name.is_char_boundary(end) && is_appimage_ref(file_name) => &file_name[..end],
+        _ => file_name,
+    }
+}
+
+fn is_version(part: &str) -> bool {
+    part.trim_start_matches(['v', 'V']).starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn is_arch(part: &str) -> bool {
+    matches!(part, "x86" | "x64" | "amd64" | "aarch64" | "arm64" | "armhf" | "i386" | "i686")
+}
+
+/// Last path segment of a URL or path, without a query string
+fn file_name_of(reference: &str) -> &str {
+    let path = reference.split(['?', '#']).next().unwrap_or(reference);
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Name an install argument (file or URL) will be installed under
+pub fn reference_name(reference: &str) -> String {
+    name_from_file(file_name_of(reference))
+}
+
+/// Search the catalog by name and description
+pub async fn search(query: &str, config: &Config) -> Result<Vec<AppImagePackage>> {
+    let query = query.to_lowercase();
+    Ok(catalog::load(config, false)
+        .await?
+        .into_iter()
+        .filter(|app| app.name.to_lowercase().contains(&query) || app.description.to_lowercase().contains(&query))
+        .collect())
+}
+
+/// Version and SHA-1 of an installed AppImage
+pub fn installed_version(name: &str) -> Option<(String, String)> {
+    let db = AppImageDb::load().ok()?;
+    let app = db.get(name)?;
+    Some((app.version.clone(), app.sha1.clone()))
+}
+
+/// Install an AppImage from a local file or an http(s) URL
+pub async fn install_ref(reference: &str, config: &Config) -> Result<InstalledAppImage> {
+    let origin = Origin {
+        name: reference_name(reference),
+        version: None,
+        source_url: reference,
+    };
+
+    if reference.starts_with("http://") || reference.starts_with("https://") {
+        let path = download(reference, file_name_of(reference), None, None).await?;
+        return install_file(&path, origin, config);
+    }
+
+    let path = Path::new(reference.strip_prefix("file://").unwrap_or(reference));
+    let source_url = std::fs::canonicalize(path)?.to_string_lossy().into_owned();
+    install_file(path, Origin { source_url: &source_url, ..origin }, config)
+}
+
+/// Install an AppImage from the catalog, or a newer build found by `get_updates`
+pub async fn install_package(package: &AppImagePackage, config: &Config) -> Result<InstalledAppImage> {
+    let path = download(&package.url, file_name_of(&package.url), package.sha1.as_deref(), package.sha256.as_deref()).await?;
+    let origin = Origin {
+        name: package.name.clone(),
+        version: Some(package.version.clone()),
+        source_url: &package.url,
+    };
+    install_file(&path, origin, config)
+}
+
+/// Download an AppImage into the cache, checking it when a checksum is known
+async fn download(url: &str, file_name: &str, sha1: Option<&str>, sha256: Option<&str>) -> Result<PathBuf> {
+    let cache_dir = crate::dirs::cache_dir()?.join("appimage");
+    std::fs::create_dir_all(&cache_dir)?;
+
+    println!("Downloading {}...", url);
+    let response = crate::net::get(url).await?;
+    if !response.status().is_success() {
+        return Err(KhazaurError::DownloadFailed(format!("{}: HTTP {}", url, response.status())));
+    }
+    let data = response.bytes().await?;
+
+    if let Some(expected) = sha256 {
+        crate::debian::verify::check_sha256(file_name, &data, expected, None)?;
+    }
+    if let Some(expected) = sha1 {
+        let actual = update::sha1_hex(&data);
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(KhazaurError::Verification(format!(
+                "{}: SHA-1 mismatch, expected {}, got {}",
+                file_name, expected, actual
+            )));
+        }
+    }
+
+    let path = cache_dir.join(file_name);
+    std::fs::write(&path, &data)?;
+    Ok(path)
+}
+
+/// Copy an AppImage into the managed directory, register its desktop entry and
+/// icon and record it. An installed AppImage of the same name is replaced.
+fn install_file(file: &Path, origin: Origin, config: &Config) -> Result<InstalledAppImage> {
+    use std::os::unix::fs::PermissionsExt;
+
+    check_name(&origin.name)?;
+    check_magic(file)?;
+
+    let dir = install_dir(config)?;
+    std::fs::create_dir_all(&dir)?;
+    let target = dir.join(format!("{}.AppImage", origin.name));
+    let staging = dir.join(format!(".{}.AppImage.part", origin.name));
+    std::fs::copy(file, &staging)?;
+    std::fs::set_permissions(&staging, std::fs::Permissions::from_mode(0o755))?;
+
+    let extracted = match desktop::extract(&staging) {
+        Ok(extracted) => extracted,
+        Err(e) => {
+            let _ = std::fs::remove_file(&staging);
+            return Err(e);
+        }
+    };
+    std::fs::rename(&staging, &target)?;
+
+    // An earlier install may have used another directory or icon format
+    let previous = AppImageDb::load()?.get(&origin.name).cloned();
+
+    let (desktop_file, icon) = match &extracted {
+        Some(extracted) => {
+            let (desktop_file, icon) = desktop::register(&origin.name, &target, extracted)?;
+            (Some(desktop_file), icon)
+        }
+        None => {
+            tracing::warn!("{} has no desktop entry", origin.name);
+            (None, None)
+        }
+    };
+
+    let version = origin
+        .version
+        .or_else(|| {
+            let extracted = extracted.as_ref()?;
+            desktop::entry_value(&extracted.desktop, "X-AppImage-Version").map(str::to_string)
+        })
+        .or_else(|| version_from_file(file_name_of(origin.source_url)))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    if let Some(previous) = previous {
+        if previous.path != target {
+            let _ = std::fs::remove_file(&previous.path);
+        }
+        if previous.icon.is_some() && previous.icon != icon {
+            desktop::unregister(None, previous.icon.as_deref());
+        }
+    }
+
+    let update_info = update::read_update_info(&target)?;
+    let sha1 = update::sha1_file(&target)?;
+    let app = InstalledAppImage {
+        name: origin.name,
+        version,
+        source_url: origin.source_url.to_string(),
+        path: target,
+        desktop_file,
+        icon,
+        update_info,
+        sha1,
+        installed_at: chrono::Local::now().to_rfc3339(),
+    };
+
+    db::record(app.clone())?;
+    Ok(app)
+}
+
+/// AppImages are ELF files with `AI` and the image type at offset 8
+fn check_magic(file: &Path) -> Result<()> {
+    use std::io::Read;
+
+    let mut header = [0u8; 11];
+    std::fs::File::open(file)?.read_exact(&mut header).ok();
+    match &header {
+        [0x7f, b'E', b'L', b'F', _, _, _, _, b'A', b'I', 2] => Ok(()),
+        [0x7f, b'E', b'L', b'F', _, _, _, _, b'A', b'I', 1] => Err(KhazaurError::AppImage(format!(
+            "{} is a type 1 AppImage, only type 2 AppImages are supported",
+            file.display()
+        ))),
+        _ => Err(KhazaurError::AppImage(format!("{} is not an AppImage", file.display()))),
+    }
+}
+
+/// Remove an installed AppImage with its desktop entry and icon
+pub fn uninstall(name: &str) -> Result<()> {
+    check_name(name)?;
+    let mut db = AppImageDb::load()?;
+    let app = db
+        .remove(name)
+        .ok_or_else(|| KhazaurError::PackageNotFound(format!("AppImage {}", name)))?;
+
+    if let Err(e) = std::fs::remove_file(&app.path)
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        return Err(e.into());
+    }
+    desktop::unregister(app.desktop_file.as_deref(), app.icon.as_deref());
+
+    db.save()
+}
+
+/// Find newer builds of installed AppImages. Embedded zsync update information is
+/// used when there is any; otherwise, or when it fails, the catalog version.
+/// Returns the installed version with the newer build.
+pub async fn get_updates(config: &Config) -> Result<Vec<(String, AppImagePackage)>> {
+    let db = AppImageDb::load()?;
+    if db.apps().next().is_none() {
+        return Ok(Vec::new());
+    }
+    let catalog = catalog::load(config, false).await.unwrap_or_else(|e| {
+        tracing::warn!("Failed to load the AppImage catalog: {}", e);
+        Vec::new()
+    });
+
+    let mut updates = Vec::new();
+    for app in db.apps() {
+        if let Some(info) = app.update_info.as_deref().and_then(update::UpdateInfo::parse) {
+            match update::latest_release(&info).await {
+                Ok(release) => {
+                    if !release.sha1.eq_ignore_ascii_case(&app.sha1) {
+                        let new_version = version_from_file(&release.file_name)
+                            .unwrap_or_else(|| release.sha1.chars().take(12).collect());
+                        updates.push((
+                            app.version.clone(),
+                            AppImagePackage {
+                                name: app.name.clone(),
+                                version: new_version,
+                                description: String::new(),
+                                url: release.url,
+                                sha256: None,
+                                sha1: Some(release.sha1),
+                            },
+                        ));
+                    }
+                    continue;
+                }
+                Err(e) => tracing::debug!("Update check for {} failed: {}", app.name, e),
+            }
+        }
+
+        if let Some(entry) = catalog.iter().find(|entry| entry.name == app.name)
+            && crate::pacman::vercmp(&entry.version, &app.version) == Ordering::Greater
+        {
+            updates.push((app.version.clone(), entry.clone()));
+        }
+    }
+
+    Ok(updates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_names_from_files() {
+        assert_eq!(name_from_file("Obsidian-1.6.7-x86_64.AppImage"), "obsidian");
+        assert_eq!(name_from_file("appimagetool-x86_64.AppImage"), "appimagetool");
+        assert_eq!(name_from_file("Visual_Studio_Code-v1.90.appimage"), "visual-studio-code");
+        assert_eq!(name_from_file("1password.AppImage"), "1password");
+        assert_eq!(version_from_file("Obsidian-1.6.7-x86_64.AppImage").as_deref(), Some("1.6.7"));
+        assert_eq!(version_from_file("Tool-v2.0.AppImage").as_deref(), Some("2.0"));
+        assert_eq!(version_from_file("Tool.AppImage"), None);
+        assert_eq!(reference_name("https://example.com/dl/Tool-2.0.AppImage?raw=1"), "tool");
+    }
+
+    #[test]
+    fn test_check_name() {
+        assert!(check_name("obsidian").is_ok());
+        assert!(check_name("Tool_2.0+git-1").is_ok());
+        for name in ["", ".", "..", "../../.config/autostart/x", "a/b", "a b"] {
+            assert!(check_name(name).is_err(), "{:?} accepted", name);
+        }
+    }
+}
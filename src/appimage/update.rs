@@ -0,0 +1,334 @@
+use crate::error::{KhazaurError, Result};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Where a newer build of an AppImage can be found, from its update information
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateInfo {
+    /// `zsync|<url of the .zsync file>`
+    Zsync(String),
+    /// `gh-releases-zsync|<owner>|<repo>|<tag or latest>|<.zsync file name pattern>`
+    GithubReleases {
+        owner: String,
+        repo: String,
+        tag: String,
+        pattern: String,
+    },
+}
+
+impl UpdateInfo {
+    /// Parse the update information string; other transports aren't supported
+    pub fn parse(info: &str) -> Option<Self> {
+        let fields: Vec<&str> = info.trim().split('|').collect();
+        match fields.as_slice() {
+            ["zsync", url] => Some(UpdateInfo::Zsync(url.to_string())),
+            ["gh-releases-zsync", owner, repo, tag, pattern] => Some(UpdateInfo::GithubReleases {
+                owner: owner.to_string(),
+                repo: repo.to_string(),
+                tag: tag.to_string(),
+                pattern: pattern.to_string(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The build a zsync file describes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub file_name: String,
+    /// Absolute URL of the AppImage
+    pub url: String,
+    pub sha1: String,
+}
+
+/// Read the update information from the `.upd_info` ELF section of an AppImage
+pub fn read_update_info(path: &Path) -> Result<Option<String>> {
+    let mut file = std::fs::File::open(path)?;
+    let Some(section) = find_section(&mut file, ".upd_info")? else {
+        return Ok(None);
+    };
+
+    let info = String::from_utf8_lossy(&section);
+    let info = info.trim_end_matches('\0').trim();
+    Ok((!info.is_empty()).then(|| info.to_string()))
+}
+
+/// Contents of a named section of a little-endian ELF file
+fn find_section<R: Read + Seek>(file: &mut R, name: &str) -> Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 64];
+    if file.read_exact(&mut header).is_err() || &header[..4] != b"\x7fELF" || header[5] != 1 {
+        return Ok(None);
+    }
+
+    let is_64 = header[4] == 2;
+    let (offset, entry_size, count, names_index) = if is_64 {
+        (le(&header[0x28..0x30]), le(&header[0x3a..0x3c]), le(&header[0x3c..0x3e]), le(&header[0x3e..0x40]))
+    } else {
+        (le(&header[0x20..0x24]), le(&header[0x2e..0x30]), le(&header[0x30..0x32]), le(&header[0x32..0x34]))
+    };
+    // A section header needs at least the fields up to sh_size
+    let min_entry_size = if is_64 { 0x28 } else { 0x18 };
+    if offset == 0 || entry_size < min_entry_size || count == 0 || count > 4096 || names_index >= count {
+        return Ok(None);
+    }
+
+    let table = read_at(file, offset, entry_size * count)?;
+
+    // (name offset, file offset, size) of each section header
+    let sections: Vec<(u64, u64, u64)> = table
+        .chunks_exact(entry_size as usize)
+        .map(|entry| {
+            if is_64 {
+                (le(&entry[0..4]), le(&entry[0x18..0x20]), le(&entry[0x20..0x28]))
+            } else {
+                (le(&entry[0..4]), le(&entry[0x10..0x14]), le(&entry[0x14..0x18]))
+            }
+        })
+        .collect();
+
+    let Some(&(_, names_offset, names_size)) = sections.get(names_index as usize) else {
+        return Ok(None);
+    };
+    let names = read_at(file, names_offset, names_size)?;
+    for &(name_offset, offset, size) in &sections {
+        let Some(rest) = names.get(name_offset as usize..) else {
+            continue;
+        };
+        let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        if &rest[..end] == name.as_bytes() {
+            return read_at(file, offset, size).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_at<R: Read + Seek>(file: &mut R, offset: u64, size: u64) -> Result<Vec<u8>> {
+    if size > 16 * 1024 * 1024 {
+        return Err(KhazaurError::AppImage("ELF section too large".to_string()));
+    }
+    let len = file.seek(SeekFrom::End(0))?;
+    if offset.checked_add(size).is_none_or(|end| end > len) {
+        return Err(KhazaurError::AppImage("ELF section lies outside the file".to_string()));
+    }
+    let mut data = vec![0u8; size as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Little-endian integer of up to 8 bytes
+fn le(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &b| (value << 8) | b as u64)
+}
+
+/// SHA-1 of a file as lowercase hex, the checksum zsync files carry
+pub fn sha1_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        context.update(&buffer[..read]);
+    }
+    Ok(hex(context.finish().as_ref()))
+}
+
+pub fn sha1_hex(data: &[u8]) -> String {
+    hex(ring::digest::digest(&ring::digest::SHA1_FOR_LEGACY_USE_ONLY, data).as_ref())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Find the newest build the update information points at
+pub async fn latest_release(info: &UpdateInfo) -> Result<Release> {
+    let zsync_url = match info {
+        UpdateInfo::Zsync(url) => url.clone(),
+        UpdateInfo::GithubReleases { owner, repo, tag, pattern } => {
+            github_asset(owner, repo, tag, pattern).await?
+        }
+    };
+
+    let response = crate::net::get(&zsync_url).await?;
+    if !response.status().is_success() {
+        return Err(KhazaurError::DownloadFailed(format!("{}: HTTP {}", zsync_url, response.status())));
+    }
+    // The header ends at the first empty line, the block checksums after it are binary
+    let data = response.bytes().await?;
+    let end = data.windows(2).position(|w| w == b"\n\n").unwrap_or(data.len());
+    parse_zsync_header(&String::from_utf8_lossy(&data[..end]), &zsync_url)
+}
+
+/// URL of the release asset matching a file name pattern
+async fn github_asset(owner: &str, repo: &str, tag: &str, pattern: &str) -> Result<String> {
+    let url = match tag {
+        "latest" => format!("https://api.github.com/repos/{}/{}/releases/latest", owner, repo),
+        tag => format!("https://api.github.com/repos/{}/{}/releases/tags/{}", owner, repo, tag),
+    };
+
+    let response = crate::net::get(&url).await?;
+    if !response.status().is_success() {
+        return Err(KhazaurError::DownloadFailed(format!("{}: HTTP {}", url, response.status())));
+    }
+    let release: serde_json::Value = response.json().await?;
+
+    release["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|asset| asset["name"].as_str().is_some_and(|name| glob_match(pattern, name)))
+        .and_then(|asset| asset["browser_download_url"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| KhazaurError::AppImage(format!("No asset matching {} in {}/{} {}", pattern, owner, repo, tag)))
+}
+
+/// Parse the header of a zsync file. The URL in it is relative to the zsync file.
+fn parse_zsync_header(header: &str, zsync_url: &str) -> Result<Release> {
+    let field = |key: &str| {
+        header.lines().find_map(|line| {
+            let (k, value) = line.split_once(':')?;
+            (k.trim() == key).then(|| value.trim().to_string())
+        })
+    };
+
+    let missing = |key: &str| KhazaurError::AppImage(format!("{}: no {} in zsync header", zsync_url, key));
+    let file_name = field("Filename").ok_or_else(|| missing("Filename"))?;
+    let sha1 = field("SHA-1").ok_or_else(|| missing("SHA-1"))?.to_lowercase();
+    let relative = field("URL").unwrap_or_else(|| file_name.clone());
+
+    let url = reqwest::Url::parse(zsync_url)
+        .and_then(|base| base.join(&relative))
+        .map_err(|e| KhazaurError::AppImage(format!("{}: bad URL {}: {}", zsync_url, relative, e)))?;
+
+    Ok(Release {
+        file_name,
+        url: url.to_string(),
+        sha1,
+    })
+}
+
+/// Match a file name against a pattern with `*` and `?` wildcards
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p + 1, n));
+            p += 1;
+        } else if let Some((star, tried)) = backtrack {
+            p = star;
+            n = tried + 1;
+            backtrack = Some((star, tried + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_info() {
+        assert_eq!(
+            UpdateInfo::parse("gh-releases-zsync|owner|app|latest|App-*-x86_64.AppImage.zsync"),
+            Some(UpdateInfo::GithubReleases {
+                owner: "owner".to_string(),
+                repo: "app".to_string(),
+                tag: "latest".to_string(),
+                pattern: "App-*-x86_64.AppImage.zsync".to_string(),
+            })
+        );
+        assert_eq!(UpdateInfo::parse("pling-v1-zsync|1234"), None);
+
+        assert!(glob_match("App-*-x86_64.AppImage.zsync", "App-1.2.3-x86_64.AppImage.zsync"));
+        assert!(glob_match("*.zsync", ".zsync"));
+        assert!(!glob_match("App-*-x86_64.AppImage.zsync", "App-1.2.3-aarch64.AppImage.zsync"));
+        assert!(glob_match("a?c*", "abcdef"));
+
+        let header = "zsync: 0.6.2\nFilename: App-1.2.3-x86_64.AppImage\nMTime: Tue, 01 Oct 2024 10:00:00 +0000\n\
+            Blocksize: 2048\nLength: 1000\nHash-Lengths: 2,2,5\nURL: App-1.2.3-x86_64.AppImage\n\
+            SHA-1: 0123456789ABCDEF0123456789abcdef01234567";
+        let release = parse_zsync_header(header, "https://example.com/releases/App-latest.AppImage.zsync").unwrap();
+        assert_eq!(release.url, "https://example.com/releases/App-1.2.3-x86_64.AppImage");
+        assert_eq!(release.sha1, "0123456789abcdef0123456789abcdef01234567");
+        assert!(parse_zsync_header("zsync: 0.6.2", "https://example.com/a.zsync").is_err());
+    }
+
+    /// A 64-bit ELF file with an `.upd_info` section and the section name table
+    fn elf_with_update_info(info: &str) -> Vec<u8> {
+        let names = b"\0.upd_info\0.shstrtab\0";
+        let info_offset = 64u64;
+        let names_offset = info_offset + info.len() as u64;
+        let table_offset = names_offset + names.len() as u64;
+
+        let mut elf = vec![0u8; 64];
+        elf[..4].copy_from_slice(b"\x7fELF");
+        elf[4] = 2;
+        elf[5] = 1;
+        elf[0x28..0x30].copy_from_slice(&table_offset.to_le_bytes());
+        elf[0x3a..0x3c].copy_from_slice(&0x40u16.to_le_bytes());
+        elf[0x3c..0x3e].copy_from_slice(&3u16.to_le_bytes());
+        elf[0x3e..0x40].copy_from_slice(&2u16.to_le_bytes());
+        elf.extend_from_slice(info.as_bytes());
+        elf.extend_from_slice(names);
+
+        for (name, offset, size) in [(0u32, 0, 0), (1, info_offset, info.len() as u64), (11, names_offset, names.len() as u64)] {
+            let mut entry = [0u8; 0x40];
+            entry[..4].copy_from_slice(&name.to_le_bytes());
+            entry[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+            entry[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+            elf.extend_from_slice(&entry);
+        }
+        elf
+    }
+
+    fn section(elf: &[u8]) -> Result<Option<Vec<u8>>> {
+        find_section(&mut std::io::Cursor::new(elf), ".upd_info")
+    }
+
+    #[test]
+    fn test_find_section() {
+        let info = "zsync|https://example.com/App.AppImage.zsync";
+        let elf = elf_with_update_info(info);
+        assert_eq!(section(&elf).unwrap(), Some(info.as_bytes().to_vec()));
+
+        // Truncated header
+        assert_eq!(section(&elf[..40]).unwrap(), None);
+
+        // Section headers too small to hold an offset and size
+        let mut small_entries = elf.clone();
+        small_entries[0x3a..0x3c].copy_from_slice(&0x10u16.to_le_bytes());
+        assert_eq!(section(&small_entries).unwrap(), None);
+
+        // Name table index past the section headers
+        let mut bad_names = elf.clone();
+        bad_names[0x3e..0x40].copy_from_slice(&7u16.to_le_bytes());
+        assert_eq!(section(&bad_names).unwrap(), None);
+
+        // Section header table cut off at the end of the file
+        assert!(section(&elf[..elf.len() - 8]).is_err());
+
+        // Section contents pointing past the end of the file
+        let mut bad_offset = elf.clone();
+        let entry = elf.len() - 2 * 0x40;
+        bad_offset[entry + 0x18..entry + 0x20].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(section(&bad_offset).is_err());
+    }
+}
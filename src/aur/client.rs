@@ -1,36 +1,49 @@
 use crate::aur::package::{AurPackage, AurResponse};
 use crate::error::{KhazaurError, Result};
+use crate::net::Settings;
 use reqwest::Client;
-use std::time::Duration;
-
-const AUR_RPC_URL: &str = "https://aur.archlinux.org/rpc/v5";
-const AUR_URL: &str = "https://aur.archlinux.org";
 
 /// AUR RPC API client
 pub struct AurClient {
     client: Client,
     rate_limiter: super::rate_limit::RateLimiter,
+    base_url: String,
+    retry_config: super::retry::RetryConfig,
 }
 
 impl AurClient {
-    /// Create a new AUR client
+    /// Create an AUR client from the loaded configuration
     pub fn new() -> Result<Self> {
-        Self::with_rate_limit(10, 100) // Default: 10 concurrent, 100ms delay
+        Self::from_settings(crate::net::settings())
     }
 
-    /// Create AUR client with custom rate limiting
-    pub fn with_rate_limit(max_concurrent: usize, delay_ms: u64) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent(format!("khazaur/{}", env!("CARGO_PKG_VERSION")))
-            .build()?;
-        
-        Ok(Self { 
-            client,
-            rate_limiter: super::rate_limit::RateLimiter::new(max_concurrent, delay_ms),
+    /// Create an AUR client with the given URL, rate limits, retries and HTTP client settings
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        Ok(Self {
+            client: settings.build_client()?,
+            rate_limiter: super::rate_limit::RateLimiter::new(
+                settings.max_concurrent_requests,
+                settings.request_delay_ms,
+            ),
+            base_url: settings.aur_url.clone(),
+            retry_config: settings.retry.clone(),
         })
     }
 
+    fn rpc_url(&self) -> String {
+        format!("{}/rpc/v5", self.base_url)
+    }
+
+    /// Git URL of a package base
+    pub fn git_url(&self, pkgbase: &str) -> String {
+        format!("{}/{}.git", self.base_url, pkgbase)
+    }
+
+    /// Web page of a package
+    pub fn package_url(&self, package_name: &str) -> String {
+        format!("{}/packages/{}", self.base_url, package_name)
+    }
+
     /// Search for packages matching a query
     pub async fn search(&self, query: &str) -> Result<Vec<AurPackage>> {
         if query.len() < 2 {
@@ -42,9 +55,7 @@ impl AurClient {
         // Acquire rate limit
         let _guard = self.rate_limiter.acquire().await;
 
-        let url = format!("{}/search/{}", AUR_RPC_URL, query);
-        let retry_config = super::retry::RetryConfig::default();
-        
+        let url = format!("{}/search/{}", self.rpc_url(), query);
         let response = super::retry::retry_request(
             || {
                 let client = self.client.clone();
@@ -53,7 +64,7 @@ impl AurClient {
                     client.get(&url).send().await
                 }
             },
-            &retry_config,
+            &self.retry_config,
         )
         .await
         .map_err(|e| KhazaurError::AurApi(format!("Search failed after retries: {}", e)))?;
@@ -75,9 +86,7 @@ impl AurClient {
         // Acquire rate limit
         let _guard = self.rate_limiter.acquire().await;
 
-        let url = format!("{}/info/{}", AUR_RPC_URL, package_name);
-        let retry_config = super::retry::RetryConfig::default();
-        
+        let url = format!("{}/info/{}", self.rpc_url(), package_name);
         let response = super::retry::retry_request(
             || {
                 let client = self.client.clone();
@@ -86,7 +95,7 @@ impl AurClient {
                     client.get(&url).send().await
                 }
             },
-            &retry_config,
+            &self.retry_config,
         )
         .await
         .map_err(|e| KhazaurError::AurApi(format!("Info query failed after retries: {}", e)))?;
@@ -125,7 +134,7 @@ impl AurClient {
 
             // Build URL with proper query parameters
             // Format: https://aur.archlinux.org/rpc/v5/info?arg[]=pkg1&arg[]=pkg2
-            let mut url = format!("{}/info", AUR_RPC_URL);
+            let mut url = format!("{}/info", self.rpc_url());
             let mut first = true;
             for pkg in chunk {
                 if first {
@@ -137,8 +146,6 @@ impl AurClient {
                 url.push_str(&format!("arg[]={}", urlencoding::encode(pkg)));
             }
 
-            let retry_config = super::retry::RetryConfig::default();
-            
             let response = super::retry::retry_request(
                 || {
                     let client = self.client.clone();
@@ -147,7 +154,7 @@ impl AurClient {
                         client.get(&url).send().await
                     }
                 },
-                &retry_config,
+                &self.retry_config,
             )
             .await
             .map_err(|e| KhazaurError::AurApi(format!("Batch info query failed after retries: {}", e)))?;
@@ -189,15 +196,13 @@ impl AurClient {
 
     /// Get the snapshot URL for a package
     pub fn snapshot_url(&self, package_name: &str) -> String {
-        format!("{}/cgit/aur.git/snapshot/{}.tar.gz", AUR_URL, package_name)
+        format!("{}/cgit/aur.git/snapshot/{}.tar.gz", self.base_url, package_name)
     }
 
     /// Download package bytes (tarball)
     pub async fn download_snapshot(&self, package_name: &str) -> Result<Vec<u8>> {
         let url = self.snapshot_url(package_name);
         
-        let retry_config = super::retry::RetryConfig::default();
-        
         let response = super::retry::retry_request(
             || {
                 let client = self.client.clone();
@@ -206,7 +211,7 @@ impl AurClient {
                     client.get(&url).send().await
                 }
             },
-            &retry_config,
+            &self.retry_config,
         )
         .await
         .map_err(|e| KhazaurError::DownloadFailed(
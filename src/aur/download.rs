@@ -17,7 +17,7 @@ pub async fn download_package(
     
     // Try git clone if enabled
     if config.use_git_clone {
-        match try_git_download(package_name, &pkg_dir).await {
+        match try_git_download(client, package_name, &pkg_dir).await {
             Ok(dir) => return Ok(dir),
             Err(e) => {
                 // Check if it's a permission error - don't fall back to tarball
@@ -33,8 +33,8 @@ pub async fn download_package(
     download_tarball(client, package_name, config).await
 }
 
-async fn try_git_download(package_name: &str, pkg_dir: &PathBuf) -> Result<PathBuf> {
-    let url = format!("https://aur.archlinux.org/{}.git", package_name);
+async fn try_git_download(client: &AurClient, package_name: &str, pkg_dir: &PathBuf) -> Result<PathBuf> {
+    let url = client.git_url(package_name);
     
     if pkg_dir.exists() {
         // Check if it's a git repository
@@ -46,7 +46,7 @@ async fn try_git_download(package_name: &str, pkg_dir: &PathBuf) -> Result<PathB
                     // Fetch and reset to latest
                     match repo.find_remote("origin") {
                         Ok(mut remote) => {
-                            if let Err(e) = remote.fetch(&["refs/heads/*:refs/heads/*"], None, None) {
+                            if let Err(e) = remote.fetch(&["refs/heads/*:refs/heads/*"], Some(&mut crate::net::fetch_options()), None) {
                                 warn!("Failed to fetch updates: {}, will use existing version", e);
                             } else {
                                 // Reset to origin/master or origin/main
@@ -105,7 +105,7 @@ async fn try_git_download(package_name: &str, pkg_dir: &PathBuf) -> Result<PathB
     }
     
     // Clone repository
-    Repository::clone(&url, pkg_dir)
+    crate::net::clone_repo(&url, pkg_dir)
         .map_err(|e| KhazaurError::DownloadFailed(format!("Git clone failed: {}", e)))?;
     
     Ok(pkg_dir.clone())
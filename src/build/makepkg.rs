@@ -63,7 +63,7 @@ pub fn build_and_install(package_dir: &Path, install: bool) -> Result<()> {
                         // Check if dependency is already installed
                         if !is_package_installed(dep)? {
                             eprintln!("Installing dependency: {}", dep);
-                            match crate::pacman::install_packages(&[dep.clone()], &vec!["--noconfirm".to_string()]) {
+                            match crate::pacman::install_packages(&[dep.clone()], &["--noconfirm".to_string(), "--asdeps".to_string()]) {
                                 Ok(()) => {
                                     info!("Dependency {} installed successfully", dep);
                                 }
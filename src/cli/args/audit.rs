@@ -0,0 +1,73 @@
+use crate::config::Config;
+use crate::error::Result;
+use crate::security;
+use crate::ui;
+use colored::Colorize;
+use std::collections::HashMap;
+
+/// Report open security advisories for installed packages
+pub async fn audit(config: &Config) -> Result<()> {
+    println!("{}", ui::section_header("Security Audit"));
+
+    let spinner = ui::spinner("Fetching security tracker data...");
+    let advisories = security::fetch(&config.security_tracker_url).await;
+    spinner.finish_and_clear();
+    let advisories = advisories?;
+
+    let installed = crate::pacman::get_installed_packages()?;
+    let findings = security::find_affected(&advisories, &installed);
+
+    if findings.is_empty() {
+        println!("{}", ui::success("No known vulnerabilities affect installed packages"));
+        return Ok(());
+    }
+
+    // Pending updates tell us whether a fix can be installed right now
+    let updates: HashMap<String, String> = crate::pacman::get_repo_updates()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, _, new_ver)| (name, new_ver))
+        .collect();
+
+    let mut fixable = 0;
+    for finding in &findings {
+        let advisory = finding.advisory;
+
+        println!("\n{} {} {}",
+            "::".bright_blue().bold(),
+            finding.package.bold(),
+            finding.installed_version.dimmed()
+        );
+        println!("   {} [{}] {}", advisory.name.bold(), advisory.severity_colored(), advisory.kind);
+
+        match advisory.fixed.as_deref().filter(|f| !f.is_empty()) {
+            Some(fixed) => {
+                let available = updates
+                    .get(&finding.package)
+                    .is_some_and(|new_ver| advisory.is_fixed_in(new_ver));
+                if available {
+                    fixable += 1;
+                    println!("   {} {} {}", "Fixed in:".dimmed(), fixed.green(), "(update available)".bright_green());
+                } else {
+                    println!("   {} {}", "Fixed in:".dimmed(), fixed.green());
+                }
+            }
+            None => println!("   {} {}", "Fixed in:".dimmed(), "not fixed yet".red()),
+        }
+
+        if !advisory.issues.is_empty() {
+            println!("   {} {}", "Issues:".dimmed(), advisory.issues.join(", "));
+        }
+    }
+
+    println!("\n{} {} affected package(s), {} fixable by upgrading",
+        "::".bright_blue().bold(),
+        findings.len(),
+        fixable
+    );
+    if fixable > 0 {
+        println!("{}", ui::info("Run 'khazaur -Syu' to install available fixes"));
+    }
+
+    Ok(())
+}
@@ -1,9 +1,10 @@
+use crate::cli::backend;
+use crate::cli::SourceKind;
 use crate::error::Result;
 use crate::ui;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
-use std::process::Command;
 
 pub fn backup(path: &PathBuf) -> Result<()> {
     println!("{}", ui::section_header("System Backup"));
@@ -41,69 +42,31 @@ pub fn backup(path: &PathBuf) -> Result<()> {
     
     println!("{}", ui::info(&format!("Backing up package list to {:?}", final_path)));
 
-    // Get native packages (explicitly installed)
-    let native_out = Command::new("pacman")
-        .args(["-Q", "-q", "-e", "-n"]) // Query, quiet, explicit, native
-        .output()?;
-    
-    // Get foreign packages (AUR, explicit)
-    let foreign_out = Command::new("pacman")
-        .args(["-Q", "-q", "-e", "-m"])
-        .output()?;
-        
-    let native = String::from_utf8_lossy(&native_out.stdout);
-    let foreign = String::from_utf8_lossy(&foreign_out.stdout);
-    
     let mut file = File::create(&final_path)?;
     
     writeln!(file, "# Khazaur Package Backup")?;
     writeln!(file, "# Created: {}", chrono::Local::now().to_rfc3339())?;
-    writeln!(file, "")?;
-    
-    writeln!(file, "# Native Packages")?;
-    for line in native.lines() {
-        writeln!(file, "{}", line)?;
-    }
     
-    writeln!(file, "")?;
-    writeln!(file, "# Foreign/AUR Packages")?;
-    for line in foreign.lines() {
-        writeln!(file, "{}", line)?;
-    }
-    
-    // Flatpak
-    if Command::new("which").arg("flatpak").output().map(|o| o.status.success()).unwrap_or(false) {
-        println!("{}", ui::info("Backing up Flatpak packages..."));
-        let flatpak_out = Command::new("flatpak")
-            .args(["list", "--app", "--columns=application"])
-            .output()?;
-        let flatpaks = String::from_utf8_lossy(&flatpak_out.stdout);
+    // Explicitly installed packages of every source, a section each
+    for backend in backend::all(false)? {
+        let packages = match backend.list_installed() {
+            Ok(packages) => packages,
+            Err(e) => {
+                eprintln!("{}", ui::warning(&format!("Skipping {}: {}", backend.title(), e)));
+                continue;
+            }
+        };
+        if packages.is_empty() {
+            continue;
+        }
+        println!("{}", ui::info(&format!("Backing up {}...", backend.title())));
         
-        writeln!(file, "")?;
-        writeln!(file, "# Flatpak Packages")?;
-        for line in flatpaks.lines() {
-            writeln!(file, "{}", line)?;
+        writeln!(file)?;
+        writeln!(file, "# {} ({})", backend.title(), backend.prefix())?;
+        for pkg in packages.iter().filter(|pkg| !pkg.dependency) {
+            writeln!(file, "{}", pkg.name)?;
         }
     }
-
-    // Snap
-    if Command::new("which").arg("snap").output().map(|o| o.status.success()).unwrap_or(false) {
-         println!("{}", ui::info("Backing up Snap packages..."));
-         // Snap list output is table, we need first column, skip header
-         let snap_out = Command::new("snap")
-            .arg("list")
-            .output()?;
-         let output_str = String::from_utf8_lossy(&snap_out.stdout);
-         
-         writeln!(file, "")?;
-         writeln!(file, "# Snap Packages")?;
-         // Skip first line (header)
-         for line in output_str.lines().skip(1) {
-             if let Some(name) = line.split_whitespace().next() {
-                 writeln!(file, "{}", name)?;
-             }
-         }
-    }
     
     println!("{}", ui::success(&format!("Backup created successfully at {:?}", final_path)));
     Ok(())
@@ -120,22 +83,8 @@ pub async fn restore(path: &PathBuf) -> Result<()> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     
-    let mut repo_packages = Vec::new();
-    let mut aur_packages = Vec::new();
-    let mut flatpak_packages = Vec::new();
-    let mut snap_packages = Vec::new();
-    
-    // State machine for parsing
-    #[derive(PartialEq)]
-    enum Section {
-        None,
-        Repo,
-        Aur,
-        Flatpak,
-        Snap,
-    }
-    
-    let mut current_section = Section::None;
+    let mut sections: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_section = false;
     
     for line in reader.lines() {
         let line = line?;
@@ -146,80 +95,52 @@ pub async fn restore(path: &PathBuf) -> Result<()> {
         }
         
         if trimmed.starts_with('#') {
-            let lower = trimmed.to_lowercase();
-            if lower.contains("flatpak") {
-                current_section = Section::Flatpak;
-            } else if lower.contains("snap") {
-                current_section = Section::Snap;
-            } else if lower.contains("native packages") {
-                current_section = Section::Repo;
-            } else if lower.contains("foreign") || lower.contains("aur") {
-                current_section = Section::Aur;
-            }
+            // Header comments and unknown sources end the current section
+            in_section = match section_source(trimmed) {
+                Some(source) => {
+                    sections.push((source, Vec::new()));
+                    true
+                }
+                None => false,
+            };
             continue;
         }
         
-        match current_section {
-            Section::Repo => repo_packages.push(trimmed.to_string()),
-            Section::Aur => aur_packages.push(trimmed.to_string()),
-            Section::Flatpak => flatpak_packages.push(trimmed.to_string()),
-            Section::Snap => snap_packages.push(trimmed.to_string()),
-            Section::None => {} // Skip header comments before first section
+        if in_section && let Some((_, names)) = sections.last_mut() {
+            names.push(trimmed.to_string());
         }
     }
     
     let mut config = crate::config::Config::load()?;
-    
-    // 1. Install Repo Packages (Native)
-    if !repo_packages.is_empty() {
-        println!("\n{}", ui::section_header(&format!("Restoring {} Repository Packages", repo_packages.len())));
-        
-        // Use pacman directly for native packages, bypassing search
-        match crate::pacman::install_packages(&repo_packages, &Vec::new()) {
-            Ok(_) => println!("{}", ui::success("Repository packages installed")),
-            Err(e) => eprintln!("{}", ui::error(&format!("Failed to install repository packages: {}", e))),
+    let total: usize = sections.iter().map(|(_, names)| names.len()).sum();
+    
+    // Install source by source, in the order of the backends
+    let backends = backend::all(false)?;
+    for backend in &backends {
+        let names: Vec<String> = sections
+            .iter()
+            .filter(|(source, _)| source == backend.prefix())
+            .flat_map(|(_, names)| names.clone())
+            .collect();
+        if names.is_empty() {
+            continue;
         }
-    }
-
-    // 2. Install AUR Packages
-    if !aur_packages.is_empty() {
-        println!("\n{}", ui::section_header(&format!("Restoring {} AUR Packages", aur_packages.len())));
         
-        // Use install_aur_packages directly
-        if let Err(e) = crate::cli::install::install_aur_packages(
-            &aur_packages,
-            &mut config,
-            false, // noconfirm (false = ask? backup restore maybe should be interactive or respected global flag? passing false for now)
-        ).await {
-            eprintln!("{}", ui::error(&format!("Failed to restore AUR packages: {}", e)));
+        println!("{}", ui::section_header(&format!("Restoring {} {}", names.len(), backend.title())));
+        // noconfirm is false: a restore installs a lot, let the user see each step
+        if let Err(e) = backend.install_by_name(&names, &mut config, false).await {
+            eprintln!("{}", ui::error(&format!("Failed to restore {}: {}", backend.title(), e)));
         }
     }
-
-    // 3. Install Flatpak packages
-    if !flatpak_packages.is_empty() {
-        println!("\n{}", ui::section_header(&format!("Restoring {} Flatpak Packages", flatpak_packages.len())));
-        for app_id in &flatpak_packages {
-            if let Err(e) = crate::flatpak::install_flatpak(app_id).await {
-                eprintln!("{}", ui::error(&format!("Failed to install {}: {}", app_id, e)));
-            } else {
-                 println!("{}", ui::success(&format!("{} installed", app_id)));
-            }
+    
+    // Sections of plugins that aren't installed here
+    for (source, names) in &sections {
+        if !names.is_empty() && !backends.iter().any(|backend| backend.prefix() == source) {
+            println!("{}", ui::warning(&format!("Skipping {} package(s) from '{}': no such source", names.len(), source)));
         }
     }
-
-    // 4. Install Snap packages
-    if !snap_packages.is_empty() {
-         println!("\n{}", ui::section_header(&format!("Restoring {} Snap Packages", snap_packages.len())));
-         for name in &snap_packages {
-            if let Err(e) = crate::snap::install_snap(name).await {
-                eprintln!("{}", ui::error(&format!("Failed to install {}: {}", name, e)));
-            } else {
-                println!("{}", ui::success(&format!("{} installed", name)));
-            }
-         }
-    }
     
-    if repo_packages.is_empty() && aur_packages.is_empty() && flatpak_packages.is_empty() && snap_packages.is_empty() {
+    if total == 0 {
         println!("{}", ui::warning("No packages found in backup file"));
     } else {
         println!("\n{}", ui::success("Restore process completed"));
@@ -227,3 +148,26 @@ pub async fn restore(path: &PathBuf) -> Result<()> {
 
     Ok(())
 }
+
+/// Source prefix of a section header: `# AUR Packages (aur)`, or the headers of
+/// older backups (`# Native Packages`, `# Foreign/AUR Packages`, ...)
+fn section_source(header: &str) -> Option<String> {
+    let header = header.trim_start_matches('#').trim();
+    if let Some(label) = header.strip_suffix(')').and_then(|rest| rest.rsplit_once('(')).map(|(_, label)| label) {
+        return Some(label.to_string());
+    }
+
+    let lower = header.to_lowercase();
+    let source = if lower.contains("flatpak") {
+        SourceKind::Flatpak
+    } else if lower.contains("snap") {
+        SourceKind::Snap
+    } else if lower.contains("native packages") {
+        SourceKind::Repo
+    } else if lower.contains("foreign") {
+        SourceKind::Aur
+    } else {
+        return None;
+    };
+    Some(source.label().to_string())
+}
@@ -8,8 +8,7 @@ pub fn clean_cache(clean_level: u8) -> Result<()> {
     println!("{}", ui::section_header("Cleaning Package Cache"));
     
     // Get khazaur cache directory
-    let cache_dir = crate::dirs::cache_dir()?;
-    let clone_dir = cache_dir.join("clone");
+    let clone_dir = crate::dirs::clone_dir()?;
     
     // -cc: Clean pacman cache first
     if clean_level >= 2 {
@@ -63,7 +62,7 @@ pub fn clean_cache(clean_level: u8) -> Result<()> {
                 
                 // Calculate size
                 let size = dir_size(&path).unwrap_or(0);
-                let size_str = format_size(size);
+                let size_str = ui::format_size(size);
                 
                 let confirm = Confirm::with_theme(&ColorfulTheme::default())
                     .with_prompt(format!("Remove '{}' ({})?", name, size_str))
@@ -115,19 +114,3 @@ fn dir_size(path: &std::path::Path) -> std::io::Result<u64> {
     }
     Ok(size)
 }
-
-fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = KB * 1024;
-    const GB: u64 = MB * 1024;
-    
-    if bytes >= GB {
-        format!("{:.2} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.2} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.2} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
-}
@@ -1,101 +1,238 @@
 use crate::ui;
-use crate::config::Config;
-use crate::error::Result;
+use crate::config::{schema, Config, Layers, Origin, SYSTEM_CONFIG_PATH};
+use crate::error::{KhazaurError, Result};
 use clap::Subcommand;
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use std::process::Command;
 
 
 #[derive(Subcommand, Debug, Clone)]
 pub enum ConfigSubcommand {
-    /// List all configuration values
+    /// List all configuration values and where they come from
     List,
     /// Get a specific configuration value
     Get { key: String },
-    /// Set a configuration value
+    /// Set a configuration value in the user's file
     Set { key: String, value: String },
+    /// Remove a value from the user's file, falling back to the system file or default
+    Unset { key: String },
+    /// Open the user's file in an editor and validate it when saved
+    Edit,
+    /// Reset one key, or the whole user's file, to the defaults
+    Reset { key: Option<String> },
+    /// Show the type, default and description of every key, or of one
+    Describe { key: Option<String> },
 }
 
 pub fn handle_config(cmd: &ConfigSubcommand) -> Result<()> {
-    let mut config = Config::load()?;
     let path = Config::config_file_path()?;
 
     match cmd {
         ConfigSubcommand::List => {
-             println!("{}", ui::section_header("Current Configuration"));
-             println!("File: {:?}", path);
-             println!();
-             
-             // Manually print fields for now, or use debug print
-             println!("  {}: {}", "clone_dir", config.clone_dir.display());
-             println!("  {}: {}", "max_concurrent_requests", config.max_concurrent_requests);
-             println!("  {}: {}", "request_delay_ms", config.request_delay_ms);
-             println!("  {}: {:?}", "default_editor", config.default_editor);
-             println!("  {}: {}", "confirm", config.confirm);
-             println!("  {}: {}", "review_pkgbuild", config.review_pkgbuild);
+            let layers = Layers::read()?;
+            let config = layers.resolve()?;
+
+            println!("{}", ui::section_header("Current Configuration"));
+            println!("User file:   {}", path.display());
+            println!("System file: {}", SYSTEM_CONFIG_PATH);
+            println!();
+
+            for key in schema::KEYS {
+                let origin = layers.origin(key.name);
+                let origin = match origin {
+                    Origin::Default => origin.label().dimmed(),
+                    Origin::Env => format!("{} ({})", origin.label(), key.env_var()).yellow(),
+                    _ => origin.label().cyan(),
+                };
+                println!("  {:<32} {}  {}", key.name, display(config.value_of(key.name)), origin);
+            }
         },
         ConfigSubcommand::Get { key } => {
-             // Handle keys
-             let value = match key.as_str() {
-                 "clone_dir" => Some(config.clone_dir.display().to_string()),
-                 "max_concurrent_requests" => Some(config.max_concurrent_requests.to_string()),
-                 "request_delay_ms" => Some(config.request_delay_ms.to_string()),
-                 "default_editor" => Some(format!("{:?}", config.default_editor)),
-                 "confirm" => Some(config.confirm.to_string()),
-                 "review_pkgbuild" => Some(config.review_pkgbuild.to_string()),
-                 _ => None,
-             };
-             
-             if let Some(v) = value {
-                 println!("{}", v);
-             } else {
-                 eprintln!("{}", ui::error(&format!("Unknown config key: {}", key)));
-             }
+            schema::find(key)?;
+            let config = Config::load()?;
+            println!("{}", display(config.value_of(key)));
         },
         ConfigSubcommand::Set { key, value } => {
-             match key.as_str() {
-                 "clone_dir" => {
-                     config.clone_dir = std::path::PathBuf::from(value);
-                 },
-                 "max_concurrent_requests" => {
-                     if let Ok(v) = value.parse() {
-                         config.max_concurrent_requests = v;
-                     } else {
-                         return Err(crate::error::KhazaurError::Config("Invalid number for max_concurrent_requests".into()));
-                     }
-                 },
-                 "request_delay_ms" => {
-                     if let Ok(v) = value.parse() {
-                         config.request_delay_ms = v;
-                     } else {
-                         return Err(crate::error::KhazaurError::Config("Invalid number for request_delay_ms".into()));
-                     }
-                 },
-                 "default_editor" => {
-                     config.default_editor = if value.is_empty() { None } else { Some(value.clone()) };
-                 },
-                 "confirm" => {
-                     if let Ok(v) = value.parse() {
-                         config.confirm = v;
-                     } else {
-                         return Err(crate::error::KhazaurError::Config("Invalid boolean for confirm".into()));
-                     }
-                 },
-                 "review_pkgbuild" => {
-                     if let Ok(v) = value.parse() {
-                         config.review_pkgbuild = v;
-                     } else {
-                         return Err(crate::error::KhazaurError::Config("Invalid boolean for review_pkgbuild".into()));
-                     }
-                 },
-                 _ => {
-                     eprintln!("{}", ui::error(&format!("Unknown config key: {}", key)));
-                     return Ok(());
-                 }
-             }
-             
-             config.save()?;
-             println!("{}", ui::success(&format!("Set '{}' to '{}'", key, value)));
-        }
+            let info = schema::find(key)?;
+            let mut layers = Layers::read()?;
+
+            // An empty optional value means unset
+            if matches!(info.value_type, schema::ValueType::OptionalText | schema::ValueType::Proxy) && value.is_empty() {
+                return unset(key);
+            }
+
+            let parsed = info.parse(value)?;
+            schema::insert(&mut layers.user, key, parsed.clone());
+            layers.resolve()?;
+            crate::config::write_user_table(&layers.user)?;
+
+            println!("{}", ui::success(&format!("Set '{}' to {}", key, parsed)));
+            warn_if_overridden(&layers, key);
+        },
+        ConfigSubcommand::Unset { key } => {
+            schema::find(key)?;
+            unset(key)?;
+        },
+        ConfigSubcommand::Edit => edit()?,
+        ConfigSubcommand::Reset { key: Some(key) } => {
+            schema::find(key)?;
+            unset(key)?;
+        },
+        ConfigSubcommand::Reset { key: None } => {
+            if !path.exists() {
+                println!("{}", ui::info("The user configuration file doesn't exist, nothing to reset"));
+                return Ok(());
+            }
+
+            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("Reset every setting in {}?", path.display()))
+                .default(false)
+                .interact()?;
+            if !confirmed {
+                println!("{}", ui::warning("Reset cancelled"));
+                return Ok(());
+            }
+
+            let backup = path.with_extension("toml.bak");
+            std::fs::rename(&path, &backup)?;
+            println!("{}", ui::success(&format!("Configuration reset, previous file saved as {}", backup.display())));
+        },
+        ConfigSubcommand::Describe { key } => {
+            let keys: Vec<&schema::Key> = match key {
+                Some(key) => vec![schema::find(key)?],
+                None => schema::KEYS.iter().collect(),
+            };
+            let defaults = Config::new()?;
+
+            for key in keys {
+                println!("{} {}", key.name.bold(), format!("<{}>", key.value_type.label()).dimmed());
+                println!("    {}", key.description);
+                if !schema::PATH_KEYS.contains(&key.name) {
+                    println!("    {} {}", "default:".dimmed(), display(defaults.value_of(key.name)));
+                }
+                println!("    {} {}", "env:".dimmed(), key.env_var());
+            }
+        },
+    }
+
+    Ok(())
+}
+
+fn unset(key: &str) -> Result<()> {
+    let mut layers = Layers::read()?;
+    if schema::remove(&mut layers.user, key).is_none() {
+        println!("{}", ui::info(&format!("'{}' is not set in the user configuration", key)));
+        return Ok(());
     }
-    
+
+    crate::config::write_user_table(&layers.user)?;
+    let config = layers.resolve()?;
+    println!("{}", ui::success(&format!(
+        "Unset '{}', now {} ({})",
+        key,
+        display(config.value_of(key)),
+        layers.origin(key).label()
+    )));
+    warn_if_overridden(&layers, key);
     Ok(())
 }
+
+fn warn_if_overridden(layers: &Layers, key: &str) {
+    if layers.origin(key) == Origin::Env {
+        let env_var = schema::find(key).map(|k| k.env_var()).unwrap_or_default();
+        println!("{}", ui::warning(&format!("{} is set and takes precedence", env_var)));
+    }
+}
+
+/// Edit a copy of the user's file and only replace it once the result is valid
+fn edit() -> Result<()> {
+    let path = Config::config_file_path()?;
+    let editor = editor_command()?;
+
+    let draft = tempfile::Builder::new().prefix("khazaur-config").suffix(".toml").tempfile()?;
+    if path.exists() {
+        std::fs::copy(&path, draft.path())?;
+    }
+
+    loop {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(draft.path())
+            .status()?;
+        if !status.success() {
+            return Err(KhazaurError::Config(format!("Editor '{}' failed", editor)));
+        }
+
+        match validate_draft(draft.path()) {
+            Ok(table) => {
+                crate::config::write_user_table(&table)?;
+                println!("{}", ui::success(&format!("Saved {}", path.display())));
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{}", ui::error(&e.to_string()));
+                let again = Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt("Edit again? (otherwise the changes are discarded)")
+                    .default(true)
+                    .interact()?;
+                if !again {
+                    println!("{}", ui::warning("Changes discarded"));
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn validate_draft(draft: &std::path::Path) -> Result<toml::Table> {
+    let layers = Layers::read_with_user(draft)?;
+    layers.resolve()?;
+    Ok(layers.user)
+}
+
+/// The configured editor, $VISUAL, $EDITOR, or one picked from those installed
+fn editor_command() -> Result<String> {
+    // The user file may be what is broken, so don't require it to load
+    let configured = Config::load().ok().and_then(|c| c.default_editor);
+    if let Some(editor) = configured
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|e| !e.is_empty())
+    {
+        return Ok(editor);
+    }
+
+    match ui::select_editor(&ui::detect_editors())? {
+        Some(editor) => Ok(editor.command),
+        None => Err(KhazaurError::Config("No editor selected".to_string())),
+    }
+}
+
+fn display(value: Option<toml::Value>) -> String {
+    match value {
+        Some(toml::Value::String(s)) => s,
+        Some(value) => value.to_string(),
+        None => "(unset)".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_fixes_broken_user_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("config.toml");
+        std::fs::write(&broken, "confirm = \"sometimes\"\n").unwrap();
+        let draft = dir.path().join("draft.toml");
+        std::fs::write(&draft, "confirm = false\n").unwrap();
+
+        assert!(Layers::read_with_user(&broken).is_err());
+        let table = validate_draft(&draft).unwrap();
+        assert_eq!(table.get("confirm"), Some(&toml::Value::Boolean(false)));
+    }
+}
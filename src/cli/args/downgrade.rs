@@ -1,88 +1,356 @@
-use crate::error::Result;
+use crate::aur::AurClient;
+use crate::cli::SourceKind;
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use crate::history::Transaction;
+use crate::pacman::db::PackageDb;
 use crate::ui;
-use std::fs;
-use std::path::PathBuf;
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use git2::Repository;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use colored::Colorize;
 
-pub async fn downgrade(package: &str) -> Result<()> {
+const CACHE_DIR: &str = "/var/cache/pacman/pkg";
+
+/// Name, version and architecture of a package file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageFile {
+    pub name: String,
+    /// Full version: [epoch:]pkgver-pkgrel
+    pub version: String,
+    pub arch: String,
+}
+
+/// Parse `name-pkgver-pkgrel-arch.pkg.tar.*`. Names may contain dashes, the last three
+/// fields never do. Returns None for signatures and anything that isn't a package.
+pub fn parse_package_filename(filename: &str) -> Option<PackageFile> {
+    if filename.ends_with(".sig") {
+        return None;
+    }
+
+    let stem = &filename[..filename.find(".pkg.tar")?];
+    let mut parts = stem.rsplitn(4, '-');
+    let arch = parts.next()?;
+    let pkgrel = parts.next()?;
+    let pkgver = parts.next()?;
+    let name = parts.next()?;
+
+    if name.is_empty() || pkgver.is_empty() || pkgrel.is_empty() || arch.is_empty() {
+        return None;
+    }
+
+    Some(PackageFile {
+        name: name.to_string(),
+        version: format!("{}-{}", pkgver, pkgrel),
+        arch: arch.to_string(),
+    })
+}
+
+/// Where an older version can be installed from
+enum Location {
+    /// Package file in the pacman cache or a build directory
+    Cache(PathBuf),
+    /// Package file in the Arch Linux Archive
+    Archive(String),
+    /// AUR git commit to rebuild from
+    AurCommit(git2::Oid),
+}
+
+impl Location {
+    fn label(&self) -> &'static str {
+        match self {
+            Location::Cache(_) => "cache",
+            Location::Archive(_) => "archive",
+            Location::AurCommit(_) => "rebuild from AUR",
+        }
+    }
+}
+
+struct Candidate {
+    version: String,
+    location: Location,
+}
+
+pub async fn downgrade(package: &str, config: &Config) -> Result<()> {
     println!("{}", ui::section_header("Downgrade Package"));
-    
-    let cache_dir = "/var/cache/pacman/pkg";
-    let entries = fs::read_dir(cache_dir)?;
-    
-    // Filter for package files
-    // Format: name-version-arch.pkg.tar.zst (or .xz)
-    // We want files that START with package- and END with .pkg.tar...
-    // But be careful of "firefox" vs "firefox-developer-edition".
-    // "firefox-" prefix is safer.
-    
-    let prefix = format!("{}-", package);
-    let mut candidates: Vec<PathBuf> = Vec::new();
-    
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
-        if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-            if filename.starts_with(&prefix) && filename.contains(".pkg.tar") && !filename.ends_with(".sig") {
-                // Ensure it's not a different package sharing prefix
-                // e.g. "package-foo" vs "package"
-                // The char after prefix should be digit (start of version) usually?
-                // Arch package naming: name-version-release-arch
-                // So if we have "firefox-", the next char must be version start.
-                // If we have "firefox-adblock", then "adblock" is part of name?
-                // Pacman cache usually contains valid packages.
-                // A weak check: check if it matches exactly `package-version-...`
-                
-                // Let's blindly try matching.
-                candidates.push(path);
+
+    let local = PackageDb::load_local()?;
+    let installed = local.get(package).map(|p| p.version.clone());
+    let is_aur = crate::pacman::get_installed_aur_packages()?
+        .iter()
+        .any(|(name, _)| name == package);
+
+    let mut candidates = cached_versions(package, &[PathBuf::from(CACHE_DIR), config.clone_dir.join(package)]);
+
+    // Kept alive until the build below, the AUR history is cloned into it
+    let mut history_dir = None;
+
+    if is_aur {
+        let spinner = ui::spinner("Reading AUR git history...");
+        let history = aur_history(package).await;
+        spinner.finish_and_clear();
+
+        match history {
+            Ok((dir, commits)) => {
+                candidates.extend(commits);
+                history_dir = Some(dir);
             }
+            Err(e) => eprintln!("{}", ui::warning(&format!("Failed to read AUR history: {}", e))),
+        }
+    } else {
+        let spinner = ui::spinner("Querying the Arch Linux Archive...");
+        let archived = archive_versions(&config.archive_url, package).await;
+        spinner.finish_and_clear();
+
+        match archived {
+            Ok(archived) => candidates.extend(archived),
+            Err(e) => eprintln!("{}", ui::warning(&format!("Failed to query the Arch Linux Archive: {}", e))),
         }
     }
-    
+
+    // One entry per version, preferring local files over downloads and rebuilds
+    candidates.sort_by(|a, b| {
+        crate::pacman::vercmp(&b.version, &a.version)
+            .then_with(|| location_rank(&a.location).cmp(&location_rank(&b.location)))
+    });
+    candidates.dedup_by(|a, b| crate::pacman::vercmp(&a.version, &b.version) == Ordering::Equal);
+
     if candidates.is_empty() {
-        println!("{}", ui::warning(&format!("No cached versions found for '{}'", package)));
+        println!("{}", ui::warning(&format!("No other versions found for '{}'", package)));
         return Ok(());
     }
-    
-    // Sort candidates (roughly by modification time or name?)
-    // Newer versions usually have "higher" strings, but strictly we should check mod time or version parse.
-    // Mod time is safest for cache.
-    candidates.sort_by_key(|p| p.metadata().and_then(|m| m.modified()).ok());
-    candidates.reverse(); // Newest first
-    
-    println!("Found {} cached versions:", candidates.len());
-    
-    for (i, path) in candidates.iter().enumerate() {
-        let filename = path.file_name().unwrap().to_string_lossy();
-        println!(" [{}] {}", i + 1, filename.bright_cyan());
-    }
-    
-    println!("\nSelect a version to install (0 to cancel):");
-    
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input)?;
-    let choice: usize = input.trim().parse().unwrap_or(0);
-    
-    if choice == 0 || choice > candidates.len() {
-        println!("Cancelled.");
+
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|c| {
+            let marker = if installed.as_deref() == Some(c.version.as_str()) { " (installed)" } else { "" };
+            format!("{} [{}]{}", c.version, c.location.label(), marker)
+        })
+        .collect();
+
+    let Some(choice) = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Select a version of {} to install", package))
+        .items(&items)
+        .default(0)
+        .max_length(15)
+        .interact_opt()?
+    else {
+        println!("{}", ui::warning("Downgrade cancelled"));
         return Ok(());
+    };
+
+    let target = &candidates[choice];
+    println!("{}", ui::info(&format!("Installing {} {}...", package, target.version)));
+
+    let source = if is_aur { SourceKind::Aur } else { SourceKind::Repo };
+    let tx = Transaction::begin("downgrade", source, &[package.to_string()]);
+
+    let result = match &target.location {
+        Location::Cache(path) => pacman_upgrade(path.to_string_lossy().as_ref()),
+        // pacman downloads the file and its signature itself
+        Location::Archive(url) => pacman_upgrade(url),
+        Location::AurCommit(oid) => {
+            let dir = history_dir.as_ref().expect("AUR commits come with their clone");
+            rebuild_at(dir.path(), *oid)
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", ui::error("Downgrade failed"));
+        let _ = tx.fail(&e);
+        return Err(e);
+    }
+    let _ = tx.finish(true);
+
+    println!("{}", ui::success(&format!("{} is now at {}", package, target.version)));
+
+    let hold = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Hold {} at this version so upgrades skip it?", package))
+        .default(false)
+        .interact()?;
+
+    if hold {
+        crate::holds::hold(source, package)?;
+        println!("{}", ui::success(&format!("{} is held", package)));
+    }
+
+    Ok(())
+}
+
+fn location_rank(location: &Location) -> u8 {
+    match location {
+        Location::Cache(_) => 0,
+        Location::Archive(_) => 1,
+        Location::AurCommit(_) => 2,
+    }
+}
+
+fn arch_matches(arch: &str) -> bool {
+    arch == "any" || arch == std::env::consts::ARCH
+}
+
+/// Package files for `package` in the given directories
+fn cached_versions(package: &str, dirs: &[PathBuf]) -> Vec<Candidate> {
+    let mut candidates = Vec::new();
+
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let filename = entry.file_name();
+            let Some(file) = parse_package_filename(&filename.to_string_lossy()) else {
+                continue;
+            };
+            if file.name == package && arch_matches(&file.arch) {
+                candidates.push(Candidate {
+                    version: file.version,
+                    location: Location::Cache(entry.path()),
+                });
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Versions listed in the archive's per-package directory: {base}/packages/f/firefox/
+async fn archive_versions(base_url: &str, package: &str) -> Result<Vec<Candidate>> {
+    let first = package
+        .chars()
+        .next()
+        .ok_or_else(|| KhazaurError::PackageNotFound(package.to_string()))?;
+    let dir_url = format!("{}/packages/{}/{}/", base_url.trim_end_matches('/'), first, package);
+
+    let listing = crate::net::read_source(&dir_url).await?;
+    let listing = String::from_utf8_lossy(&listing);
+
+    let href = regex::Regex::new(r#"href="([^"]+)""#).expect("valid regex");
+    let mut candidates = Vec::new();
+    for captures in href.captures_iter(&listing) {
+        let link = &captures[1];
+        let filename = urlencoding::decode(link).map(|s| s.into_owned()).unwrap_or_else(|_| link.to_string());
+        let Some(file) = parse_package_filename(&filename) else {
+            continue;
+        };
+        if file.name == package && arch_matches(&file.arch) {
+            candidates.push(Candidate {
+                version: file.version,
+                location: Location::Archive(format!("{}{}", dir_url, link)),
+            });
+        }
     }
-    
-    let target = &candidates[choice - 1];
-    println!("Downgrading to {:?}...", target);
-    
+
+    Ok(candidates)
+}
+
+/// Clone the package's AUR git repository and list the version each commit builds,
+/// newest commit first
+async fn aur_history(package: &str) -> Result<(tempfile::TempDir, Vec<Candidate>)> {
+    let client = AurClient::new()?;
+    let pkgbase = client.info(package).await?.package_base;
+    let url = client.git_url(&pkgbase);
+    let dir = tempfile::tempdir()?;
+
+    let git_err = |e: git2::Error| KhazaurError::DownloadFailed(format!("{}: {}", url, e));
+    let repo = crate::net::clone_repo(&url, dir.path()).map_err(git_err)?;
+
+    let mut candidates = Vec::new();
+    {
+        let mut walk = repo.revwalk().map_err(git_err)?;
+        walk.push_head().map_err(git_err)?;
+
+        for oid in walk {
+            let oid = oid.map_err(git_err)?;
+            let tree = repo.find_commit(oid).and_then(|c| c.tree()).map_err(git_err)?;
+            let Some(entry) = tree.get_name(".SRCINFO") else {
+                continue;
+            };
+            let Ok(blob) = repo.find_blob(entry.id()) else {
+                continue;
+            };
+            let Some(version) = srcinfo_version(&String::from_utf8_lossy(blob.content())) else {
+                continue;
+            };
+
+            if !candidates.iter().any(|c: &Candidate| c.version == version) {
+                candidates.push(Candidate {
+                    version,
+                    location: Location::AurCommit(oid),
+                });
+            }
+        }
+    }
+
+    Ok((dir, candidates))
+}
+
+/// Version built by a .SRCINFO: [epoch:]pkgver-pkgrel from the pkgbase section
+fn srcinfo_version(srcinfo: &str) -> Option<String> {
+    let field = |key: &str| {
+        srcinfo.lines().find_map(|line| {
+            let (k, v) = line.trim().split_once('=')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+    };
+
+    let pkgver = field("pkgver")?;
+    let pkgrel = field("pkgrel")?;
+    Some(match field("epoch") {
+        Some(epoch) if epoch != "0" => format!("{}:{}-{}", epoch, pkgver, pkgrel),
+        _ => format!("{}-{}", pkgver, pkgrel),
+    })
+}
+
+/// Check out an older commit of the cloned AUR repository and build it
+fn rebuild_at(dir: &Path, oid: git2::Oid) -> Result<()> {
+    let git_err = |e: git2::Error| KhazaurError::BuildFailed(format!("Failed to check out {}: {}", oid, e));
+    let repo = Repository::open(dir).map_err(git_err)?;
+    let commit = repo.find_commit(oid).map_err(git_err)?;
+
+    repo.checkout_tree(commit.as_object(), Some(git2::build::CheckoutBuilder::new().force()))
+        .map_err(git_err)?;
+    repo.set_head_detached(oid).map_err(git_err)?;
+
+    crate::build::build_and_install(dir, true)
+}
+
+fn pacman_upgrade(target: &str) -> Result<()> {
     let status = Command::new("sudo")
-        .arg("pacman")
-        .arg("-U")
-        .arg(target)
+        .args(["pacman", "-U", target])
         .status()?;
-        
-    if status.success() {
-        println!("{}", ui::success("Downgrade successful"));
-    } else {
-        eprintln!("{}", ui::error("Downgrade failed"));
+
+    if !status.success() {
+        return Err(KhazaurError::PacmanFailed(format!("pacman -U {} failed", target)));
     }
-    
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_filename() {
+        let file = parse_package_filename("firefox-developer-edition-129.0b9-1-x86_64.pkg.tar.zst").unwrap();
+        assert_eq!(file.name, "firefox-developer-edition");
+        assert_eq!(file.version, "129.0b9-1");
+        assert_eq!(file.arch, "x86_64");
+
+        let file = parse_package_filename("python-3:1.2.3-2-any.pkg.tar.xz").unwrap();
+        assert_eq!(file.name, "python");
+        assert_eq!(file.version, "3:1.2.3-2");
+
+        assert!(parse_package_filename("firefox-129.0-1-x86_64.pkg.tar.zst.sig").is_none());
+        assert!(parse_package_filename("firefox-129.0.tar.gz").is_none());
+    }
+
+    #[test]
+    fn test_srcinfo_version() {
+        let srcinfo = "pkgbase = foo\n\tpkgver = 1.2\n\tpkgrel = 3\n\tepoch = 1\n\npkgname = foo\n";
+        assert_eq!(srcinfo_version(srcinfo).as_deref(), Some("1:1.2-3"));
+    }
+}
@@ -0,0 +1,89 @@
+use crate::config::Config;
+use crate::debian::contents;
+use crate::error::Result;
+use crate::pacman;
+use crate::ui;
+use colored::Colorize;
+
+/// Find the packages owning each file name or path, in pacman's files databases
+/// and, when `debian_contents` is enabled, the Debian Contents indexes.
+/// With `refresh` (-Fy) both are downloaded again first.
+pub async fn find_files(queries: &[String], refresh: bool, repo_only: bool, debian_only: bool, config: &Config) -> Result<()> {
+    let search_repo = !debian_only;
+    let search_debian = !repo_only && config.debian_contents;
+
+    if debian_only && !config.debian_contents {
+        println!("{}", ui::warning("Debian file lists are disabled, set debian_contents = true to search them"));
+        return Ok(());
+    }
+
+    if refresh {
+        println!("{}", ui::section_header("Syncing Files Databases"));
+        if search_repo {
+            pacman::sync_files_databases()?;
+        }
+    }
+
+    // Contents indexes are fetched on first use and refreshed like the package indexes
+    if search_debian && let Err(e) = contents::update(config, true).await {
+        eprintln!("{}", ui::warning(&format!("Failed to update Debian file lists: {}", e)));
+    }
+
+    if queries.is_empty() {
+        println!("\n{}", ui::success("Files databases synced"));
+        return Ok(());
+    }
+
+    for query in queries {
+        println!("{}", ui::section_header(&format!("Packages owning {}", query)));
+        let mut found = false;
+
+        if search_repo {
+            match pacman::search_files(query) {
+                Ok(owners) => {
+                    for owner in &owners {
+                        println!(
+                            "  {}/{} {}  {}",
+                            owner.repository.bright_cyan(),
+                            owner.name.bright_white(),
+                            owner.version.bright_blue(),
+                            format!("/{}", owner.path).dimmed()
+                        );
+                    }
+                    found |= !owners.is_empty();
+                }
+                Err(e) => eprintln!("{}", ui::warning(&e.to_string())),
+            }
+        }
+
+        if search_debian {
+            if !contents::available(config) {
+                eprintln!("{}", ui::warning("No Debian file lists downloaded yet, run 'khazaur -Fy'"));
+            }
+            match contents::search(query, config) {
+                Ok(matches) => {
+                    for entry in &matches {
+                        println!(
+                            "  {}/{} {}  {}",
+                            entry.suite.bright_red(),
+                            entry.package.bright_white(),
+                            format!("({})", entry.section).dimmed(),
+                            format!("/{}", entry.path).dimmed()
+                        );
+                    }
+                    found |= !matches.is_empty();
+                }
+                Err(e) => eprintln!("{}", ui::warning(&format!("Failed to search Debian file lists: {}", e))),
+            }
+        }
+
+        if !found {
+            println!("{}", ui::info(&format!("No package owns {}", query)));
+            if !config.debian_contents && !repo_only {
+                println!("{}", ui::info("Set debian_contents = true to search Debian packages as well"));
+            }
+        }
+    }
+
+    Ok(())
+}
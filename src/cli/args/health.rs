@@ -32,20 +32,21 @@ pub fn check_health() -> Result<()> {
     
     // 2. Pacnew files
     println!("\n{}", ui::info("Checking for .pacnew files..."));
-    // Safe way: find /etc -name "*.pacnew" 2>/dev/null
-    match Command::new("sudo").args(["find", "/etc", "-name", "*.pacnew"]).output() {
-        Ok(output) => {
-             let stdout = String::from_utf8_lossy(&output.stdout);
-             let files: Vec<&str> = stdout.lines().filter(|l| !l.is_empty()).collect();
+    match super::pacdiff::find_pending_files() {
+        Ok(files) => {
+             let files: Vec<_> = files
+                 .into_iter()
+                 .filter(|f| f.kind == super::pacdiff::PendingKind::Pacnew)
+                 .collect();
              
              if files.is_empty() {
                  println!("  {}", "✓ No .pacnew files found".green());
              } else {
                  println!("  {}", format!("✗ Found {} .pacnew file(s):", files.len()).red());
                  for f in files {
-                     println!("    {}", f);
+                     println!("    {}", f.path.display());
                  }
-                 println!("    {}", "(Merge these files to keep your configuration up to date)".dimmed());
+                 println!("    {}", "(Run 'khazaur pacdiff' to merge these files)".dimmed());
                  specific_issues += 1;
              }
         },
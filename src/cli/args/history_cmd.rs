@@ -1,36 +1,298 @@
+use crate::cli::SourceKind;
+use crate::error::{KhazaurError, Result};
+use crate::history::{HistoryEntry, HistoryFilter, InstalledVersion, Origin};
 use crate::ui;
-use crate::error::Result;
 use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Entries shown when no limit is given; stats and exports cover everything
+const DEFAULT_LIMIT: usize = 10;
+
+/// Machine-readable history formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HistoryFormat {
+    Csv,
+    Json,
+}
+
+/// Parse a `--source` value: repo, aur, flatpak, snap or debian
+pub fn parse_source(value: &str) -> std::result::Result<SourceKind, String> {
+    SourceKind::from_prefix(value).ok_or_else(|| format!("unknown source '{}'", value))
+}
+
+pub fn show_history(
+    filter: &HistoryFilter,
+    limit: Option<usize>,
+    stats: bool,
+    export: Option<HistoryFormat>,
+    output: Option<&Path>,
+    pacman_log: Option<&Path>,
+) -> Result<()> {
+    check_range(filter)?;
+
+    let load = |limit: Option<usize>| match pacman_log {
+        Some(path) => crate::history::query_merged(filter, limit, path),
+        None => crate::history::query(filter, limit),
+    };
+
+    if let Some(format) = export {
+        let entries = load(limit)?;
+        let text = match format {
+            HistoryFormat::Json => serde_json::to_string_pretty(&entries)? + "\n",
+            HistoryFormat::Csv => to_csv(&entries),
+        };
+        match output {
+            Some(path) => {
+                std::fs::write(path, text)?;
+                println!("{}", ui::success(&format!("Exported {} entries to {}", entries.len(), path.display())));
+            }
+            None => print!("{}", text),
+        }
+        return Ok(());
+    }
+
+    if stats {
+        return show_stats(&load(limit)?);
+    }
 
-pub fn show_history(limit: usize) -> Result<()> {
     println!("{}", ui::section_header("Operation History"));
-    
-    let history = crate::history::get_history(limit)?;
-    
+
+    if let Some(package) = &filter.package {
+        show_package_summary(package, &load(None)?);
+    }
+
+    let history = load(Some(limit.unwrap_or(DEFAULT_LIMIT)))?;
+
     if history.is_empty() {
         println!("{}", ui::info("No history found."));
         return Ok(());
     }
-    
+
     for entry in history {
-        let timestamp = match chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
-             Ok(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-             Err(_) => entry.timestamp.clone(),
+        let timestamp = match entry.time() {
+             Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
+             None => entry.timestamp.clone(),
         };
-        
+
         let status = if entry.success {
             "SUCCESS".green()
         } else {
             "FAILED".red()
         };
-        
-        println!("{} [{}] {} : {}", 
+
+        let id = match entry.origin {
+            Origin::Pacman => "pacman".to_string(),
+            Origin::Khazaur if entry.id > 0 => format!("#{:<5}", entry.id),
+            Origin::Khazaur => " ".repeat(6),
+        };
+
+        println!("{} {} [{}] {} : {} {}",
+            id.bright_blue(),
             timestamp.dimmed(),
             status,
             entry.action.bold(),
-            entry.packages.join(", ")
+            entry.packages.join(", "),
+            format_duration(entry.duration_ms).dimmed()
         );
+
+        for change in entry.changes.iter().filter(|c| c.changed()) {
+            println!("        {} {} {} -> {}",
+                change.name,
+                format!("[{}]", change.source.label()).dimmed(),
+                version(&change.before),
+                version(&change.after)
+            );
+        }
+
+        if let Some(error) = &entry.error {
+            println!("        {}", error.red());
+        }
+    }
+
+    Ok(())
+}
+
+/// When a package was last installed, upgraded, downgraded and removed
+fn show_package_summary(package: &str, entries: &[HistoryEntry]) {
+    let mut latest: BTreeMap<&str, (String, String)> = BTreeMap::new();
+
+    // Entries are newest first, so the first change of each kind is the latest
+    for entry in entries.iter().filter(|e| e.success) {
+        let Some(change) = entry.changes.iter().find(|c| c.name == package && c.changed()) else {
+            continue;
+        };
+        let kind = match (&change.before, &change.after) {
+            (None, Some(_)) => "Installed",
+            (Some(_), None) => "Removed",
+            (Some(old), Some(new)) if crate::pacman::vercmp(&new.version, &old.version).is_lt() => "Downgraded",
+            _ => "Upgraded",
+        };
+        let date = entry
+            .time()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| entry.timestamp.clone());
+        let versions = format!("{} -> {}", version(&change.before), version(&change.after));
+        latest.entry(kind).or_insert((date, versions));
+    }
+
+    if latest.is_empty() {
+        return;
+    }
+
+    for kind in ["Installed", "Upgraded", "Downgraded", "Removed"] {
+        if let Some((date, versions)) = latest.get(kind) {
+            println!("  {:<20} {}  {}", format!("Last {}", kind.to_lowercase()).bold(), date, versions.dimmed());
+        }
+    }
+    println!();
+}
+
+fn show_stats(entries: &[HistoryEntry]) -> Result<()> {
+    println!("{}", ui::section_header("History Statistics"));
+
+    if entries.is_empty() {
+        println!("{}", ui::info("No history found."));
+        return Ok(());
+    }
+
+    let failed = entries.iter().filter(|e| !e.success).count();
+    println!("  {:<20} {}", "Transactions".bold(), entries.len());
+    println!("  {:<20} {}", "Succeeded".bold(), (entries.len() - failed).to_string().green());
+    println!("  {:<20} {}", "Failed".bold(), if failed > 0 { failed.to_string().red() } else { failed.to_string().normal() });
+
+    let timed: Vec<u64> = entries.iter().map(|e| e.duration_ms).filter(|d| *d > 0).collect();
+    if !timed.is_empty() {
+        let total: u64 = timed.iter().sum();
+        println!("  {:<20} {}", "Total time".bold(), format_duration(total));
+        println!("  {:<20} {}", "Average time".bold(), format_duration(total / timed.len() as u64));
+    }
+
+    let times: Vec<_> = entries.iter().filter_map(|e| e.time()).collect();
+    if let (Some(first), Some(last)) = (times.iter().min(), times.iter().max()) {
+        println!("  {:<20} {} to {}", "Period".bold(), first.format("%Y-%m-%d"), last.format("%Y-%m-%d"));
+    }
+
+    let mut actions: BTreeMap<&str, usize> = BTreeMap::new();
+    for entry in entries {
+        *actions.entry(entry.action.as_str()).or_default() += 1;
+    }
+    println!("\n{} {}", "::".bright_blue().bold(), "By action:".bold());
+    for (action, count) in &actions {
+        println!("  {:<20} {}", action, count);
+    }
+
+    let mut sources: BTreeMap<SourceKind, usize> = BTreeMap::new();
+    let mut packages: HashMap<&str, usize> = HashMap::new();
+    for change in entries.iter().flat_map(|e| &e.changes).filter(|c| c.changed()) {
+        *sources.entry(change.source).or_default() += 1;
+        *packages.entry(change.name.as_str()).or_default() += 1;
     }
-    
+
+    if !sources.is_empty() {
+        println!("\n{} {}", "::".bright_blue().bold(), "Package changes by source:".bold());
+        for (source, count) in &sources {
+            println!("  {:<20} {}", source.label(), count);
+        }
+
+        let mut busiest: Vec<_> = packages.into_iter().collect();
+        busiest.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        println!("\n{} {}", "::".bright_blue().bold(), "Most changed packages:".bold());
+        for (name, count) in busiest.iter().take(10) {
+            println!("  {:<40} {}", name, count);
+        }
+    }
+
     Ok(())
 }
+
+/// One row per package: per change when versions were recorded, per name otherwise
+fn to_csv(entries: &[HistoryEntry]) -> String {
+    let mut csv = String::from("id,timestamp,action,success,source,package,before,after,duration_ms,error,command\n");
+
+    for entry in entries {
+        let mut row = |source: &str, package: &str, before: &str, after: &str| {
+            let fields = [
+                entry.id.to_string(),
+                entry.timestamp.clone(),
+                entry.action.clone(),
+                entry.success.to_string(),
+                source.to_string(),
+                package.to_string(),
+                before.to_string(),
+                after.to_string(),
+                entry.duration_ms.to_string(),
+                entry.error.clone().unwrap_or_default(),
+                entry.command.clone(),
+            ];
+            let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        };
+
+        if entry.changes.is_empty() {
+            for package in &entry.packages {
+                row("", package, "", "");
+            }
+        } else {
+            for change in &entry.changes {
+                let field = |v: &Option<InstalledVersion>| v.as_ref().map(|v| v.version.clone()).unwrap_or_default();
+                row(change.source.label(), &change.name, &field(&change.before), &field(&change.after));
+            }
+        }
+    }
+
+    csv
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn version(v: &Option<InstalledVersion>) -> String {
+    v.as_ref().map(|v| v.version.clone()).unwrap_or_else(|| "-".to_string())
+}
+
+fn format_duration(ms: u64) -> String {
+    match ms {
+        0 => String::new(),
+        ms if ms < 1000 => format!("{}ms", ms),
+        ms if ms < 60_000 => format!("{:.1}s", ms as f64 / 1000.0),
+        ms => format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1000),
+    }
+}
+
+/// Reject date ranges that can't match anything
+fn check_range(filter: &HistoryFilter) -> Result<()> {
+    if let (Some(since), Some(until)) = (filter.since, filter.until)
+        && since > until
+    {
+        return Err(KhazaurError::Config(format!("--since {} is after --until {}", since, until)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("firefox"), "firefox");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "");
+        assert_eq!(format_duration(250), "250ms");
+        assert_eq!(format_duration(1500), "1.5s");
+        assert_eq!(format_duration(125_000), "2m05s");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::cli::SourceKind;
+use crate::cli::backend::{self, locate_installed};
+use crate::error::Result;
+use crate::holds::HoldDb;
+use crate::ui;
+use colored::Colorize;
+
+
+/// Hold packages at their installed version, or list holds when none are given
+pub fn hold_packages(packages: &[String]) -> Result<()> {
+    if packages.is_empty() {
+        return list_holds();
+    }
+
+    println!("{}", ui::section_header("Holding Packages"));
+    let backends = backend::all(false)?;
+    let db = HoldDb::load()?;
+
+    for query in packages {
+        let Some((index, name)) = locate_installed(&backends, query)? else {
+            println!("{}", ui::warning(&format!("'{}' is not installed", query)));
+            continue;
+        };
+        let source = backends[index].kind();
+        let name = backends[index].record_name(&name);
+
+        if db.is_held(source, &name) {
+            println!("{} {} {} {}", "::".bright_blue().bold(), name.bold(),
+                format!("[{}]", source.label()).dimmed(), "is already held".dimmed());
+            continue;
+        }
+
+        match crate::holds::hold(source, &name) {
+            Ok(()) => println!("{}", ui::success(&format!("Held {} [{}]", name, source.label()))),
+            Err(e) => eprintln!("{}", ui::error(&format!("Failed to hold {}: {}", name, e))),
+        }
+    }
+
+    Ok(())
+}
+
+/// Release held packages
+pub fn unhold_packages(packages: &[String]) -> Result<()> {
+    println!("{}", ui::section_header("Releasing Held Packages"));
+    let holds = HoldDb::load()?.all();
+
+    for query in packages {
+        // Match against the holds themselves so packages that were removed can be released too
+        // Plugin ids aren't source kinds, `cargo/ripgrep` is matched as a whole
+        let known = query.split_once('/').and_then(|(prefix, name)| Some((SourceKind::from_prefix(prefix)?, name)));
+        let (prefix, name) = match known {
+            Some((source, name)) => (Some(source), name),
+            None => (None, query.as_str()),
+        };
+        let matches: Vec<_> = holds
+            .iter()
+            .filter(|(source, held)| {
+                // Plugin packages are held as `<id>/package`
+                let bare = match source {
+                    SourceKind::Plugin => held.split_once('/').map_or(held.as_str(), |(_, bare)| bare),
+                    _ => held.as_str(),
+                };
+                (held == name || bare == name) && prefix.is_none_or(|p| p == *source)
+            })
+            .collect();
+
+        if matches.is_empty() {
+            println!("{}", ui::warning(&format!("'{}' is not held", query)));
+            continue;
+        }
+
+        for (source, name) in matches {
+            match crate::holds::unhold(*source, name) {
+                Ok(()) => println!("{}", ui::success(&format!("Released {} [{}]", name, source.label()))),
+                Err(e) => eprintln!("{}", ui::error(&format!("Failed to release {}: {}", name, e))),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn list_holds() -> Result<()> {
+    println!("{}", ui::section_header("Held Packages"));
+
+    let holds = HoldDb::load()?.all();
+    if holds.is_empty() {
+        println!("{}", ui::info("No packages are held"));
+        return Ok(());
+    }
+
+    for (source, name) in holds {
+        println!("  {} {}", name.bold(), format!("[{}]", source.label()).dimmed());
+    }
+
+    Ok(())
+}
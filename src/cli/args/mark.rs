@@ -0,0 +1,53 @@
+use crate::cli::SourceKind;
+use crate::cli::backend::{self, locate_installed};
+use crate::error::Result;
+use crate::reasons::{InstallReason, ReasonDb};
+use crate::ui;
+use colored::Colorize;
+
+/// Change the install reason of packages from any source (like pacman -D)
+pub fn mark_packages(packages: &[String], reason: InstallReason) -> Result<()> {
+    println!("{}", ui::section_header("Changing Install Reason"));
+
+    let backends = backend::all(false)?;
+    let mut db = ReasonDb::load()?;
+    let mut pacman_packages = Vec::new();
+
+    for query in packages {
+        let Some((index, name)) = locate_installed(&backends, query)? else {
+            println!("{}", ui::warning(&format!("'{}' is not installed", query)));
+            continue;
+        };
+        let source = backends[index].kind();
+        let name = backends[index].record_name(&name);
+
+        let current = if source.is_pacman() {
+            crate::pacman::get_install_reason(&name)?
+        } else {
+            db.get(source, &name)
+        };
+
+        if current == Some(reason) {
+            println!("{} {} {} {}", "::".bright_blue().bold(), name.bold(),
+                format!("[{}]", source.label()).dimmed(), format!("is already {}", reason.label()).dimmed());
+            continue;
+        }
+
+        // Converted Debian packages are pacman packages, but khazaur keeps its own record too
+        if source.is_pacman() {
+            pacman_packages.push(name.clone());
+        }
+        if !matches!(source, SourceKind::Repo | SourceKind::Aur) {
+            db.set(source, &name, reason);
+        }
+
+        println!("{} {} {} {}", "::".bright_blue().bold(), name.bold(),
+            format!("[{}]", source.label()).dimmed(), reason.label());
+    }
+
+    crate::pacman::set_install_reason(&pacman_packages, reason)?;
+    db.save()?;
+
+    println!("\n{}", ui::success("Install reasons updated"));
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use crate::cli::SourceKind;
 use crate::config::Config;
 use crate::error::{KhazaurError, Result};
 use crate::pacman;
@@ -19,6 +20,14 @@ pub mod mirrors;
 pub mod backup;
 pub mod downgrade;
 pub mod repo;
+pub mod pacdiff;
+pub mod news;
+pub mod audit;
+pub mod mark;
+pub mod why;
+pub mod files;
+pub mod hold;
+pub mod rollback;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -60,6 +69,10 @@ pub struct Args {
     #[arg(short = 'Q', long)]
     pub query: bool,
 
+    /// Find which packages own a file name or path (-F, -Fy to sync the files databases)
+    #[arg(short = 'F', long)]
+    pub files: bool,
+
     /// Search/install only from AUR
     #[arg(long)]
     pub aur: bool,
@@ -80,10 +93,18 @@ pub struct Args {
     #[arg(long)]
     pub debian: bool,
 
+    /// Search/install only from the AppImage catalog
+    #[arg(long)]
+    pub appimage: bool,
+
     /// Don't ask for confirmation
     #[arg(long)]
     pub noconfirm: bool,
 
+    /// Show what would change without doing it
+    #[arg(long)]
+    pub dry_run: bool,
+
     /// Verbose output (show debug information)
     #[arg(short = 'v', long)]
     pub verbose: bool,
@@ -142,6 +163,47 @@ pub enum Command {
     Orphans,
     /// Run a system health check
     Health,
+    /// Review and merge .pacnew/.pacsave configuration files
+    Pacdiff,
+    /// Read Arch Linux news
+    News {
+        /// Maximum number of items to show
+        #[arg(short = 'n', long, default_value_t = 10)]
+        limit: usize,
+        /// Include items that were already read
+        #[arg(long)]
+        all: bool,
+    },
+    /// Check installed packages against the Arch security tracker
+    Audit,
+    /// Mark packages as explicitly installed or as dependencies (any source)
+    Mark {
+        /// Mark as installed as a dependency
+        #[arg(long, conflicts_with = "asexplicit", required_unless_present = "asexplicit")]
+        asdeps: bool,
+        /// Mark as explicitly installed
+        #[arg(long)]
+        asexplicit: bool,
+        /// Package names (use flatpak/, snap/, debian/ or appimage/ prefixes to pick a source)
+        #[arg(required = true)]
+        packages: Vec<String>,
+    },
+    /// Hold packages at their installed version (lists holds without arguments)
+    Hold {
+        /// Package names (use flatpak/, snap/, debian/ or appimage/ prefixes to pick a source)
+        packages: Vec<String>,
+    },
+    /// Release held packages
+    Unhold {
+        /// Package names
+        #[arg(required = true)]
+        packages: Vec<String>,
+    },
+    /// Explain why a package is installed
+    Why {
+        /// Package name, Flatpak runtime or snap
+        package: String,
+    },
     /// Show dependency tree for a package
     Tree {
         /// Package name
@@ -149,6 +211,24 @@ pub enum Command {
         /// Show GUI window
         #[arg(long)]
         gui: bool,
+        /// Show packages that depend on the package instead
+        #[arg(short, long)]
+        reverse: bool,
+        /// Limit the tree depth
+        #[arg(short, long)]
+        depth: Option<usize>,
+        /// Include optional dependencies
+        #[arg(short, long)]
+        optional: bool,
+        /// Expand repeated packages every time they appear
+        #[arg(long)]
+        full: bool,
+        /// Print the tree in a machine-readable format
+        #[arg(long, value_enum)]
+        export: Option<tree::ExportFormat>,
+        /// Write the export to a file instead of stdout
+        #[arg(long, requires = "export")]
+        output: Option<std::path::PathBuf>,
     },
     /// Manage configuration
     Config {
@@ -157,10 +237,42 @@ pub enum Command {
     },
     /// View operation history
     History {
-        /// Number of recent entries to show
-        #[arg(short = 'n', long, default_value_t = 10)]
-        limit: usize,
-
+        /// Number of recent entries to show (default 10; stats and exports include all)
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+        /// Only transactions involving this package
+        #[arg(long)]
+        package: Option<String>,
+        /// Only transactions that changed packages from this source
+        #[arg(long, value_parser = history_cmd::parse_source)]
+        source: Option<crate::cli::SourceKind>,
+        /// Only this action (install, update, remove, rollback)
+        #[arg(long)]
+        action: Option<String>,
+        /// Only transactions on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<chrono::NaiveDate>,
+        /// Only transactions on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<chrono::NaiveDate>,
+        /// Only transactions on this date (YYYY-MM-DD)
+        #[arg(long, conflicts_with_all = ["since", "until"])]
+        on: Option<chrono::NaiveDate>,
+        /// Only failed transactions
+        #[arg(long)]
+        failed: bool,
+        /// Show a summary instead of the entries
+        #[arg(long)]
+        stats: bool,
+        /// Print the entries in a machine-readable format
+        #[arg(long, value_enum, conflicts_with = "stats")]
+        export: Option<history_cmd::HistoryFormat>,
+        /// Write the export to a file instead of stdout
+        #[arg(long, requires = "export")]
+        output: Option<std::path::PathBuf>,
+        /// Leave out operations from the pacman log
+        #[arg(long)]
+        no_pacman_log: bool,
     },
     /// Manage package mirrors
     Mirrors {
@@ -183,6 +295,11 @@ pub enum Command {
         /// Package name
         package: String,
     },
+    /// Undo a transaction from the history, restoring previous versions
+    Rollback {
+        /// Transaction id, as shown by `khazaur history`
+        txid: u64,
+    },
     /// Manage package repositories
     Repo {
         /// Action to perform: list, add, remove
@@ -212,6 +329,11 @@ impl Args {
         let mut config = Config::load()?;
         config.ensure_dirs()?;
 
+        // NO_COLOR and CLICOLOR_FORCE still decide when colors are enabled
+        if !config.use_color {
+            colored::control::set_override(false);
+        }
+
         // Handle --set-editor flag
         if let Some(ref editor) = self.set_editor {
             return editor::set_default_editor(editor, &mut config);
@@ -236,7 +358,7 @@ impl Args {
 
         // -Sy: Sync databases only
         if self.sync && self.refresh && self.packages.is_empty() && !self.upgrade {
-            return self.sync_databases().await;
+            return self.sync_databases(&config).await;
         }
 
         // -Ss: Search packages
@@ -260,7 +382,7 @@ impl Args {
 
         // -R: Remove packages
         if self.remove && !self.packages.is_empty() {
-            return remove::remove_packages(&self.packages);
+            return remove::remove_packages(&self.packages, self.dry_run);
         }
 
         // -U: Install local package
@@ -268,6 +390,14 @@ impl Args {
             return self.install_local(file);
         }
 
+        // -F: Find which packages own a file, -Fy syncs the files databases first
+        if self.files {
+            if self.packages.is_empty() && !self.refresh {
+                return Err(KhazaurError::Config("File name or path required".to_string()));
+            }
+            return files::find_files(&self.packages, self.refresh, self.repo, self.debian, &config).await;
+        }
+
         // -Q: Query installed packages
         if self.query {
             return query::query_packages();
@@ -298,7 +428,7 @@ impl Args {
             if self.packages.is_empty() {
                 return Err(KhazaurError::Config("Package name(s) required".to_string()).into());
             }
-            return self.get_pkgbuild(&self.packages, &mut config).await;
+            return self.get_pkgbuild(&self.packages).await;
         }
 
         // -P: Show AUR package information
@@ -306,12 +436,12 @@ impl Args {
             if self.packages.is_empty() {
                 return Err(KhazaurError::Config("Package name(s) required".to_string()).into());
             }
-            return self.show_aur_packages(&self.packages, &mut config).await;
+            return self.show_aur_packages(&self.packages).await;
         }
 
-        // Auto-detect .deb files for installation (if no other operation flags are set)
+        // Auto-detect .deb and AppImage files for installation (if no other operation flags are set)
         if !self.sync && !self.remove && !self.query && self.upgrade_local.is_none() && !self.packages.is_empty() {
-            if self.packages.iter().any(|p| p.ends_with(".deb")) {
+            if self.packages.iter().any(|p| p.ends_with(".deb") || crate::appimage::is_appimage_ref(p)) {
                 return self.install_packages(&self.packages, &mut config).await;
             }
         }
@@ -329,25 +459,59 @@ impl Args {
             Command::Update => self.system_upgrade(config).await,
             Command::Orphans => orphans::clean_orphans(),
             Command::Health => health::check_health(),
-            Command::Tree { package, gui } => tree::show_tree(package.clone(), *gui),
+            Command::Pacdiff => pacdiff::pacdiff(config),
+            Command::News { limit, all } => news::show_news(config, *limit, *all).await,
+            Command::Audit => audit::audit(config).await,
+            Command::Mark { asdeps, packages, .. } => {
+                let reason = if *asdeps {
+                    crate::reasons::InstallReason::Dependency
+                } else {
+                    crate::reasons::InstallReason::Explicit
+                };
+                mark::mark_packages(packages, reason)
+            }
+            Command::Hold { packages } => hold::hold_packages(packages),
+            Command::Unhold { packages } => hold::unhold_packages(packages),
+            Command::Why { package } => why::why(package),
+            Command::Tree { package, gui, reverse, depth, optional, full, export, output } => {
+                let options = tree::TreeOptions {
+                    reverse: *reverse,
+                    max_depth: *depth,
+                    optional: *optional,
+                    dedup: !*full,
+                };
+                tree::show_tree(package.clone(), *gui, options, *export, output.clone()).await
+            }
             Command::Config { cmd } => config_cmd::handle_config(cmd),
-            Command::History { limit } => history_cmd::show_history(*limit),
+            Command::History { limit, package, source, action, since, until, on, failed, stats, export, output, no_pacman_log } => {
+                let filter = crate::history::HistoryFilter {
+                    package: package.clone(),
+                    source: *source,
+                    action: action.clone(),
+                    since: on.or(*since),
+                    until: on.or(*until),
+                    failed_only: *failed,
+                };
+                let pacman_log = (!no_pacman_log).then_some(config.pacman_log.as_path());
+                history_cmd::show_history(&filter, *limit, *stats, *export, output.as_deref(), pacman_log)
+            }
             Command::Mirrors { country, fast } => mirrors::update_mirrors(country.clone(), *fast),
             Command::Backup { path, restore } => if *restore { 
                 backup::restore(path).await 
             } else { 
                 backup::backup(path) 
             },
-            Command::Downgrade { package } => downgrade::downgrade(package).await,
+            Command::Downgrade { package } => downgrade::downgrade(package, config).await,
+            Command::Rollback { txid } => rollback::rollback(*txid, config),
             Command::Repo { action } => repo::handle_repo_command(action).await,
         }
     }
 
-    async fn sync_databases(&self) -> Result<()> {
+    async fn sync_databases(&self, config: &Config) -> Result<()> {
         println!("{}", ui::section_header("Syncing Package Databases"));
         
         // Determine what to sync based on flags
-        let sync_all = !self.aur && !self.repo && !self.snap && !self.debian;
+        let sync_all = !self.aur && !self.repo && !self.snap && !self.debian && !self.appimage;
         
         // Sync pacman databases (repos and AUR metadata)
         if sync_all || self.repo || self.aur {
@@ -357,7 +521,7 @@ impl Args {
         // Update Debian package index and debtap if requested
         if sync_all || self.debian {
             // Update Debian package index with progress bar
-            match crate::debian::update_index().await {
+            match crate::debian::update_index(config).await {
                 Ok(_) => {
                     println!("{}", ui::success("Debian index updated"));
                 }
@@ -365,9 +529,16 @@ impl Args {
                     eprintln!("{}", ui::warning(&format!("Failed to update Debian index: {}", e)));
                 }
             }
+
+            if config.debian_contents {
+                match crate::debian::contents::update(config, true).await {
+                    Ok(_) => println!("{}", ui::success("Debian file lists updated")),
+                    Err(e) => eprintln!("{}", ui::warning(&format!("Failed to update Debian file lists: {}", e))),
+                }
+            }
             
-            // Update debtap database last (takes longer)
-            if crate::debtap::is_available() {
+            // Update debtap database last (takes longer), only needed when debtap converts
+            if config.debian_converter == crate::debian::convert::Converter::Debtap && crate::debtap::is_available() {
                 println!("\n{}", ui::info("Updating debtap database (this may take a while)..."));
                 if let Err(e) = crate::debtap::update_database() {
                     eprintln!("{}", ui::warning(&format!("Failed to update debtap database: {}", e)));
@@ -375,22 +546,35 @@ impl Args {
             }
         }
         
+        // Fetch the AppImage catalog again when it is downloaded from a URL
+        if (sync_all || self.appimage)
+            && let Err(e) = crate::appimage::catalog::load(config, true).await
+        {
+            eprintln!("{}", ui::warning(&format!("Failed to update AppImage catalog: {}", e)));
+        }
+        
         println!("\n{}", ui::success("Database sync complete"));
         Ok(())
     }
 
+    /// Sources picked with --aur, --repo, --flatpak, ...; empty means all of them
+    fn sources(&self) -> Vec<SourceKind> {
+        [
+            (self.repo, SourceKind::Repo),
+            (self.aur, SourceKind::Aur),
+            (self.debian, SourceKind::Debian),
+            (self.flatpak, SourceKind::Flatpak),
+            (self.snap, SourceKind::Snap),
+            (self.appimage, SourceKind::AppImage),
+        ]
+        .into_iter()
+        .filter(|(picked, _)| *picked)
+        .map(|(_, kind)| kind)
+        .collect()
+    }
+
     async fn search_packages(&self, query: &str, config: &mut Config) -> Result<()> {
-        crate::cli::search::search(
-            query,
-            config,
-            self.aur,
-            self.repo,
-            self.aur,
-            self.repo,
-            self.flatpak,
-            self.snap,
-            self.debian,
-        ).await
+        crate::cli::search::search(query, config, &self.sources()).await
     }
 
     async fn show_package_info(&self, package_name: &str, config: &mut Config) -> Result<()> {
@@ -402,11 +586,7 @@ impl Args {
             packages,
             config,
             self.noconfirm,
-            self.aur,
-            self.repo,
-            self.flatpak,
-            self.snap,
-            self.debian,
+            &self.sources(),
             self.no_timeout,
         ).await
     }
@@ -421,28 +601,8 @@ impl Args {
         // Check for all updates (repo + AUR) and upgrade together
         crate::cli::install::upgrade_system(config, self.noconfirm).await?;
         
-        // Refresh snap if available
-        if crate::snap::is_available() {
-            println!("\n{}", ui::info("Refreshing snap packages..."));
-            let status = std::process::Command::new("snap")
-                .args(["refresh"])
-                .status();
-            
-            match status {
-                Ok(s) if s.success() => {
-                    println!("{}", ui::success("Snap packages refreshed"));
-                }
-                Ok(_) => {
-                    eprintln!("{}", ui::warning("Snap refresh failed"));
-                }
-                Err(e) => {
-                    eprintln!("{}", ui::warning(&format!("Failed to run snap refresh: {}", e)));
-                }
-            }
-        }
-        
         // Update Debian package index with progress bar
-        match crate::debian::update_index().await {
+        match crate::debian::update_index(config).await {
             Ok(_) => {
                 println!("{}", ui::success("Debian index updated"));
             }
@@ -451,8 +611,8 @@ impl Args {
             }
         }
         
-        // Update debtap database last (takes longer)
-        if crate::debtap::is_available() {
+        // Update debtap database last (takes longer), only needed when debtap converts
+        if config.debian_converter == crate::debian::convert::Converter::Debtap && crate::debtap::is_available() {
             println!("\n{}", ui::info("Updating debtap database (this may take a while)..."));
             if let Err(e) = crate::debtap::update_database() {
                 eprintln!("{}", ui::warning(&format!("Failed to update debtap database: {}", e)));
@@ -500,7 +660,7 @@ impl Args {
         Ok(())
     }
 
-    async fn get_pkgbuild(&self, packages: &[String], config: &mut Config) -> Result<()> {
+    async fn get_pkgbuild(&self, packages: &[String]) -> Result<()> {
         use crate::aur::AurClient;
         use std::path::PathBuf;
         
@@ -532,7 +692,7 @@ impl Args {
             (packages, std::env::current_dir()?)
         };
         
-        let client = AurClient::with_rate_limit(config.max_concurrent_requests, config.request_delay_ms)?;
+        let client = AurClient::new()?;
         
         for pkg_name in pkg_names {
             println!("\n{}", ui::info(&format!("Downloading: {}", pkg_name)));
@@ -555,9 +715,9 @@ impl Args {
             }
             
             // Clone the AUR repo
-            let url = format!("https://aur.archlinux.org/{}.git", pkg_name);
+            let url = client.git_url(pkg_name);
             
-            match git2::Repository::clone(&url, &target_dir) {
+            match crate::net::clone_repo(&url, &target_dir) {
                 Ok(_) => {
                     println!("{}", ui::success(&format!("Downloaded to: {:?}", target_dir)));
                 }
@@ -571,12 +731,12 @@ impl Args {
         Ok(())
     }
 
-    async fn show_aur_packages(&self, packages: &[String], config: &mut Config) -> Result<()> {
+    async fn show_aur_packages(&self, packages: &[String]) -> Result<()> {
         use crate::aur::AurClient;
         
         println!("{}", ui::section_header("AUR Package Information"));
         
-        let client = AurClient::with_rate_limit(config.max_concurrent_requests, config.request_delay_ms)?;
+        let client = AurClient::new()?;
         
         for pkg_name in packages {
             match client.info(pkg_name).await {
@@ -592,7 +752,7 @@ impl Args {
                         println!("  {} {}", "Upstream URL:".dimmed(), url);
                     }
                     
-                    println!("  {} https://aur.archlinux.org/packages/{}", "AUR URL:".dimmed(), pkg.name);
+                    println!("  {} {}", "AUR URL:".dimmed(), client.package_url(&pkg.name));
                     
                     if let Some(maintainer) = &pkg.maintainer {
                         println!("  {} {}", "Maintainer:".dimmed(), maintainer);
@@ -0,0 +1,36 @@
+use crate::config::Config;
+use crate::error::Result;
+use crate::news::{self, NewsState};
+use crate::ui;
+
+/// Show Arch Linux news, unread items only unless `all` is set
+pub async fn show_news(config: &Config, limit: usize, all: bool) -> Result<()> {
+    println!("{}", ui::section_header("Arch Linux News"));
+
+    let spinner = ui::spinner("Fetching news...");
+    let items = news::fetch(&config.news_url).await;
+    spinner.finish_and_clear();
+    let items = items?;
+
+    let mut state = NewsState::load()?;
+
+    let shown: Vec<_> = items
+        .iter()
+        .filter(|item| all || !state.is_seen(item))
+        .take(limit)
+        .collect();
+
+    if shown.is_empty() {
+        println!("{}", ui::info("No unread news. Use --all to show read items."));
+        return Ok(());
+    }
+
+    for item in shown.iter().rev() {
+        news::print_item(item, !state.is_seen(item));
+    }
+
+    state.mark_seen(shown);
+    state.save()?;
+
+    Ok(())
+}
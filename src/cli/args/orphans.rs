@@ -1,81 +1,165 @@
+use crate::cli::SourceKind;
+use crate::history::Transaction;
+use crate::reasons::{InstallReason, ReasonDb};
 use crate::ui;
 use crate::error::Result;
+use colored::Colorize;
+use std::collections::HashSet;
 use std::process::Command;
 
 use dialoguer::{theme::ColorfulTheme, Confirm};
 
 pub fn clean_orphans() -> Result<()> {
     println!("{}", ui::section_header("Cleaning Orphaned Packages"));
-    
-    // --- Pacman Orphans ---
-    println!("{}", ui::info("Checking for pacman orphans (unused dependencies)..."));
-    
-    // Get list of orphans
-    let output = Command::new("pacman")
-        .args(["-Qtdq"])
-        .output()?;
-        
-    let orphans_str = String::from_utf8_lossy(&output.stdout);
-    let orphans: Vec<&str> = orphans_str.lines().filter(|l| !l.is_empty()).collect();
-    
+
+    println!("{}", ui::info("Checking for orphans (unused dependencies) in all sources..."));
+
+    let orphans = find_orphans()?;
+
     if orphans.is_empty() {
-        println!("{}", ui::success("No pacman orphans found"));
+        println!("{}", ui::success("No orphans found"));
     } else {
         println!("{}", ui::info(&format!("Found {} orphan(s):", orphans.len())));
-        for pkg in &orphans {
-            println!("  {}", pkg);
+        for (source, pkg) in &orphans {
+            println!("  {} {}", pkg, format!("[{}]", source.label()).dimmed());
         }
         println!();
-        
+
         let confirm = Confirm::with_theme(&ColorfulTheme::default())
             .with_prompt("Remove these packages?")
             .default(false)
             .interact()?;
-            
+
         if confirm {
-            let mut args = vec!["-Rns", "--noconfirm"];
-            args.extend(orphans);
-            
-            let status = Command::new("sudo")
-                .arg("pacman")
-                .args(&args)
-                .status()?;
-                
-            if status.success() {
-                println!("{}", ui::success("Orphans removed successfully"));
-            } else {
-                eprintln!("{}", ui::error("Failed to remove orphans"));
-            }
+            remove_orphans(&orphans)?;
         } else {
-            println!("{}", ui::warning("Skipping pacman orphan removal"));
+            println!("{}", ui::warning("Skipping orphan removal"));
         }
     }
-    
-    // --- Flatpak Unused ---
+
+    Ok(())
+}
+
+/// Find packages installed as dependencies that nothing needs anymore, in every source.
+/// Pacman decides for its own packages; Flatpak and Snap use khazaur's install reasons.
+pub fn find_orphans() -> Result<Vec<(SourceKind, String)>> {
+    let mut orphans = Vec::new();
+    let mut db = ReasonDb::load()?;
+    let mut pruned = false;
+
+    // --- Pacman (repo, AUR and converted Debian packages) ---
+    let output = Command::new("pacman")
+        .args(["-Qtdq"])
+        .output()?;
+    let foreign: HashSet<String> = crate::pacman::get_installed_aur_packages()?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+
+    for pkg in String::from_utf8_lossy(&output.stdout).lines().filter(|l| !l.is_empty()) {
+        let source = if crate::debian::is_debian_package(pkg) {
+            SourceKind::Debian
+        } else if foreign.contains(pkg) {
+            SourceKind::Aur
+        } else {
+            SourceKind::Repo
+        };
+        orphans.push((source, pkg.to_string()));
+    }
+
+    // --- Flatpak: dependency refs no installed app runs on ---
     if crate::flatpak::is_available() {
-        println!("\n{}", ui::info("Checking for unused Flatpak runtimes..."));
-        
-        // Flatpak remove --unused
-        // We run it with --assumeyes if confirmed, but first let's see if we can list them?
-        // simple way: just run flatpak uninstall --unused interactively or verify first.
-        // There isn't a clean "list unused" command without parsing. 
-        // We'll run `flatpak uninstall --unused` and let it handle interaction if not noconfirm,
-        // but since we want to be consistent, we can try to just run it. 
-        // However, users prefer to KNOW if there are orphans first.
-        
-        // We can mimic `flatpak uninstall --unused` roughly, or just invoke it.
-        // Let's invoke it directly as it handles its own detection well.
-        
-        println!("{}", ui::info("Running 'flatpak uninstall --unused'..."));
-        let status = Command::new("flatpak")
-            .args(["uninstall", "--unused"])
+        let runtimes: HashSet<String> = crate::flatpak::install::get_app_runtimes()?
+            .into_iter()
+            .map(|(_, runtime)| runtime)
+            .collect();
+
+        for ref_id in db.with_reason(SourceKind::Flatpak, InstallReason::Dependency) {
+            if !crate::flatpak::install::is_flatpak_ref_installed(&ref_id)? {
+                db.remove(SourceKind::Flatpak, &ref_id);
+                pruned = true;
+            } else if !runtimes.contains(&ref_id) {
+                orphans.push((SourceKind::Flatpak, ref_id));
+            }
+        }
+    }
+
+    // --- Snap: dependency snaps no other snap uses as base or content provider ---
+    if crate::snap::is_available() {
+        let installed = crate::snap::get_installed_snaps("")?;
+        let needed: HashSet<String> = installed
+            .iter()
+            .flat_map(|name| crate::snap::get_snap_dependencies(name))
+            .collect();
+
+        for name in db.with_reason(SourceKind::Snap, InstallReason::Dependency) {
+            if !installed.contains(&name) {
+                db.remove(SourceKind::Snap, &name);
+                pruned = true;
+            } else if !needed.contains(&name) {
+                orphans.push((SourceKind::Snap, name));
+            }
+        }
+    }
+
+    if pruned {
+        db.save()?;
+    }
+
+    Ok(orphans)
+}
+
+fn remove_orphans(orphans: &[(SourceKind, String)]) -> Result<()> {
+    let names_for = |pred: fn(&SourceKind) -> bool| -> Vec<String> {
+        orphans
+            .iter()
+            .filter(|(source, _)| pred(source))
+            .map(|(_, name)| name.clone())
+            .collect()
+    };
+
+    let pacman_orphans = names_for(SourceKind::is_pacman);
+    if !pacman_orphans.is_empty() {
+        let tx = Transaction::begin_mixed(
+            "remove",
+            orphans.iter().filter(|(source, _)| source.is_pacman()).cloned().collect(),
+        );
+        let status = Command::new("sudo")
+            .args(["pacman", "-Rns", "--noconfirm"])
+            .args(&pacman_orphans)
             .status()?;
-            
+
         if status.success() {
-            println!("{}", ui::success("Flatpak cleanup complete"));
+            println!("{}", ui::success("Pacman orphans removed successfully"));
+            let _ = tx.finish(true);
+            let _ = crate::reasons::forget(SourceKind::Debian, &pacman_orphans);
+        } else {
+            let _ = tx.fail(&format!("pacman exited with {}", status));
+            eprintln!("{}", ui::error("Failed to remove pacman orphans"));
+        }
+    }
+
+    for ref_id in names_for(|s| *s == SourceKind::Flatpak) {
+        let tx = Transaction::begin("remove", SourceKind::Flatpak, std::slice::from_ref(&ref_id));
+        if let Err(e) = crate::flatpak::uninstall_flatpak(&ref_id) {
+            eprintln!("{}", ui::error(&format!("Failed to remove flatpak {}: {}", ref_id, e)));
+            let _ = tx.fail(&e);
+        } else {
+            println!("{}", ui::success(&format!("Removed flatpak: {}", ref_id)));
+            let _ = tx.finish(true);
+            let _ = crate::reasons::forget(SourceKind::Flatpak, &[ref_id]);
+        }
+    }
+
+    for name in names_for(|s| *s == SourceKind::Snap) {
+        let tx = Transaction::begin("remove", SourceKind::Snap, std::slice::from_ref(&name));
+        if let Err(e) = crate::snap::uninstall_snap(&name) {
+            eprintln!("{}", ui::error(&format!("Failed to remove snap {}: {}", name, e)));
+            let _ = tx.fail(&e);
         } else {
-            // It might fail if no unused refs, or user cancelled. 
-            // Flatpak exit codes are not always super precise for "nothing to do".
+            println!("{}", ui::success(&format!("Removed snap: {}", name)));
+            let _ = tx.finish(true);
+            let _ = crate::reasons::forget(SourceKind::Snap, &[name]);
         }
     }
 
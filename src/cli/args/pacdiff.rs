@@ -0,0 +1,507 @@
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use crate::ui;
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Confirm, Select};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PKG_CACHE_DIR: &str = "/var/cache/pacman/pkg";
+
+/// Kind of configuration file left behind by pacman
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PendingKind {
+    /// New default config shipped by a package (`.pacnew`)
+    Pacnew,
+    /// Config saved when a package was removed or replaced (`.pacsave`)
+    Pacsave,
+}
+
+impl PendingKind {
+    fn extension(&self) -> &'static str {
+        match self {
+            PendingKind::Pacnew => ".pacnew",
+            PendingKind::Pacsave => ".pacsave",
+        }
+    }
+}
+
+/// A .pacnew/.pacsave file together with the live file it belongs to
+#[derive(Debug, Clone)]
+pub struct PendingFile {
+    pub path: PathBuf,
+    pub live: PathBuf,
+    pub kind: PendingKind,
+}
+
+impl PendingFile {
+    fn from_path(path: &str) -> Option<Self> {
+        for kind in [PendingKind::Pacnew, PendingKind::Pacsave] {
+            if let Some(live) = path.strip_suffix(kind.extension()) {
+                return Some(Self {
+                    path: PathBuf::from(path),
+                    live: PathBuf::from(live),
+                    kind,
+                });
+            }
+        }
+        None
+    }
+}
+
+/// Find .pacnew and .pacsave files under /etc
+pub fn find_pending_files() -> Result<Vec<PendingFile>> {
+    let output = Command::new("sudo")
+        .args(["find", "/etc", "(", "-name", "*.pacnew", "-o", "-name", "*.pacsave", ")", "-type", "f"])
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut files: Vec<PendingFile> = stdout
+        .lines()
+        .filter(|l| !l.is_empty())
+        .filter_map(PendingFile::from_path)
+        .collect();
+
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+/// Interactively review and merge .pacnew/.pacsave files
+pub fn pacdiff(config: &mut Config) -> Result<()> {
+    println!("{}", ui::section_header("Configuration File Merge"));
+
+    let files = find_pending_files()?;
+    if files.is_empty() {
+        println!("{}", ui::success("No .pacnew or .pacsave files found"));
+        return Ok(());
+    }
+
+    println!("{}", ui::info(&format!("Found {} file(s) to review:", files.len())));
+    for file in &files {
+        println!("  {}", file.path.display());
+    }
+
+    for (idx, file) in files.iter().enumerate() {
+        println!("\n{} {} {}",
+            "::".bright_blue().bold(),
+            format!("({}/{})", idx + 1, files.len()).bright_black(),
+            file.path.display().to_string().bold()
+        );
+
+        if let Err(e) = review_file(file, config) {
+            eprintln!("{}", ui::error(&format!("Failed to process {}: {}", file.path.display(), e)));
+        }
+    }
+
+    println!("\n{}", ui::success("Configuration review complete"));
+    Ok(())
+}
+
+/// Show the diff for a single file and apply the action chosen by the user
+fn review_file(file: &PendingFile, config: &mut Config) -> Result<()> {
+    let live_exists = file.live.exists();
+    let pending = read_privileged(&file.path)?;
+    let live = if live_exists { read_privileged(&file.live)? } else { String::new() };
+
+    if live_exists && live == pending {
+        println!("{}", ui::info("File is identical to the live configuration, removing it"));
+        run_privileged("rm", &[file.path.as_os_str()])?;
+        return Ok(());
+    }
+
+    print_diff(&live, &pending, &file.live, &file.path)?;
+
+    let ext = file.kind.extension();
+    let items = vec![
+        "View diff again".to_string(),
+        format!("Keep current file (delete {})", ext),
+        if live_exists {
+            format!("Replace with {} (backup current file)", ext)
+        } else {
+            format!("Restore {} as {}", ext, file.live.display())
+        },
+        "Merge in editor".to_string(),
+        "Skip".to_string(),
+    ];
+
+    loop {
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Action")
+            .items(&items)
+            .default(4)
+            .interact_opt()?;
+
+        match choice {
+            Some(0) => print_diff(&live, &pending, &file.live, &file.path)?,
+            Some(1) => {
+                let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+                    .with_prompt(format!("Delete {}?", file.path.display()))
+                    .default(true)
+                    .interact()?;
+                if confirmed {
+                    run_privileged("rm", &[file.path.as_os_str()])?;
+                    println!("{}", ui::success(&format!("Removed {}", file.path.display())));
+                }
+                return Ok(());
+            }
+            Some(2) => {
+                if live_exists {
+                    let backup = backup_live(&file.live)?;
+                    println!("{}", ui::info(&format!("Backed up current file to {}", backup.display())));
+                }
+                run_privileged("mv", &[file.path.as_os_str(), file.live.as_os_str()])?;
+                println!("{}", ui::success(&format!("Installed {}", file.live.display())));
+                return Ok(());
+            }
+            Some(3) => {
+                if merge_file(file, &live, &pending, config)? {
+                    return Ok(());
+                }
+            }
+            _ => {
+                println!("{}", ui::warning(&format!("Skipping {}", file.path.display())));
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Build a merge candidate, let the user edit it and install the result
+/// Returns true if the merged file was installed
+fn merge_file(file: &PendingFile, live: &str, pending: &str, config: &mut Config) -> Result<bool> {
+    let workdir = tempfile::tempdir()?;
+    let live_tmp = workdir.path().join("current");
+    let pending_tmp = workdir.path().join("new");
+    let merged_tmp = workdir.path().join(
+        file.live.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| "merged".into())
+    );
+    fs::write(&live_tmp, live)?;
+    fs::write(&pending_tmp, pending)?;
+
+    // A .pacsave has no meaningful common ancestor in the cache
+    let base = match file.kind {
+        PendingKind::Pacnew => original_from_cache(&file.live),
+        PendingKind::Pacsave => None,
+    };
+
+    let merged = match base {
+        Some(base) => {
+            println!("{}", ui::info("Using the previous package version from the cache as merge base"));
+            let base_tmp = workdir.path().join("base");
+            fs::write(&base_tmp, base)?;
+            three_way_merge(&live_tmp, &base_tmp, &pending_tmp)?
+        }
+        None => {
+            println!("{}", ui::info("No original file found in the package cache, marking all differences as conflicts"));
+            two_way_merge(&live_tmp, &pending_tmp)?
+        }
+    };
+    fs::write(&merged_tmp, &merged)?;
+
+    let Some(editor_cmd) = resolve_editor(config)? else {
+        println!("{}", ui::warning("No editor available, cannot merge"));
+        return Ok(false);
+    };
+
+    loop {
+        ui::open_in_editor(&editor_cmd, &merged_tmp)?;
+        let result = fs::read_to_string(&merged_tmp)?;
+
+        if result.lines().any(|l| l.starts_with("<<<<<<<") || l.starts_with(">>>>>>>")) {
+            println!("{}", ui::warning("Merged file still contains conflict markers"));
+            let edit_again = Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("Edit again?")
+                .default(true)
+                .interact()?;
+            if edit_again {
+                continue;
+            }
+        }
+
+        print_diff(live, &result, &file.live, &merged_tmp)?;
+
+        let choice = Select::with_theme(&ColorfulTheme::default())
+            .with_prompt("Install merged file?")
+            .items(&["Install", "Edit again", "Cancel merge"])
+            .default(0)
+            .interact_opt()?;
+
+        match choice {
+            Some(0) => {
+                if file.live.exists() {
+                    let backup = backup_live(&file.live)?;
+                    println!("{}", ui::info(&format!("Backed up current file to {}", backup.display())));
+                    // Copy onto the existing file so its owner and mode are kept
+                    run_privileged("cp", &[merged_tmp.as_os_str(), file.live.as_os_str()])?;
+                } else {
+                    run_privileged("install", &["-m".as_ref(), "644".as_ref(), merged_tmp.as_os_str(), file.live.as_os_str()])?;
+                }
+                run_privileged("rm", &[file.path.as_os_str()])?;
+                println!("{}", ui::success(&format!("Merged into {}", file.live.display())));
+                return Ok(true);
+            }
+            Some(1) => continue,
+            _ => return Ok(false),
+        }
+    }
+}
+
+/// Run `diff3 -m` to produce a merged file with conflict markers where needed
+fn three_way_merge(live: &Path, base: &Path, pending: &Path) -> Result<String> {
+    let output = Command::new("diff3")
+        .args(["-m", "-L", "current", "-L", "original", "-L", "pacnew"])
+        .args([live, base, pending])
+        .output()?;
+
+    // diff3 exits with 1 when there are conflicts, 2 on trouble
+    if output.status.code() == Some(2) {
+        return Err(KhazaurError::Pacdiff(format!(
+            "diff3 failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Produce a merge file where every changed hunk becomes a conflict
+fn two_way_merge(live: &Path, pending: &Path) -> Result<String> {
+    let output = Command::new("diff")
+        .arg("--changed-group-format=<<<<<<< current\n%<=======\n%>>>>>>>> new\n")
+        .arg("--old-group-format=<<<<<<< current\n%<=======\n>>>>>>> new\n")
+        .arg("--new-group-format=<<<<<<< current\n=======\n%>>>>>>> new\n")
+        .arg("--unchanged-group-format=%=")
+        .args([live, pending])
+        .output()?;
+
+    if output.status.code() == Some(2) {
+        return Err(KhazaurError::Pacdiff(format!(
+            "diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Print a colored unified diff between the live file and a candidate
+fn print_diff(live: &str, candidate: &str, live_path: &Path, candidate_path: &Path) -> Result<()> {
+    let workdir = tempfile::tempdir()?;
+    let a = workdir.path().join("a");
+    let b = workdir.path().join("b");
+    fs::write(&a, live)?;
+    fs::write(&b, candidate)?;
+
+    let output = Command::new("diff")
+        .arg("-u")
+        .arg("--label").arg(live_path)
+        .arg("--label").arg(candidate_path)
+        .args([&a, &b])
+        .output()?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.is_empty() {
+        println!("{}", ui::info("No differences"));
+        return Ok(());
+    }
+
+    println!();
+    for line in stdout.lines() {
+        println!("{}", colorize_diff_line(line));
+    }
+    println!();
+    Ok(())
+}
+
+fn colorize_diff_line(line: &str) -> String {
+    if line.starts_with("+++") || line.starts_with("---") {
+        line.bold().to_string()
+    } else if line.starts_with("@@") {
+        line.bright_cyan().to_string()
+    } else if line.starts_with('+') {
+        line.green().to_string()
+    } else if line.starts_with('-') {
+        line.red().to_string()
+    } else {
+        line.to_string()
+    }
+}
+
+/// Extract the file as shipped by the previous version of its owning package
+/// from the pacman package cache
+fn original_from_cache(live: &Path) -> Option<String> {
+    let output = Command::new("pacman").arg("-Qqo").arg(live).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let owner = String::from_utf8_lossy(&output.stdout).lines().next()?.trim().to_string();
+
+    let output = Command::new("pacman").args(["-Q", &owner]).output().ok()?;
+    let installed = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)?
+        .to_string();
+
+    // The .pacnew comes from the installed version, so the base the user
+    // edited is the newest cached version older than that
+    let prefix = format!("{}-", owner);
+    let mut candidates: Vec<(String, PathBuf)> = fs::read_dir(PKG_CACHE_DIR)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let filename = e.file_name().to_string_lossy().to_string();
+            if filename.ends_with(".sig") {
+                return None;
+            }
+            let rest = filename.strip_prefix(&prefix)?;
+            let (stem, _) = rest.split_once(".pkg.tar")?;
+            // stem is pkgver-pkgrel-arch
+            let mut parts = stem.rsplitn(3, '-');
+            let _arch = parts.next()?;
+            let pkgrel = parts.next()?;
+            let pkgver = parts.next()?;
+            if pkgver.contains('-') || pkgver.is_empty() {
+                return None;
+            }
+            Some((format!("{}-{}", pkgver, pkgrel), e.path()))
+        })
+        .filter(|(version, _)| crate::pacman::vercmp(version, &installed) == std::cmp::Ordering::Less)
+        .collect();
+
+    candidates.sort_by(|a, b| crate::pacman::vercmp(&a.0, &b.0));
+    let (_, package_file) = candidates.pop()?;
+
+    let member = live.strip_prefix("/").unwrap_or(live);
+    let output = Command::new("bsdtar")
+        .arg("-xOf")
+        .arg(&package_file)
+        .arg(member)
+        .output()
+        .ok()?;
+
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+/// Pick the editor to use: the configured default, or ask the user
+fn resolve_editor(config: &mut Config) -> Result<Option<String>> {
+    if let Some(editor) = &config.default_editor {
+        return Ok(Some(editor.clone()));
+    }
+
+    let editors = ui::detect_editors();
+    match ui::select_editor(&editors)? {
+        Some(selected) => {
+            if ui::prompt_save_default()? {
+                config.default_editor = Some(selected.command.clone());
+                config.save()?;
+            }
+            Ok(Some(selected.command))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Read a file, falling back to sudo for root-only files
+fn read_privileged(path: &Path) -> Result<String> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            let output = Command::new("sudo").arg("cat").arg(path).output()?;
+            if !output.status.success() {
+                return Err(KhazaurError::Io(e));
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Err(e) => Err(KhazaurError::Io(e)),
+    }
+}
+
+/// Copy the live file next to itself with a timestamped suffix
+fn backup_live(live: &Path) -> Result<PathBuf> {
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let mut backup = live.as_os_str().to_os_string();
+    backup.push(format!(".khazaur-{}.bak", timestamp));
+    let backup = PathBuf::from(backup);
+
+    run_privileged("cp", &["-a".as_ref(), live.as_os_str(), backup.as_os_str()])?;
+    Ok(backup)
+}
+
+fn run_privileged(program: &str, args: &[&std::ffi::OsStr]) -> Result<()> {
+    let status = Command::new("sudo")
+        .arg(program)
+        .args(args)
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::Pacdiff(format!("'{}' failed with status: {}", program, status)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn pending_files_map_to_their_live_file() {
+        let file = PendingFile::from_path("/etc/pacman.conf.pacnew").unwrap();
+        assert_eq!(file.live, PathBuf::from("/etc/pacman.conf"));
+        assert_eq!(file.kind, PendingKind::Pacnew);
+        assert_eq!(PendingFile::from_path("/etc/fstab.pacsave").unwrap().kind, PendingKind::Pacsave);
+        assert!(PendingFile::from_path("/etc/fstab").is_none());
+    }
+
+    #[test]
+    fn three_way_merge_keeps_local_and_package_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(dir.path(), "base", "a=1\nb=2\nc=3\n");
+        // The user changed a, the package changed c
+        let live = write(dir.path(), "live", "a=10\nb=2\nc=3\n");
+        let pending = write(dir.path(), "pending", "a=1\nb=2\nc=30\n");
+
+        let merged = three_way_merge(&live, &base, &pending).unwrap();
+        assert_eq!(merged, "a=10\nb=2\nc=30\n");
+    }
+
+    #[test]
+    fn three_way_merge_marks_conflicting_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = write(dir.path(), "base", "a=1\n");
+        let live = write(dir.path(), "live", "a=2\n");
+        let pending = write(dir.path(), "pending", "a=3\n");
+
+        let merged = three_way_merge(&live, &base, &pending).unwrap();
+        assert!(merged.contains("<<<<<<< current\na=2\n"));
+        assert!(merged.contains("a=3\n>>>>>>> pacnew"));
+    }
+
+    #[test]
+    fn two_way_merge_turns_every_change_into_a_conflict() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = write(dir.path(), "live", "same\nold\n");
+        let pending = write(dir.path(), "pending", "same\nnew\nadded\n");
+
+        let merged = two_way_merge(&live, &pending).unwrap();
+        assert_eq!(merged, "same\n<<<<<<< current\nold\n=======\nnew\nadded\n>>>>>>> new\n");
+    }
+
+    #[test]
+    fn identical_files_merge_cleanly() {
+        let dir = tempfile::tempdir().unwrap();
+        let live = write(dir.path(), "live", "x\n");
+        let pending = write(dir.path(), "pending", "x\n");
+        assert_eq!(two_way_merge(&live, &pending).unwrap(), "x\n");
+    }
+}
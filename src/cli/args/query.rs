@@ -1,105 +1,65 @@
+use crate::cli::backend;
+use crate::cli::SourceKind;
 use crate::ui;
-use crate::pacman;
 use crate::error::Result;
 use colored::Colorize;
 
 pub fn query_packages() -> Result<()> {
     println!("{}", ui::section_header("Installed Packages"));
-    
-    // Get pacman packages (repo + AUR)
-    let pacman_packages = pacman::get_installed_packages()?;
-    let aur_packages = pacman::get_installed_aur_packages()?;
-    
-    // Create a set of AUR package names for quick lookup
-    let aur_names: std::collections::HashSet<String> = aur_packages
-        .iter()
-        .map(|(name, _)| name.clone())
-        .collect();
-    
-    // Separate repo and AUR packages
-    let mut repo_packages = Vec::new();
-    for (name, version) in &pacman_packages {
-        if !aur_names.contains(name) {
-            repo_packages.push((name.clone(), version.clone()));
-        }
-    }
-    
-    // Get Flatpak packages
-    let flatpak_packages = if crate::flatpak::is_available() {
-        crate::flatpak::get_installed_flatpaks("")?
-    } else {
-        Vec::new()
-    };
-    
-    // Get Snap packages
-    let snap_packages = if crate::snap::is_available() {
-        crate::snap::get_installed_snaps("")?
-    } else {
-        Vec::new()
-    };
-    
-    // Display summary
-    let total = pacman_packages.len() + flatpak_packages.len() + snap_packages.len();
-    println!("\n{} Total: {}, Repository: {}, AUR: {}, Flatpak: {}, Snap: {}\n",
-        "::".bright_blue().bold(),
-        total,
-        repo_packages.len(),
-        aur_packages.len(),
-        flatpak_packages.len(),
-        snap_packages.len()
-    );
-    
-    // Display repository packages
-    if !repo_packages.is_empty() {
-        println!("{} {} ({})", 
-            "::".bright_blue().bold(),
-            "Repository Packages".bold(),
-            repo_packages.len()
-        );
-        for (name, version) in &repo_packages {
-            println!("  {} {}", name, version.dimmed());
-        }
-        println!();
+
+    // Converted Debian packages removed since; querying leaves the database as it is,
+    // the next upgrade drops their entries
+    let (_, reconciled) = crate::debian::tracking::reconcile()?;
+    if !reconciled.dropped.is_empty() {
+        let names: Vec<&str> = reconciled.dropped.iter().map(|entry| entry.arch_name.as_str()).collect();
+        println!("{}", ui::info(&format!("Tracked Debian packages no longer installed: {}", names.join(", "))));
     }
-    
-    // Display AUR packages
-    if !aur_packages.is_empty() {
-        println!("{} {} ({})", 
-            "::".bright_cyan().bold(),
-            "AUR Packages".bold(),
-            aur_packages.len()
-        );
-        for (name, version) in &aur_packages {
-            println!("  {} {}", name, version.dimmed());
+
+    let backends = backend::all(false)?;
+    let mut installed = Vec::new();
+    for backend in &backends {
+        match backend.list_installed() {
+            Ok(packages) => installed.push((backend, packages)),
+            Err(e) => eprintln!("{}", ui::warning(&format!("Failed to list {}: {}", backend.title(), e))),
         }
-        println!();
     }
-    
-    // Display Flatpak packages
-    if !flatpak_packages.is_empty() {
-        println!("{} {} ({})", 
-            "::".bright_green().bold(),
-            "Flatpak Applications".bold(),
-            flatpak_packages.len()
-        );
-        for app_id in &flatpak_packages {
-            println!("  {}", app_id);
+
+    // Display summary
+    let total: usize = installed.iter().map(|(_, packages)| packages.len()).sum();
+    let counts: Vec<String> = installed
+        .iter()
+        .map(|(backend, packages)| format!("{}: {}", backend.name(), packages.len()))
+        .collect();
+    println!("\n{} Total: {}, {}\n", "::".bright_blue().bold(), total, counts.join(", "));
+
+    // Display each source's packages, with where they came from when known
+    for (backend, packages) in &installed {
+        if packages.is_empty() {
+            continue;
         }
-        println!();
-    }
-    
-    // Display Snap packages
-    if !snap_packages.is_empty() {
-        println!("{} {} ({})", 
-            "::".bright_yellow().bold(),
-            "Snap Packages".bold(),
-            snap_packages.len()
-        );
-        for name in &snap_packages {
-            println!("  {}", name);
+
+        let marker = match backend.kind() {
+            SourceKind::Repo => "::".bright_blue(),
+            SourceKind::Aur => "::".bright_cyan(),
+            SourceKind::Debian => "::".bright_red(),
+            SourceKind::Flatpak => "::".bright_green(),
+            SourceKind::Snap => "::".bright_yellow(),
+            SourceKind::AppImage => "::".bright_magenta(),
+            SourceKind::Plugin => "::".bright_white(),
+        };
+        println!("{} {} ({})", marker.bold(), backend.title().bold(), packages.len());
+        for pkg in packages {
+            let mut line = format!("  {}", pkg.name);
+            if !pkg.version.is_empty() {
+                line.push_str(&format!(" {}", pkg.version.dimmed()));
+            }
+            if let Some(origin) = &pkg.origin {
+                line.push_str(&format!(" {}", format!("({})", origin).dimmed()));
+            }
+            println!("{}", line);
         }
         println!();
     }
-    
+
     Ok(())
 }
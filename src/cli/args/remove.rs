@@ -1,69 +1,68 @@
+use crate::cli::backend;
+use crate::cli::SourceKind;
+use crate::history::Transaction;
 use crate::ui;
 use crate::pacman;
+use crate::pacman::db::PackageDb;
 use crate::error::Result;
+use colored::Colorize;
+use std::collections::HashSet;
 
 use dialoguer::{theme::ColorfulTheme, MultiSelect, Confirm};
 
+/// One line of the removal impact report
+struct ImpactItem {
+    source: String,
+    name: String,
+    version: Option<String>,
+    size: Option<u64>,
+    /// Pulled in by the cascade rather than selected
+    cascaded: bool,
+}
+
 /// Remove packages
-pub fn remove_packages(packages: &[String]) -> Result<()> {
+pub fn remove_packages(packages: &[String], dry_run: bool) -> Result<()> {
     println!("{}", ui::section_header("Removing Packages"));
     
-    let mut pacman_packages = Vec::new();
-    let mut flatpak_packages = Vec::new();
-    let mut snap_packages = Vec::new();
+    let backends = backend::all(false)?;
+    // Converted Debian packages are foreign to pacman too
+    let tracked = crate::debian::tracking::TrackingDb::load().unwrap_or_else(|e| {
+        eprintln!("{}", ui::warning(&format!("Could not read the Debian package database: {}", e)));
+        Default::default()
+    });
+    // Selected packages with the index of their backend
+    let mut selected: Vec<(usize, String)> = Vec::new();
     
     for query in packages {
-        // Search across all sources
-        let pacman_matches = pacman::search_installed_packages(query)?;
-        let flatpak_matches = if crate::flatpak::is_available() {
-            crate::flatpak::get_installed_flatpaks(query)?
-        } else {
-            Vec::new()
-        };
-        let snap_matches = if crate::snap::is_available() {
-            crate::snap::get_installed_snaps(query)?
-        } else {
-            Vec::new()
-        };
+        // A Debian package name stands for the package it was converted to
+        let query = tracked.find(query).map_or(query.as_str(), |entry| entry.arch_name.as_str());
         
-        let total_matches = pacman_matches.len() + flatpak_matches.len() + snap_matches.len();
+        // Search across all sources
+        let mut matches = Vec::new();
+        for (index, backend) in backends.iter().enumerate() {
+            match backend.find_installed(query) {
+                Ok(found) => matches.extend(found.into_iter().map(|pkg| (index, pkg.name))),
+                Err(e) => eprintln!("{}", ui::warning(&format!("Failed to list {}: {}", backend.title(), e))),
+            }
+        }
         
-        if total_matches == 0 {
+        if matches.is_empty() {
             println!("{}", ui::warning(&format!("No installed packages found matching '{}'", query)));
             continue;
-        } else if total_matches == 1 {
+        } else if matches.len() == 1 {
             // Single match, add directly
-            if !pacman_matches.is_empty() {
-                println!("{}", ui::info(&format!("Found (pacman): {}", pacman_matches[0])));
-                pacman_packages.push(pacman_matches[0].clone());
-            } else if !flatpak_matches.is_empty() {
-                println!("{}", ui::info(&format!("Found (flatpak): {}", flatpak_matches[0])));
-                flatpak_packages.push(flatpak_matches[0].clone());
-            } else {
-                println!("{}", ui::info(&format!("Found (snap): {}", snap_matches[0])));
-                snap_packages.push(snap_matches[0].clone());
-            }
+            let (index, name) = matches.remove(0);
+            println!("{}", ui::info(&format!("Found ({}): {}", backends[index].prefix(), name)));
+            selected.push((index, name));
         } else {
             // Multiple matches, show selection UI with source indicators
             
             println!("\n{}", ui::info(&format!("Multiple packages found matching '{}':", query)));
             
-            // Build items with source indicators
-            let mut items = Vec::new();
-            let mut sources = Vec::new();
-            
-            for pkg in &pacman_matches {
-                items.push(format!("{} (pacman)", pkg));
-                sources.push(("pacman", pkg.clone()));
-            }
-            for pkg in &flatpak_matches {
-                items.push(format!("{} (flatpak)", pkg));
-                sources.push(("flatpak", pkg.clone()));
-            }
-            for pkg in &snap_matches {
-                items.push(format!("{} (snap)", pkg));
-                sources.push(("snap", pkg.clone()));
-            }
+            let items: Vec<String> = matches
+                .iter()
+                .map(|(index, name)| format!("{} ({})", name, backends[*index].prefix()))
+                .collect();
             
             let selections = MultiSelect::with_theme(&ColorfulTheme::default())
                 .with_prompt("Select packages to remove (Space to select, Enter to confirm)")
@@ -77,35 +76,93 @@ pub fn remove_packages(packages: &[String]) -> Result<()> {
             }
             
             for &idx in &selections {
-                let (source, pkg) = &sources[idx];
-                match *source {
-                    "pacman" => pacman_packages.push(pkg.clone()),
-                    "flatpak" => flatpak_packages.push(pkg.clone()),
-                    "snap" => snap_packages.push(pkg.clone()),
-                    _ => {}
-                }
+                selected.push(matches[idx].clone());
             }
         }
     }
     
-    let total_to_remove = pacman_packages.len() + flatpak_packages.len() + snap_packages.len();
-    
-    if total_to_remove == 0 {
+    if selected.is_empty() {
         println!("{}", ui::warning("No packages to remove"));
         return Ok(());
     }
     
-    // Show summary
-    if !pacman_packages.is_empty() {
-        println!("\n{}", ui::info(&format!("Pacman packages: {}", pacman_packages.join(", "))));
+    // Pacman removes packages of every source it manages in one transaction
+    let pacman_packages: Vec<String> = selected
+        .iter()
+        .filter(|(index, _)| backends[*index].kind().is_pacman())
+        .map(|(_, name)| name.clone())
+        .collect();
+    let names_from = |index: usize| -> Vec<String> {
+        selected.iter().filter(|(selected, _)| *selected == index).map(|(_, name)| name.clone()).collect()
+    };
+    
+    // Work out everything that goes with the selection
+    let db = PackageDb::load_local()?;
+    let pacman_cascade = db.removal_cascade(&pacman_packages);
+    let cascades = backend::removal_cascades(&backends, &selected)?;
+    let foreign: HashSet<String> = pacman::get_installed_aur_packages()?.into_iter().map(|(name, _)| name).collect();
+    let pacman_kind = |name: &str| {
+        if tracked.contains(name) {
+            SourceKind::Debian
+        } else if foreign.contains(name) {
+            SourceKind::Aur
+        } else {
+            SourceKind::Repo
+        }
+    };
+
+    let mut impact = Vec::new();
+    for (name, cascaded) in pacman_packages
+        .iter()
+        .map(|n| (n, false))
+        .chain(pacman_cascade.iter().map(|n| (n, true)))
+    {
+        let pkg = db.get(name);
+        impact.push(ImpactItem {
+            source: pacman_kind(name).label().to_string(),
+            name: name.clone(),
+            version: pkg.map(|p| p.version.clone()),
+            size: pkg.map(|p| p.installed_size),
+            cascaded,
+        });
     }
-    if !flatpak_packages.is_empty() {
-        println!("{}", ui::info(&format!("Flatpak apps: {}", flatpak_packages.join(", "))));
+    for (index, backend) in backends.iter().enumerate().filter(|(_, backend)| !backend.kind().is_pacman()) {
+        let names = names_from(index);
+        let installed = if names.is_empty() { Vec::new() } else { backend.list_installed()? };
+        for name in names {
+            let version = installed
+                .iter()
+                .find(|pkg| pkg.name == name && !pkg.version.is_empty())
+                .map(|pkg| pkg.version.clone());
+            impact.push(ImpactItem {
+                source: backend.prefix().to_string(),
+                size: backend.installed_size(&name),
+                name,
+                version,
+                cascaded: false,
+            });
+        }
+        for name in &cascades[index] {
+            impact.push(ImpactItem {
+                source: backend.prefix().to_string(),
+                name: name.clone(),
+                version: None,
+                size: backend.installed_size(name),
+                cascaded: true,
+            });
+        }
     }
-    if !snap_packages.is_empty() {
-        println!("{}", ui::info(&format!("Snap packages: {}", snap_packages.join(", "))));
+
+    print_impact(&impact);
+
+    let all_pacman: Vec<String> = pacman_packages.iter().chain(&pacman_cascade).cloned().collect();
+    print_broken(&db, &db.broken_by_removal(&all_pacman));
+
+    if dry_run {
+        println!("\n{}", ui::info("Dry run: nothing was removed"));
+        return Ok(());
     }
-    
+
     // Ask for confirmation
     
     let confirmed = Confirm::with_theme(&ColorfulTheme::default())
@@ -118,15 +175,25 @@ pub fn remove_packages(packages: &[String]) -> Result<()> {
         return Ok(());
     }
     
+    // Pacman removes the cascade in the same transaction
+    let pacman_packages = all_pacman;
+
     // Remove pacman packages (pass --noconfirm since user already confirmed)
     if !pacman_packages.is_empty() {
+        let mut tagged = Vec::new();
+        for name in &pacman_packages {
+            tagged.push((pacman_kind(name), name.clone()));
+        }
+        let tx = Transaction::begin_mixed("remove", tagged);
+
         match pacman::remove_packages(&pacman_packages, &vec!["--noconfirm".to_string()]) {
             Ok(_) => {
                 println!("{}", ui::success("Pacman packages removed successfully"));
-                let _ = crate::history::log_action("remove", &pacman_packages, true);
+                let _ = tx.finish(true);
+                let _ = crate::reasons::forget(SourceKind::Debian, &pacman_packages);
+                let _ = crate::debian::tracking::forget(&pacman_packages);
             },
             Err(e) => {
-                let _ = crate::history::log_action("remove", &pacman_packages, false);
                 let error_msg = e.to_string();
                 
                 // Check if it's a dependency conflict
@@ -143,40 +210,104 @@ pub fn remove_packages(packages: &[String]) -> Result<()> {
                     
                     if force_remove {
                         println!("{}", ui::warning("Force removing packages (ignoring dependencies)..."));
-                        pacman::remove_packages(&pacman_packages, &vec!["-dd".to_string(), "--noconfirm".to_string()])?;
+                        let result = pacman::remove_packages(&pacman_packages, &vec!["-dd".to_string(), "--noconfirm".to_string()]);
+                        match &result {
+                            Ok(()) => {
+                                let _ = tx.finish(true);
+                                let _ = crate::reasons::forget(SourceKind::Debian, &pacman_packages);
+                                let _ = crate::debian::tracking::forget(&pacman_packages);
+                            }
+                            Err(e) => { let _ = tx.fail(e); }
+                        }
+                        result?;
                     } else {
                         println!("{}", ui::warning("Removal cancelled"));
                         return Ok(());
                     }
                 } else {
+                    let _ = tx.fail(&e);
                     return Err(e);
                 }
             }
         }
     }
     
-    // Remove flatpak packages
-    for app_id in &flatpak_packages {
-        if let Err(e) = crate::flatpak::uninstall_flatpak(app_id) {
-            eprintln!("{}", ui::error(&format!("Failed to remove flatpak {}: {}", app_id, e)));
-            let _ = crate::history::log_action("remove", &[app_id.clone()], false);
-        } else {
-            println!("{}", ui::success(&format!("Removed flatpak: {}", app_id)));
-            let _ = crate::history::log_action("remove", &[app_id.clone()], true);
+    // Remove packages of the other sources one by one
+    for (index, backend) in backends.iter().enumerate().filter(|(_, backend)| !backend.kind().is_pacman()) {
+        let kind = backend.kind();
+        // The selection first, then what only it needed
+        for name in names_from(index).into_iter().chain(cascades[index].iter().cloned()) {
+            let recorded = backend.record_name(&name);
+            let tx = Transaction::begin("remove", kind, std::slice::from_ref(&recorded));
+            if let Err(e) = backend.remove(&name) {
+                eprintln!("{}", ui::error(&format!("Failed to remove {} {}: {}", backend.prefix(), name, e)));
+                let _ = tx.fail(&e);
+            } else {
+                println!("{}", ui::success(&format!("Removed {}: {}", backend.prefix(), name)));
+                let _ = tx.finish(true);
+                let _ = crate::reasons::forget(kind, &[recorded]);
+            }
         }
     }
     
-    // Remove snap packages
-    for pkg in &snap_packages {
-        if let Err(e) = crate::snap::uninstall_snap(pkg) {
-            eprintln!("{}", ui::error(&format!("Failed to remove snap {}: {}", pkg, e)));
-            let _ = crate::history::log_action("remove", &[pkg.clone()], false);
+    println!("\n{}", ui::success("Package removal complete"));
+    Ok(())
+}
+
+fn print_impact(items: &[ImpactItem]) {
+    println!("\n{} {}", "::".bright_blue().bold(), "Packages to remove:".bold());
+
+    for item in items {
+        let name = match item.version {
+            Some(ref version) => format!("{} {}", item.name, version),
+            None => item.name.clone(),
+        };
+        let size = item.size.map(ui::format_size).unwrap_or_else(|| "?".to_string());
+        let note = if item.cascaded {
+            match item.source.as_str() {
+                "flatpak" => "unused runtime",
+                _ => "no longer needed",
+            }
         } else {
-            println!("{}", ui::success(&format!("Removed snap: {}", pkg)));
-            let _ = crate::history::log_action("remove", &[pkg.clone()], true);
+            ""
+        };
+
+        let line = format!("  {:<45} {:>10}  {:<8} {}", name, size, item.source, note.dimmed());
+        if item.cascaded {
+            println!("{}", line);
+        } else {
+            println!("{}", line.bold());
         }
     }
-    
-    println!("\n{}", ui::success("Package removal complete"));
-    Ok(())
+
+    let total: u64 = items.iter().filter_map(|i| i.size).sum();
+    let unknown = items.iter().filter(|i| i.size.is_none()).count();
+    let cascaded = items.iter().filter(|i| i.cascaded).count();
+
+    println!();
+    println!("{}", ui::info(&format!(
+        "{} package(s), {} pulled in as unneeded dependencies",
+        items.len(),
+        cascaded
+    )));
+    if unknown > 0 {
+        println!("{}", ui::info(&format!("Space freed: {} (size of {} unknown)", ui::format_size(total), unknown)));
+    } else {
+        println!("{}", ui::info(&format!("Space freed: {}", ui::format_size(total))));
+    }
+}
+
+/// Warn about installed packages that would lose a required dependency
+fn print_broken(db: &PackageDb, broken: &[(String, String)]) {
+    if broken.is_empty() {
+        return;
+    }
+
+    println!("\n{}", ui::warning("These installed packages still need packages being removed:"));
+    for (dependent, required) in broken {
+        let explicit = db.get(dependent).is_some_and(|p| p.is_explicit());
+        let marker = if explicit { " (explicitly installed)".bold().to_string() } else { String::new() };
+        println!("  {} requires {}{}", dependent.bold(), required, marker);
+    }
+    println!("{}", ui::info("Pacman will refuse the removal unless it is forced"));
 }
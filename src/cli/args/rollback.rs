@@ -0,0 +1,317 @@
+use crate::cli::SourceKind;
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use crate::history::{InstalledVersion, PackageChange, Transaction};
+use crate::ui;
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::downgrade::parse_package_filename;
+
+const CACHE_DIR: &str = "/var/cache/pacman/pkg";
+
+/// How one package is brought back to its state before the transaction
+enum Step {
+    /// Reinstall a package file with pacman -U
+    PacmanFile(PathBuf),
+    /// Remove a pacman package the transaction installed
+    PacmanRemove,
+    /// Deploy an earlier Flatpak commit
+    FlatpakCommit(String),
+    /// Uninstall a Flatpak ref the transaction installed
+    FlatpakRemove,
+    /// Revert to a snap revision snapd still keeps
+    SnapRevert(String),
+    /// Remove a snap the transaction installed
+    SnapRemove,
+    /// Remove an AppImage the transaction installed
+    AppImageRemove,
+}
+
+impl Step {
+    fn describe(&self) -> String {
+        match self {
+            Step::PacmanFile(path) => format!("from {}", path.display()),
+            Step::PacmanRemove | Step::FlatpakRemove | Step::SnapRemove | Step::AppImageRemove => "remove".to_string(),
+            Step::FlatpakCommit(commit) => format!("commit {}", commit.chars().take(12).collect::<String>()),
+            Step::SnapRevert(revision) => format!("revision {}", revision),
+        }
+    }
+}
+
+/// Undo a recorded transaction, reinstalling the versions it replaced
+pub fn rollback(txid: u64, config: &Config) -> Result<()> {
+    println!("{}", ui::section_header(&format!("Rollback Transaction #{}", txid)));
+
+    let entry = crate::history::get_entry(txid)?
+        .ok_or_else(|| KhazaurError::History(format!("No transaction #{} in history", txid)))?;
+
+    let changes: Vec<&PackageChange> = entry.changes.iter().filter(|c| c.changed()).collect();
+    if changes.is_empty() {
+        println!("{}", ui::info("The transaction did not change any recorded package versions"));
+        return Ok(());
+    }
+
+    let package_files = package_files(config);
+    let mut restorable = Vec::new();
+    let mut unrestorable = Vec::new();
+    for change in changes {
+        match plan(change, &package_files) {
+            Ok(step) => restorable.push((change, step)),
+            Err(reason) => unrestorable.push((change, reason)),
+        }
+    }
+
+    let version = |v: &Option<InstalledVersion>| match v {
+        Some(v) => v.version.clone(),
+        None => "not installed".to_string(),
+    };
+
+    if !restorable.is_empty() {
+        println!("\n{} {}", "::".bright_blue().bold(), format!("Can be restored ({}):", restorable.len()).bold());
+        for (change, step) in &restorable {
+            println!("  {} {} {} {}  {}",
+                change.name.bold(),
+                format!("[{}]", change.source.label()).dimmed(),
+                version(&change.after).red(),
+                format!("-> {}", version(&change.before)).green(),
+                step.describe().dimmed()
+            );
+        }
+    }
+
+    if !unrestorable.is_empty() {
+        println!("\n{} {}", "::".yellow().bold(), format!("Cannot be restored ({}):", unrestorable.len()).bold());
+        for (change, reason) in &unrestorable {
+            println!("  {} {} {}  {}",
+                change.name.bold(),
+                format!("[{}]", change.source.label()).dimmed(),
+                version(&change.before),
+                reason.dimmed()
+            );
+        }
+    }
+
+    if restorable.is_empty() {
+        println!("\n{}", ui::warning("Nothing from this transaction can be restored"));
+        return Ok(());
+    }
+
+    println!();
+    let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt("Proceed with rollback?")
+        .default(true)
+        .interact()?;
+
+    if !confirmed {
+        println!("{}", ui::warning("Rollback cancelled"));
+        return Ok(());
+    }
+
+    let tx = Transaction::begin_mixed(
+        "rollback",
+        restorable.iter().map(|(c, _)| (c.source, c.name.clone())).collect(),
+    );
+    let failed = apply(&restorable);
+    if !failed.is_empty() {
+        let error = KhazaurError::Rollback(format!(
+            "{} of {} package(s) could not be restored: {}",
+            failed.len(),
+            restorable.len(),
+            failed.join(", ")
+        ));
+        let new_id = tx.fail(&error);
+        if failed.len() < restorable.len() {
+            println!("\n{}", ui::warning(&format!("Transaction #{} partially rolled back", txid)));
+        }
+        if let Ok(id) = new_id {
+            println!("{}", ui::info(&format!("Recorded as failed transaction #{}", id)));
+        }
+        return Err(error);
+    }
+
+    let new_id = tx.finish(true);
+    println!("\n{}", ui::success(&format!("Transaction #{} rolled back", txid)));
+    if let Ok(id) = new_id {
+        println!("{}", ui::info(&format!("Recorded as transaction #{}", id)));
+    }
+    Ok(())
+}
+
+/// Work out how to restore one package, or why it can't be
+fn plan(change: &PackageChange, package_files: &[(String, String, PathBuf)]) -> std::result::Result<Step, String> {
+    let Some(before) = &change.before else {
+        return match change.source {
+            SourceKind::Flatpak => Ok(Step::FlatpakRemove),
+            SourceKind::Snap => Ok(Step::SnapRemove),
+            SourceKind::AppImage => Ok(Step::AppImageRemove),
+            // The history doesn't record which plugin a package came from
+            SourceKind::Plugin => Err("backend plugin packages can't be rolled back".to_string()),
+            _ => Ok(Step::PacmanRemove),
+        };
+    };
+
+    match change.source {
+        SourceKind::Repo | SourceKind::Aur | SourceKind::Debian => package_files
+            .iter()
+            .find(|(name, version, _)| *name == change.name && *version == before.version)
+            .map(|(_, _, path)| Step::PacmanFile(path.clone()))
+            .ok_or_else(|| format!("no cached package file for {}", before.version)),
+        SourceKind::Flatpak => before
+            .revision
+            .clone()
+            .map(Step::FlatpakCommit)
+            .ok_or_else(|| "commit was not recorded".to_string()),
+        SourceKind::Snap => match &before.revision {
+            // snapd only keeps a few revisions and drops them all on removal
+            Some(revision) if crate::snap::has_revision(&change.name, revision) => {
+                Ok(Step::SnapRevert(revision.clone()))
+            }
+            Some(revision) => Err(format!("revision {} is no longer kept by snapd", revision)),
+            None => Err("revision was not recorded".to_string()),
+        },
+        // An update replaces the file in place
+        SourceKind::AppImage => Err("earlier AppImage builds are not kept".to_string()),
+        SourceKind::Plugin => Err("backend plugin packages can't be rolled back".to_string()),
+    }
+}
+
+/// Restore every planned package, going on past failures.
+/// Returns the names of the packages that could not be restored.
+fn apply(steps: &[(&PackageChange, Step)]) -> Vec<String> {
+    let mut failed = Vec::new();
+
+    // pacman packages go in one transaction each way so dependencies stay consistent
+    let files: Vec<(&str, &Path)> = steps
+        .iter()
+        .filter_map(|(change, step)| match step {
+            Step::PacmanFile(path) => Some((change.name.as_str(), path.as_path())),
+            _ => None,
+        })
+        .collect();
+    if !files.is_empty() {
+        let result = Command::new("sudo")
+            .args(["pacman", "-U"])
+            .args(files.iter().map(|(_, path)| path))
+            .status();
+        if !matches!(result, Ok(status) if status.success()) {
+            eprintln!("{}", ui::error("Failed to reinstall previous versions"));
+            failed.extend(files.iter().map(|(name, _)| name.to_string()));
+        }
+    }
+
+    let removals: Vec<String> = steps
+        .iter()
+        .filter(|(_, step)| matches!(step, Step::PacmanRemove))
+        .map(|(change, _)| change.name.clone())
+        .collect();
+    if !removals.is_empty()
+        && let Err(e) = crate::pacman::remove_packages(&removals, &["--noconfirm".to_string()])
+    {
+        eprintln!("{}", ui::error(&format!("Failed to remove {}: {}", removals.join(", "), e)));
+        failed.extend(removals);
+    }
+
+    for (change, step) in steps {
+        let result = match step {
+            Step::FlatpakCommit(commit) => crate::flatpak::install::deploy_commit(&change.name, commit),
+            Step::FlatpakRemove => crate::flatpak::uninstall_flatpak(&change.name),
+            Step::SnapRevert(revision) => crate::snap::revert(&change.name, revision),
+            Step::SnapRemove => crate::snap::uninstall_snap(&change.name),
+            Step::AppImageRemove => crate::appimage::uninstall(&change.name),
+            Step::PacmanFile(_) | Step::PacmanRemove => continue,
+        };
+
+        if let Err(e) = result {
+            eprintln!("{}", ui::error(&format!("Failed to restore {}: {}", change.name, e)));
+            failed.push(change.name.clone());
+        }
+    }
+
+    failed
+}
+
+/// Package files that can be reinstalled, as (name, version, path): the pacman cache,
+/// khazaur's package cache, AUR build directories and converted Debian packages
+fn package_files(config: &Config) -> Vec<(String, String, PathBuf)> {
+    let mut dirs = vec![PathBuf::from(CACHE_DIR), config.pkg_dir.clone(), config.cache_dir.join("debian")];
+    if let Ok(entries) = std::fs::read_dir(&config.clone_dir) {
+        dirs.extend(entries.flatten().map(|e| e.path()).filter(|p| p.is_dir()));
+    }
+
+    let mut files = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if let Some(file) = parse_package_filename(&entry.file_name().to_string_lossy()) {
+                files.push((file.name, file.version, entry.path()));
+            }
+        }
+    }
+
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(source: SourceKind, before: Option<(&str, Option<&str>)>) -> PackageChange {
+        let version = |(version, revision): (&str, Option<&str>)| InstalledVersion {
+            version: version.to_string(),
+            revision: revision.map(str::to_string),
+        };
+        PackageChange {
+            source,
+            name: "foo".to_string(),
+            before: before.map(version),
+            after: Some(version(("2.0-1", Some("new")))),
+        }
+    }
+
+    #[test]
+    fn installed_packages_are_removed() {
+        let plan_for = |source| plan(&change(source, None), &[]);
+        assert!(matches!(plan_for(SourceKind::Repo), Ok(Step::PacmanRemove)));
+        assert!(matches!(plan_for(SourceKind::Aur), Ok(Step::PacmanRemove)));
+        assert!(matches!(plan_for(SourceKind::Flatpak), Ok(Step::FlatpakRemove)));
+        assert!(matches!(plan_for(SourceKind::Snap), Ok(Step::SnapRemove)));
+        assert!(matches!(plan_for(SourceKind::AppImage), Ok(Step::AppImageRemove)));
+        assert!(plan_for(SourceKind::Plugin).is_err());
+    }
+
+    #[test]
+    fn pacman_packages_need_a_file_of_the_earlier_version() {
+        let files = vec![
+            ("foo".to_string(), "1.0-1".to_string(), PathBuf::from("/cache/foo-1.0-1-x86_64.pkg.tar.zst")),
+            ("bar".to_string(), "1.5-1".to_string(), PathBuf::from("/cache/bar-1.5-1-x86_64.pkg.tar.zst")),
+        ];
+
+        match plan(&change(SourceKind::Repo, Some(("1.0-1", None))), &files) {
+            Ok(Step::PacmanFile(path)) => assert_eq!(path, files[0].2),
+            _ => panic!("expected the cached file to be reinstalled"),
+        }
+        assert!(plan(&change(SourceKind::Aur, Some(("1.5-1", None))), &files).is_err());
+        assert!(plan(&change(SourceKind::Debian, Some(("0.9-1", None))), &[]).is_err());
+    }
+
+    #[test]
+    fn flatpaks_and_snaps_need_a_recorded_revision() {
+        match plan(&change(SourceKind::Flatpak, Some(("1.0", Some("abc123")))), &[]) {
+            Ok(Step::FlatpakCommit(commit)) => assert_eq!(commit, "abc123"),
+            _ => panic!("expected the earlier commit to be deployed"),
+        }
+        assert!(plan(&change(SourceKind::Flatpak, Some(("1.0", None))), &[]).is_err());
+
+        assert!(plan(&change(SourceKind::Snap, Some(("1.0", None))), &[]).is_err());
+        // Not kept in /var/lib/snapd/snaps
+        assert!(plan(&change(SourceKind::Snap, Some(("1.0", Some("0")))), &[]).is_err());
+
+        assert!(plan(&change(SourceKind::AppImage, Some(("1.0", None))), &[]).is_err());
+        assert!(plan(&change(SourceKind::Plugin, Some(("1.0", None))), &[]).is_err());
+    }
+}
@@ -1,31 +1,349 @@
-use crate::error::Result;
-use std::process::Command;
-
-
-
-pub fn get_flat_tree(package: &str) -> Result<Vec<(usize, String)>> {
-    if Command::new("which").arg("pactree").output().map(|o| o.status.success()).unwrap_or(false) {
-        let output = Command::new("pactree")
-            //.arg("-u") 
-            .arg(package)
-            .output()?;
-            
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let mut result = Vec::new();
-            
-            for line in stdout.lines() {
-                 // Counts logic
-                 // "──" match count?
-                 // pactree uses "└─" or "├─"
-                 let depth = line.matches("─").count();
-                 let clean_name = line.trim_start_matches(|c| c == ' ' || c == '│' || c == '├' || c == '└' || c == '─').trim();
-                 result.push((depth, clean_name.to_string()));
+use crate::aur::{AurClient, AurPackage};
+use crate::error::{KhazaurError, Result};
+use crate::pacman::db::{dep_name, PackageDb};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// How the dependency tree should be built
+#[derive(Debug, Clone)]
+pub struct TreeOptions {
+    /// Show packages that depend on the root instead of its dependencies
+    pub reverse: bool,
+    /// Stop expanding below this depth (root is depth 0)
+    pub max_depth: Option<usize>,
+    /// Include optional dependencies
+    pub optional: bool,
+    /// Expand each package only once, later occurrences are marked as repeated
+    pub dedup: bool,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            reverse: false,
+            max_depth: None,
+            optional: false,
+            dedup: true,
+        }
+    }
+}
+
+/// Where a package in the tree was found
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeSource {
+    /// Installed (local database)
+    Local,
+    /// Available from a sync repository
+    Repo(String),
+    /// Available from the AUR
+    Aur,
+    /// Not found anywhere
+    Missing,
+}
+
+impl NodeSource {
+    pub fn label(&self) -> &str {
+        match self {
+            NodeSource::Local => "installed",
+            NodeSource::Repo(repo) => repo,
+            NodeSource::Aur => "aur",
+            NodeSource::Missing => "not found",
+        }
+    }
+}
+
+/// A package in the dependency tree
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeNode {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub source: NodeSource,
+    /// Dependency this package satisfies through provides (e.g. `sh` for bash)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub satisfies: Option<String>,
+    pub optional: bool,
+    /// Already expanded elsewhere in the tree (or a dependency cycle)
+    pub repeated: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    /// One-line description used by the terminal and GTK views
+    pub fn label(&self) -> String {
+        let mut label = self.name.clone();
+        if let Some(ref version) = self.version {
+            label.push(' ');
+            label.push_str(version);
+        }
+        label.push_str(&format!(" [{}]", self.source.label()));
+        if let Some(ref satisfies) = self.satisfies {
+            label.push_str(&format!(" (provides {})", satisfies));
+        }
+        if self.optional {
+            label.push_str(" (optional)");
+        }
+        if self.repeated {
+            label.push_str(" (already shown)");
+        }
+        label
+    }
+
+    /// Depth-first (depth, label) list, the format the TUI and GUI render
+    pub fn flatten(&self) -> Vec<(usize, String)> {
+        let mut items = Vec::new();
+        self.flatten_into(0, &mut items);
+        items
+    }
+
+    fn flatten_into(&self, depth: usize, items: &mut Vec<(usize, String)>) {
+        items.push((depth, self.label()));
+        for child in &self.children {
+            child.flatten_into(depth + 1, items);
+        }
+    }
+}
+
+/// Package metadata the builder needs, from whichever source had it
+struct NodeInfo<'a> {
+    name: &'a str,
+    version: &'a str,
+    source: NodeSource,
+    depends: &'a [String],
+    optdepends: &'a [String],
+}
+
+/// Local, sync and AUR package metadata
+struct Sources {
+    local: PackageDb,
+    sync: PackageDb,
+    aur: HashMap<String, AurPackage>,
+}
+
+impl Sources {
+    fn lookup(&self, dep: &str) -> Option<NodeInfo<'_>> {
+        if let Some(pkg) = self.local.resolve(dep) {
+            return Some(NodeInfo {
+                name: &pkg.name,
+                version: &pkg.version,
+                source: NodeSource::Local,
+                depends: &pkg.depends,
+                optdepends: &pkg.optdepends,
+            });
+        }
+
+        if let Some(pkg) = self.sync.resolve(dep) {
+            return Some(NodeInfo {
+                name: &pkg.name,
+                version: &pkg.version,
+                source: NodeSource::Repo(pkg.repo.clone().unwrap_or_default()),
+                depends: &pkg.depends,
+                optdepends: &pkg.optdepends,
+            });
+        }
+
+        let name = dep_name(dep);
+        let pkg = self.aur.get(name).or_else(|| {
+            self.aur
+                .values()
+                .find(|p| p.provides.iter().any(|provide| dep_name(provide) == name))
+        })?;
+
+        Some(NodeInfo {
+            name: &pkg.name,
+            version: &pkg.version,
+            source: NodeSource::Aur,
+            depends: &pkg.depends,
+            optdepends: &pkg.opt_depends,
+        })
+    }
+
+    /// Query the AUR for everything reachable that the databases don't know,
+    /// one batch request per tree level
+    async fn fetch_aur(&mut self, root: &str, options: &TreeOptions) -> Result<()> {
+        let client = AurClient::new()?;
+        let mut queried: HashSet<String> = HashSet::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut frontier = vec![root.to_string()];
+        let mut depth = 0;
+
+        while !frontier.is_empty() {
+            let missing: Vec<String> = frontier
+                .iter()
+                .filter(|dep| self.lookup(dep).is_none())
+                .map(|dep| dep_name(dep).to_string())
+                .filter(|name| queried.insert(name.clone()))
+                .collect();
+
+            if !missing.is_empty() {
+                match client.info_batch(&missing).await {
+                    Ok(packages) => {
+                        for pkg in packages {
+                            self.aur.insert(pkg.name.clone(), pkg);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("{}", crate::ui::warning(&format!("Failed to query AUR: {}", e)));
+                        return Ok(());
+                    }
+                }
+            }
+
+            if options.max_depth.is_some_and(|max| depth >= max) {
+                break;
             }
-            return Ok(result);
+
+            let mut next = Vec::new();
+            for dep in &frontier {
+                let Some(info) = self.lookup(dep) else {
+                    continue;
+                };
+                if !seen.insert(info.name.to_string()) {
+                    continue;
+                }
+                let optional = if options.optional { info.optdepends } else { &[] };
+                for child in info.depends.iter().chain(optional) {
+                    next.push(dep_name(child).to_string());
+                }
+            }
+
+            frontier = next;
+            depth += 1;
         }
+
+        Ok(())
+    }
+}
+
+/// Build a dependency tree from the pacman databases, falling back to the AUR
+/// for packages that are neither installed nor in a sync repository
+pub async fn build_tree(package: &str, options: &TreeOptions) -> Result<TreeNode> {
+    let mut sources = Sources {
+        local: PackageDb::load_local()?,
+        sync: PackageDb::load_sync().unwrap_or_default(),
+        aur: HashMap::new(),
+    };
+
+    let mut expanded = HashSet::new();
+    let mut ancestors = Vec::new();
+
+    if options.reverse {
+        let root = sources
+            .local
+            .resolve(package)
+            .ok_or_else(|| KhazaurError::PackageNotFound(format!("{} is not installed", package)))?;
+        let reverse = sources.local.reverse_dependencies();
+
+        return Ok(build_reverse(&sources.local, &reverse, &root.name, false, 0, options, &mut ancestors, &mut expanded));
+    }
+
+    sources.fetch_aur(package, options).await?;
+    if sources.lookup(package).is_none() {
+        return Err(KhazaurError::PackageNotFound(package.to_string()));
+    }
+
+    Ok(build_forward(&sources, package, false, 0, options, &mut ancestors, &mut expanded))
+}
+
+fn build_forward(
+    sources: &Sources,
+    dep: &str,
+    optional: bool,
+    depth: usize,
+    options: &TreeOptions,
+    ancestors: &mut Vec<String>,
+    expanded: &mut HashSet<String>,
+) -> TreeNode {
+    let requested = dep_name(dep);
+    let Some(info) = sources.lookup(dep) else {
+        return TreeNode {
+            name: requested.to_string(),
+            version: None,
+            source: NodeSource::Missing,
+            satisfies: None,
+            optional,
+            repeated: false,
+            children: Vec::new(),
+        };
+    };
+
+    let mut node = TreeNode {
+        name: info.name.to_string(),
+        version: Some(info.version.to_string()),
+        source: info.source.clone(),
+        satisfies: (requested != info.name).then(|| requested.to_string()),
+        optional,
+        repeated: false,
+        children: Vec::new(),
+    };
+
+    if ancestors.iter().any(|a| a == info.name) || (options.dedup && !expanded.insert(node.name.clone())) {
+        node.repeated = true;
+        return node;
+    }
+    if options.max_depth.is_some_and(|max| depth >= max) {
+        return node;
+    }
+
+    ancestors.push(node.name.clone());
+    let optional_deps = if options.optional { info.optdepends } else { &[] };
+    for (child, child_optional) in info
+        .depends
+        .iter()
+        .map(|d| (d, false))
+        .chain(optional_deps.iter().map(|d| (d, true)))
+    {
+        node.children.push(build_forward(sources, child, child_optional, depth + 1, options, ancestors, expanded));
+    }
+    ancestors.pop();
+
+    node
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_reverse(
+    local: &PackageDb,
+    reverse: &HashMap<String, Vec<(String, bool)>>,
+    name: &str,
+    optional: bool,
+    depth: usize,
+    options: &TreeOptions,
+    ancestors: &mut Vec<String>,
+    expanded: &mut HashSet<String>,
+) -> TreeNode {
+    let mut node = TreeNode {
+        name: name.to_string(),
+        version: local.get(name).map(|p| p.version.clone()),
+        source: NodeSource::Local,
+        satisfies: None,
+        optional,
+        repeated: false,
+        children: Vec::new(),
+    };
+
+    if ancestors.iter().any(|a| a == name) || (options.dedup && !expanded.insert(name.to_string())) {
+        node.repeated = true;
+        return node;
+    }
+    if options.max_depth.is_some_and(|max| depth >= max) {
+        return node;
     }
-    
-    // Fallback
-    Ok(vec![(0, package.to_string()), (1, "Dependencies not available (pactree missing)".to_string())])
+
+    ancestors.push(name.to_string());
+    let mut dependents: Vec<&(String, bool)> = reverse
+        .get(name)
+        .into_iter()
+        .flatten()
+        .filter(|(_, is_optional)| options.optional || !is_optional)
+        .collect();
+    dependents.sort();
+
+    for (dependent, is_optional) in dependents {
+        node.children.push(build_reverse(local, reverse, dependent, *is_optional, depth + 1, options, ancestors, expanded));
+    }
+    ancestors.pop();
+
+    node
 }
@@ -0,0 +1,142 @@
+use super::data::{NodeSource, TreeNode};
+use crate::error::Result;
+use std::collections::HashSet;
+
+/// Machine-readable tree formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Dot,
+    Mermaid,
+}
+
+pub fn export(root: &TreeNode, format: ExportFormat, reverse: bool) -> Result<String> {
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_string_pretty(root)? + "\n"),
+        ExportFormat::Dot => Ok(to_dot(root, reverse)),
+        ExportFormat::Mermaid => Ok(to_mermaid(root, reverse)),
+    }
+}
+
+/// Unique (dependent, dependency, optional) edges of the tree.
+/// Edges always point from a package to what it depends on, also for reverse trees.
+fn edges(root: &TreeNode, reverse: bool) -> Vec<(&str, &str, bool)> {
+    let mut seen = HashSet::new();
+    let mut edges = Vec::new();
+    let mut stack = vec![root];
+
+    while let Some(node) = stack.pop() {
+        for child in &node.children {
+            let (from, to) = if reverse {
+                (child.name.as_str(), node.name.as_str())
+            } else {
+                (node.name.as_str(), child.name.as_str())
+            };
+            if seen.insert((from, to)) {
+                edges.push((from, to, child.optional));
+            }
+            stack.push(child);
+        }
+    }
+
+    edges.reverse();
+    edges
+}
+
+/// Every distinct package in the tree, in first-seen order
+fn nodes(root: &TreeNode) -> Vec<&TreeNode> {
+    let mut seen = HashSet::new();
+    let mut nodes = Vec::new();
+    let mut stack = vec![root];
+
+    while let Some(node) = stack.pop() {
+        if seen.insert(node.name.as_str()) {
+            nodes.push(node);
+        }
+        stack.extend(node.children.iter().rev());
+    }
+
+    nodes
+}
+
+fn node_text(node: &TreeNode) -> String {
+    match node.version {
+        Some(ref version) => format!("{} {}", node.name, version),
+        None => node.name.clone(),
+    }
+}
+
+fn to_dot(root: &TreeNode, reverse: bool) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut out = format!("digraph {} {{\n    rankdir=LR;\n    node [shape=box];\n", quote(&root.name));
+
+    for node in nodes(root) {
+        let style = match node.source {
+            NodeSource::Missing => ", style=dashed, color=red",
+            NodeSource::Aur => ", color=blue",
+            _ => "",
+        };
+        out.push_str(&format!("    {} [label={}{}];\n", quote(&node.name), quote(&node_text(node)), style));
+    }
+
+    for (from, to, optional) in edges(root, reverse) {
+        let style = if optional { " [style=dashed]" } else { "" };
+        out.push_str(&format!("    {} -> {}{};\n", quote(from), quote(to), style));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn to_mermaid(root: &TreeNode, reverse: bool) -> String {
+    let nodes = nodes(root);
+    // Package names may contain characters Mermaid ids can't, so number them
+    let id = |name: &str| nodes.iter().position(|n| n.name == name).unwrap_or(0);
+    let mut out = String::from("graph LR\n");
+
+    for (idx, node) in nodes.iter().enumerate() {
+        out.push_str(&format!("    n{}[\"{}\"]\n", idx, node_text(node).replace('"', "#quot;")));
+    }
+
+    for (from, to, optional) in edges(root, reverse) {
+        let arrow = if optional { "-.->" } else { "-->" };
+        out.push_str(&format!("    n{} {} n{}\n", id(from), arrow, id(to)));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(name: &str, optional: bool, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode {
+            name: name.to_string(),
+            version: Some("1-1".to_string()),
+            source: NodeSource::Local,
+            satisfies: None,
+            optional,
+            repeated: false,
+            children,
+        }
+    }
+
+    #[test]
+    fn test_edges_dedup_and_direction() {
+        let tree = node("app", false, vec![
+            node("lib", false, vec![node("glibc", false, vec![])]),
+            node("glibc", false, vec![]),
+            node("python", true, vec![]),
+        ]);
+
+        let dot = to_dot(&tree, false);
+        assert!(dot.contains("\"app\" -> \"lib\";"));
+        assert!(dot.contains("\"app\" -> \"python\" [style=dashed];"));
+        assert_eq!(dot.matches("-> \"glibc\"").count(), 2);
+
+        let mermaid = to_mermaid(&tree, true);
+        assert!(mermaid.contains("n1 --> n0"));
+        assert!(mermaid.contains("n3 -.-> n0"));
+    }
+}
@@ -1,5 +1,4 @@
 use crate::error::Result;
-use crate::cli::args::tree::data::get_flat_tree;
 use gtk4::prelude::*;
 use gtk4::{
     Application, ApplicationWindow, Orientation, TreeStore, TreeView, TreeViewColumn, 
@@ -13,7 +12,7 @@ use gtk4::glib;
 // Embed the logo
 const LOGO_BYTES: &[u8] = include_bytes!("../../../../res/logo/khazaur.svg");
 
-pub fn run(package: &str) -> Result<()> {
+pub fn run(package: &str, items: Vec<(usize, String)>) -> Result<()> {
     // Initialize GTK
     let app_id = format!("org.khazaur.tree.{}", package);
     let app = Application::builder()
@@ -22,14 +21,14 @@ pub fn run(package: &str) -> Result<()> {
 
     let package_clone = package.to_string();
     app.connect_activate(move |app| {
-        build_ui(app, &package_clone);
+        build_ui(app, &package_clone, items.clone());
     });
 
     app.run_with_args(&Vec::<String>::new());
     Ok(())
 }
 
-fn build_ui(app: &Application, package: &str) {
+fn build_ui(app: &Application, package: &str, data: Vec<(usize, String)>) {
     let window = ApplicationWindow::builder()
         .application(app)
         .title(format!("Khazaur - {}", package))
@@ -78,7 +77,6 @@ fn build_ui(app: &Application, package: &str) {
     // Tree Area
     let store = TreeStore::new(&[String::static_type()]);
 
-    let data = get_flat_tree(package).unwrap_or_default();
     
     // Populate tree store logic
     store.clear();
@@ -1,15 +1,44 @@
 mod data;
+mod export;
 mod tui;
 mod gui;
 
+pub use data::TreeOptions;
+pub use export::ExportFormat;
+
 use crate::error::Result;
+use crate::ui;
+
+pub async fn show_tree(
+    package: String,
+    use_gui: bool,
+    options: TreeOptions,
+    format: Option<ExportFormat>,
+    output: Option<std::path::PathBuf>,
+) -> Result<()> {
+    let spinner = ui::spinner(&format!("Resolving dependencies of {}...", package));
+    let tree = data::build_tree(&package, &options).await;
+    spinner.finish_and_clear();
+    let tree = tree?;
+
+    if let Some(format) = format {
+        let text = export::export(&tree, format, options.reverse)?;
+        match output {
+            Some(path) => {
+                std::fs::write(&path, text)?;
+                println!("{}", ui::success(&format!("Tree written to {}", path.display())));
+            }
+            None => print!("{}", text),
+        }
+        return Ok(());
+    }
 
-pub fn show_tree(package: String, use_gui: bool) -> Result<()> {
+    let items = tree.flatten();
     // If GUI requested
     if use_gui {
-        gui::run(&package)?;
+        gui::run(&package, items)?;
     } else {
-        tui::run(&package)?;
+        tui::run(&package, items)?;
     }
     Ok(())
 }
@@ -1,5 +1,4 @@
 use crate::error::Result;
-use crate::cli::args::tree::data::get_flat_tree;
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
     execute,
@@ -141,7 +140,7 @@ impl App {
     }
 }
 
-pub fn run(package: &str) -> Result<()> {
+pub fn run(package: &str, items: Vec<(usize, String)>) -> Result<()> {
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -149,7 +148,7 @@ pub fn run(package: &str) -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let app_result = run_app(&mut terminal, package);
+    let app_result = run_app(&mut terminal, package, items);
 
     // Restore terminal
     disable_raw_mode()?;
@@ -167,8 +166,7 @@ pub fn run(package: &str) -> Result<()> {
     Ok(())
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, package: &str) -> std::result::Result<(), Box<dyn Error>> {
-    let items = get_flat_tree(package).map_err(|e| Box::new(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())))?;
+fn run_app<B: Backend>(terminal: &mut Terminal<B>, package: &str, items: Vec<(usize, String)>) -> std::result::Result<(), Box<dyn Error>> {
     let mut app = App::new(items);
 
     loop {
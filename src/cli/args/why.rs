@@ -0,0 +1,223 @@
+use crate::error::Result;
+use crate::pacman::db::PackageDb;
+use crate::ui;
+use colored::Colorize;
+use std::collections::{HashMap, VecDeque};
+
+/// Upper bound on printed chains, dependency graphs can fan out quickly
+const MAX_CHAINS: usize = 50;
+
+/// One step from a package to something that depends on it
+type Link = (String, bool);
+
+/// Explain why a package is installed
+pub fn why(package: &str) -> Result<()> {
+    println!("{}", ui::section_header(&format!("Why is {} installed?", package)));
+
+    let db = PackageDb::load_local()?;
+    if db.get(package).is_some() {
+        return explain_pacman(&db, package);
+    }
+
+    if let Some(pkg) = db.resolve(package) {
+        println!("{}", ui::info(&format!("{} is provided by {}", package, pkg.name.bold())));
+        return explain_pacman(&db, &pkg.name);
+    }
+
+    if crate::flatpak::install::is_flatpak_ref_installed(package)? {
+        return explain_flatpak(package);
+    }
+
+    if crate::snap::is_snap_installed(package)? {
+        return explain_snap(package);
+    }
+
+    println!("{}", ui::warning(&format!("{} is not installed", package)));
+    Ok(())
+}
+
+fn explain_pacman(db: &PackageDb, package: &str) -> Result<()> {
+    let pkg = db.get(package).expect("package checked by caller");
+    let reverse = db.reverse_dependencies();
+
+    if pkg.is_explicit() {
+        println!("{}", ui::success(&format!("{} {} was explicitly installed", pkg.name, pkg.version)));
+    } else {
+        println!("{}", ui::info(&format!("{} {} was installed as a dependency", pkg.name, pkg.version)));
+    }
+
+    let chains = shortest_chains(package, |name| reverse.get(name).cloned().unwrap_or_default(), |name| {
+        db.get(name).is_some_and(|p| p.is_explicit())
+    });
+
+    if chains.is_empty() {
+        if !pkg.is_explicit() {
+            println!("{}", ui::warning("Nothing explicitly installed needs it (orphan, see 'khazaur orphans')"));
+        }
+        return Ok(());
+    }
+
+    println!("\n{} {}", "::".bright_blue().bold(), "Required by explicitly installed packages:".bold());
+    let truncated = chains.len() > MAX_CHAINS;
+    let mut has_optional = false;
+
+    for chain in chains.iter().take(MAX_CHAINS) {
+        has_optional |= chain.iter().any(|(_, optional)| *optional);
+        println!("  {}", format_chain(package, chain));
+    }
+
+    if truncated {
+        println!("  {}", "... more chains not shown".dimmed());
+    }
+    if has_optional {
+        println!("\n  {}", "~> marks an optional dependency".dimmed());
+    }
+
+    Ok(())
+}
+
+fn explain_flatpak(ref_id: &str) -> Result<()> {
+    let users: Vec<String> = crate::flatpak::install::get_app_runtimes()?
+        .into_iter()
+        .filter(|(_, runtime)| runtime == ref_id)
+        .map(|(app, _)| app)
+        .collect();
+
+    let reason = crate::reasons::ReasonDb::load()?.get(crate::cli::SourceKind::Flatpak, ref_id);
+
+    if users.is_empty() {
+        match reason {
+            Some(reason) => println!("{}", ui::info(&format!("Flatpak {} was {}", ref_id, reason.label()))),
+            None => println!("{}", ui::info(&format!("Flatpak {} is not used as a runtime by any installed app", ref_id))),
+        }
+        return Ok(());
+    }
+
+    println!("{}", ui::info(&format!("Flatpak runtime {} is used by {} app(s):", ref_id, users.len())));
+    for app in users {
+        println!("  {} -> {}", app.bold(), ref_id);
+    }
+    Ok(())
+}
+
+fn explain_snap(name: &str) -> Result<()> {
+    let users: Vec<String> = crate::snap::get_installed_snaps("")?
+        .into_iter()
+        .filter(|snap| crate::snap::get_snap_dependencies(snap).iter().any(|d| d == name))
+        .collect();
+
+    if users.is_empty() {
+        let reason = crate::reasons::ReasonDb::load()?
+            .get(crate::cli::SourceKind::Snap, name)
+            .unwrap_or(crate::reasons::InstallReason::Explicit);
+        println!("{}", ui::info(&format!("Snap {} was {}", name, reason.label())));
+        return Ok(());
+    }
+
+    println!("{}", ui::info(&format!("Snap {} is used by {} snap(s):", name, users.len())));
+    for snap in users {
+        println!("  {} -> {}", snap.bold(), name);
+    }
+    Ok(())
+}
+
+/// Breadth-first walk over reverse dependencies from `start`, stopping at roots.
+/// Returns every shortest chain to each reachable root; each chain lists the links
+/// from `start` outwards, so the root is the last entry.
+fn shortest_chains(
+    start: &str,
+    dependents: impl Fn(&str) -> Vec<Link>,
+    is_root: impl Fn(&str) -> bool,
+) -> Vec<Vec<Link>> {
+    let mut distance: HashMap<String, usize> = HashMap::from([(start.to_string(), 0)]);
+    // node -> links leading back one step towards start
+    let mut parents: HashMap<String, Vec<(String, bool)>> = HashMap::new();
+    let mut roots = Vec::new();
+    let mut queue = VecDeque::from([start.to_string()]);
+
+    while let Some(node) = queue.pop_front() {
+        let dist = distance[&node];
+        if node != start && is_root(&node) {
+            roots.push(node);
+            continue;
+        }
+
+        for (dependent, optional) in dependents(&node) {
+            match distance.get(&dependent) {
+                None => {
+                    distance.insert(dependent.clone(), dist + 1);
+                    parents.entry(dependent.clone()).or_default().push((node.clone(), optional));
+                    queue.push_back(dependent);
+                }
+                Some(&d) if d == dist + 1 => {
+                    parents.entry(dependent).or_default().push((node.clone(), optional));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut chains = Vec::new();
+    'roots: for root in roots {
+        let mut partial = vec![(root.clone(), Vec::new())];
+        while let Some((node, tail)) = partial.pop() {
+            if node == start {
+                chains.push(tail);
+                if chains.len() > MAX_CHAINS {
+                    break 'roots;
+                }
+                continue;
+            }
+            for (parent, optional) in parents.get(&node).into_iter().flatten() {
+                let mut tail = tail.clone();
+                tail.insert(0, (node.clone(), *optional));
+                partial.push((parent.clone(), tail));
+            }
+        }
+    }
+
+    // Chains made only of hard dependencies first, then shortest
+    chains.sort_by_key(|chain| (chain.iter().any(|(_, optional)| *optional), chain.len()));
+    chains
+}
+
+/// Render a chain root-first: `root -> ... -> package`
+fn format_chain(package: &str, chain: &[Link]) -> String {
+    let mut out = String::new();
+    for (idx, (name, optional)) in chain.iter().enumerate().rev() {
+        if idx == chain.len() - 1 {
+            out.push_str(&name.bold().to_string());
+        } else {
+            out.push_str(name);
+        }
+        out.push_str(if *optional { " ~> " } else { " -> " });
+    }
+    out.push_str(package);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortest_chains_stop_at_roots() {
+        // app -> lib -> base, tool -> base, app is explicit, tool is explicit, lib is a dep
+        let graph: HashMap<&str, Vec<Link>> = HashMap::from([
+            ("base", vec![("lib".to_string(), false), ("tool".to_string(), true)]),
+            ("lib", vec![("app".to_string(), false)]),
+            ("app", vec![("meta".to_string(), false)]),
+        ]);
+        let roots = ["app", "tool", "meta"];
+
+        let chains = shortest_chains(
+            "base",
+            |n| graph.get(n).cloned().unwrap_or_default(),
+            |n| roots.contains(&n),
+        );
+
+        assert_eq!(chains.len(), 2);
+        assert_eq!(chains[0], vec![("lib".to_string(), false), ("app".to_string(), false)]);
+        assert_eq!(chains[1], vec![("tool".to_string(), true)]);
+    }
+}
@@ -0,0 +1,152 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::appimage::db::AppImageDb;
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::ui;
+use colored::*;
+
+/// AppImages from the catalog, kept in the AppImage directory with desktop entries
+pub struct AppImageBackend;
+
+impl PackageBackend for AppImageBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::AppImage
+    }
+
+    fn name(&self) -> &str {
+        "AppImage"
+    }
+
+    fn title(&self) -> &str {
+        "AppImages"
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            Ok(crate::appimage::search(query, config)
+                .await?
+                .into_iter()
+                .map(PackageSource::AppImage)
+                .collect())
+        })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            // The catalog search also matches descriptions, only names count here
+            let query = name.to_lowercase();
+            Ok(crate::appimage::search(name, config)
+                .await?
+                .into_iter()
+                .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+                .map(PackageSource::AppImage)
+                .collect())
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        config: &'a mut Config,
+        _noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} AppImages...", packages.len()).bold());
+            for pkg in packages {
+                let PackageSource::AppImage(pkg) = pkg else {
+                    continue;
+                };
+                let tx = Transaction::begin("install", SourceKind::AppImage, std::slice::from_ref(&pkg.name));
+                match crate::appimage::install_package(pkg, config).await {
+                    Ok(app) => {
+                        println!("{}", ui::success(&format!("{} {} installed to {}", app.name, app.version, app.path.display())));
+                        let _ = crate::reasons::record(SourceKind::AppImage, &app.name, crate::reasons::InstallReason::Explicit);
+                        let _ = tx.finish(true);
+                    }
+                    Err(e) => {
+                        eprintln!("{}", ui::error(&format!("Failed to install {}: {}", pkg.name, e)));
+                        let _ = tx.fail(&e);
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        crate::appimage::uninstall(name)
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        Ok(AppImageDb::load()?
+            .apps()
+            .map(|app| InstalledPackage {
+                name: app.name.clone(),
+                version: app.version.clone(),
+                origin: Some(app.source_url.clone()),
+                dependency: false,
+            })
+            .collect())
+    }
+
+    fn installed_size(&self, name: &str) -> Option<u64> {
+        let db = AppImageDb::load().ok()?;
+        let app = db.get(name)?;
+        std::fs::metadata(&app.path).ok().map(|meta| meta.len())
+    }
+
+    fn check_updates<'a>(&'a self, config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(crate::appimage::get_updates(config)
+                .await?
+                .into_iter()
+                .map(|(current_version, pkg)| Update {
+                    name: pkg.name.clone(),
+                    current_version,
+                    new_version: pkg.version.clone(),
+                    display_name: None,
+                    package: Some(PackageSource::AppImage(pkg)),
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), "Upgrading AppImages...".bold());
+
+            let mut upgraded_count = 0;
+            for update in updates {
+                let Some(PackageSource::AppImage(pkg)) = &update.package else {
+                    continue;
+                };
+                let tx = Transaction::begin("update", SourceKind::AppImage, std::slice::from_ref(&update.name));
+                match crate::appimage::install_package(pkg, config).await {
+                    Ok(_) => {
+                        println!("{}", ui::success(&format!("{} upgraded successfully", update.name)));
+                        let _ = tx.finish(true);
+                        upgraded_count += 1;
+                    }
+                    Err(e) => {
+                        let _ = tx.fail(&e);
+                        eprintln!("{}", ui::error(&format!("Failed to upgrade {}: {}", update.name, e)));
+                    }
+                }
+            }
+
+            if upgraded_count > 0 {
+                println!("\n{} {}",
+                    "::".bright_green().bold(),
+                    format!("Successfully upgraded {} AppImage(s)", upgraded_count).bold()
+                );
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,175 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::aur::AurClient;
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::pacman;
+use crate::ui;
+use colored::*;
+use tracing::debug;
+
+/// Arch User Repository: packages are built with makepkg and installed through pacman
+pub struct AurBackend {
+    client: AurClient,
+}
+
+impl AurBackend {
+    pub fn new() -> Result<Self> {
+        Ok(Self { client: AurClient::new()? })
+    }
+}
+
+impl PackageBackend for AurBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Aur
+    }
+
+    fn name(&self) -> &str {
+        "AUR"
+    }
+
+    fn title(&self) -> &str {
+        "AUR Packages"
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move { Ok(self.client.search(query).await?.into_iter().map(PackageSource::Aur).collect()) })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            // First try exact match
+            if let Ok(pkg) = self.client.info(name).await {
+                debug!("{} found in AUR", name);
+                return Ok(vec![PackageSource::Aur(pkg)]);
+            }
+
+            // If exact match fails, try fuzzy search, keeping packages that contain the search term
+            debug!("Exact match not found in AUR, trying search...");
+            let query = name.to_lowercase();
+            Ok(self
+                .client
+                .search(name)
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+                .map(PackageSource::Aur)
+                .collect())
+        })
+    }
+
+    fn info<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move { Ok(self.client.info(name).await.ok().map(|pkg| ui::format_aur_info(&pkg))) })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let names: Vec<String> = packages.iter().map(|pkg| pkg.name().to_string()).collect();
+            install_names(&names, config, noconfirm).await
+        })
+    }
+
+    fn install_by_name<'a>(
+        &'a self,
+        names: &'a [String],
+        config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(install_names(names, config, noconfirm))
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        pacman::remove_packages(&[name.to_string()], &["--noconfirm".to_string()])
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        // Converted Debian packages are foreign to pacman too, keep them apart from AUR ones
+        let tracked = crate::debian::tracking::TrackingDb::load()?;
+        let packages = pacman::get_installed_aur_packages()?
+            .into_iter()
+            .filter(|(name, _)| !tracked.contains(name))
+            .collect();
+        super::repo::pacman_installed(packages)
+    }
+
+    fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            let installed = self.list_installed()?;
+            if installed.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let names: Vec<String> = installed.iter().map(|pkg| pkg.name.clone()).collect();
+            let aur_packages = self.client.info_batch(&names).await?;
+
+            // Compare versions and find packages that need updates
+            let mut updates = Vec::new();
+            for pkg in installed {
+                let Some(aur_pkg) = aur_packages.iter().find(|p| p.name == pkg.name) else {
+                    continue;
+                };
+                if crate::cli::install::needs_update(&pkg.version, &aur_pkg.version) {
+                    updates.push(Update {
+                        name: pkg.name,
+                        current_version: pkg.version,
+                        new_version: aur_pkg.version.clone(),
+                        display_name: None,
+                        package: Some(PackageSource::Aur(aur_pkg.clone())),
+                    });
+                }
+            }
+            Ok(updates)
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], config: &'a mut Config, noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let packages: Vec<_> = updates
+                .iter()
+                .filter_map(|update| match &update.package {
+                    Some(PackageSource::Aur(pkg)) => Some(pkg.clone()),
+                    _ => None,
+                })
+                .collect();
+            crate::cli::install::upgrade_aur_packages(&packages, config, noconfirm).await
+        })
+    }
+}
+
+/// Build and install AUR packages that aren't installed yet
+async fn install_names(names: &[String], config: &mut Config, noconfirm: bool) -> Result<()> {
+    let mut to_install = Vec::new();
+    for name in names {
+        if pacman::is_installed(name)? {
+            println!("{} {} {}", "::".bright_blue().bold(), name.bold(), "is already installed".dimmed());
+        } else {
+            to_install.push(name.clone());
+        }
+    }
+    if to_install.is_empty() {
+        return Ok(());
+    }
+
+    let tx = Transaction::begin("install", SourceKind::Aur, &to_install);
+    match crate::cli::install::install_aur_packages(&to_install, config, noconfirm).await {
+        Ok(()) => {
+            let _ = tx.finish(true);
+            Ok(())
+        }
+        Err(e) => {
+            let _ = tx.fail(&e);
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::debian::tracking::{TrackedPackage, TrackingDb};
+use crate::error::Result;
+use crate::ui;
+use std::collections::HashMap;
+
+/// Debian packages, converted to pacman packages and tracked by khazaur
+pub struct DebianBackend;
+
+impl PackageBackend for DebianBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Debian
+    }
+
+    fn name(&self) -> &str {
+        "Debian"
+    }
+
+    fn title(&self) -> &str {
+        "Debian Packages"
+    }
+
+    fn is_available(&self, config: &Config) -> bool {
+        crate::debian::converter_available(config)
+    }
+
+    fn ensure_available<'a>(&'a self, config: &'a mut Config) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !self.is_available(config) {
+                crate::cli::optional_deps::check_and_prompt_debtap(config).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            Ok(crate::debian::search_debian(query, config)
+                .await?
+                .into_iter()
+                .map(PackageSource::Debian)
+                .collect())
+        })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            let packages = crate::debian::search_debian_names(name, config).await?;
+            Ok(config
+                .debian_suite_policy
+                .select(packages)
+                .into_iter()
+                .map(PackageSource::Debian)
+                .collect())
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            self.ensure_available(config).await?;
+            if !self.is_available(config) {
+                eprintln!("{}", ui::warning("Skipping Debian packages: debtap not available"));
+                return Ok(());
+            }
+
+            // Download and convert them with their dependencies
+            let packages: Vec<_> = packages
+                .iter()
+                .filter_map(|pkg| match pkg {
+                    PackageSource::Debian(pkg) => Some(pkg.clone()),
+                    _ => None,
+                })
+                .collect();
+            crate::cli::install::install_debian_packages(&packages, config, noconfirm).await
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        crate::pacman::remove_packages(&[name.to_string()], &["--noconfirm".to_string()])?;
+        crate::debian::tracking::forget(&[name.to_string()])
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        let tracked = TrackingDb::load()?;
+        let installed: HashMap<String, String> = crate::pacman::get_installed_packages()?.into_iter().collect();
+        let dependencies = crate::pacman::get_dependency_packages()?;
+
+        Ok(tracked
+            .packages()
+            .filter_map(|entry| {
+                let version = installed.get(&entry.arch_name)?;
+                Some(InstalledPackage {
+                    name: entry.arch_name.clone(),
+                    version: version.clone(),
+                    origin: origin(entry),
+                    dependency: dependencies.contains(&entry.arch_name),
+                })
+            })
+            .collect())
+    }
+
+    /// Packages match by the name of the Debian package they were converted from too
+    fn check_updates<'a>(&'a self, config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(crate::debian::check_debian_updates(config)
+                .await?
+                .into_iter()
+                .map(|(name, current_version, new_version, pkg)| Update {
+                    name,
+                    current_version,
+                    new_version,
+                    display_name: None,
+                    package: Some(PackageSource::Debian(pkg)),
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let packages: Vec<_> = updates
+                .iter()
+                .filter_map(|update| match &update.package {
+                    Some(PackageSource::Debian(pkg)) => Some((update.name.clone(), pkg.clone())),
+                    _ => None,
+                })
+                .collect();
+            crate::cli::install::upgrade_debian_packages(&packages, config).await
+        })
+    }
+}
+
+/// Suite, Debian name and version a package was converted from
+fn origin(entry: &TrackedPackage) -> Option<String> {
+    let mut origin = Vec::new();
+    if !entry.suite.is_empty() {
+        origin.push(entry.suite.clone());
+    }
+    if entry.debian_name != entry.arch_name {
+        origin.push(format!("from {}", entry.debian_name));
+    }
+    if !entry.version.is_empty() {
+        origin.push(format!("Debian {}", entry.version));
+    }
+    (!origin.is_empty()).then(|| origin.join(", "))
+}
@@ -0,0 +1,217 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::flatpak;
+use crate::history::Transaction;
+use crate::ui;
+use colored::*;
+use std::collections::HashSet;
+
+/// Flatpak applications from the configured remotes
+pub struct FlatpakBackend {
+    /// Let searches run without the usual time limit
+    pub no_timeout: bool,
+}
+
+impl PackageBackend for FlatpakBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Flatpak
+    }
+
+    fn name(&self) -> &str {
+        "Flatpak"
+    }
+
+    fn title(&self) -> &str {
+        "Flatpak Applications"
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        flatpak::is_available()
+    }
+
+    fn ensure_available<'a>(&'a self, config: &'a mut Config) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !flatpak::is_available() {
+                crate::cli::optional_deps::check_and_prompt_flatpak(config).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            Ok(flatpak::search_flatpak(query, false)?
+                .into_iter()
+                .map(PackageSource::Flatpak)
+                .collect())
+        })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            // Match if the query appears in the name or is the app ID
+            let query = name.to_lowercase();
+            Ok(flatpak::search_flatpak(name, self.no_timeout)?
+                .into_iter()
+                .filter(|pkg| pkg.name.to_lowercase().contains(&query) || pkg.app_id.to_lowercase() == query)
+                .map(PackageSource::Flatpak)
+                .collect())
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        _config: &'a mut Config,
+        _noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} Flatpak packages...", packages.len()).bold());
+            for pkg in packages {
+                let app_id = pkg.name().to_string();
+                let tx = Transaction::begin("install", SourceKind::Flatpak, std::slice::from_ref(&app_id));
+                let runtimes_before = flatpak::install::get_installed_runtimes().unwrap_or_default();
+                if let Err(e) = flatpak::install_flatpak(&app_id).await {
+                    eprintln!("{}", ui::error(&format!("Failed to install {}: {}", app_id, e)));
+                    let _ = tx.fail(&e);
+                } else {
+                    let _ = crate::reasons::record(SourceKind::Flatpak, &app_id, crate::reasons::InstallReason::Explicit);
+                    record_pulled_runtime(&app_id, &runtimes_before);
+                    let _ = tx.finish(true);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        flatpak::uninstall_flatpak(name)?;
+        // Runtimes are removed by full ref but their install reason is kept by id
+        if name.contains('/') {
+            let _ = crate::reasons::forget(SourceKind::Flatpak, &[runtime_id(name).to_string()]);
+        }
+        Ok(())
+    }
+
+    /// Runtimes the apps use that no other app does
+    fn removal_cascade(&self, names: &[String]) -> Result<Vec<String>> {
+        unused_runtimes(names)
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        Ok(flatpak::get_installed_flatpaks("")?
+            .into_iter()
+            .map(|app_id| InstalledPackage {
+                name: app_id,
+                version: String::new(),
+                origin: None,
+                dependency: false,
+            })
+            .collect())
+    }
+
+    /// Installed apps and runtimes whose id contains the query, so runtimes can be marked and held
+    fn find_installed(&self, query: &str) -> Result<Vec<InstalledPackage>> {
+        let query = query.to_lowercase();
+        let runtimes = flatpak::install::get_installed_runtimes()?.into_iter().map(|id| InstalledPackage {
+            name: id,
+            version: String::new(),
+            origin: None,
+            dependency: true,
+        });
+        Ok(self
+            .list_installed()?
+            .into_iter()
+            .chain(runtimes)
+            .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    fn installed_size(&self, name: &str) -> Option<u64> {
+        flatpak::install::get_installed_size(name)
+    }
+
+    fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(flatpak::get_updates()?
+                .into_iter()
+                .map(|update| Update {
+                    display_name: Some(format!("{} ({})", update.name, update.app_id)),
+                    name: update.app_id,
+                    current_version: update.current_version,
+                    new_version: update.new_version,
+                    package: None,
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], _config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), "Upgrading Flatpak packages...".bold());
+            let app_ids: Vec<String> = updates.iter().map(|update| update.name.clone()).collect();
+            let tx = Transaction::begin("update", SourceKind::Flatpak, &app_ids);
+            match flatpak::update_all() {
+                Ok(_) => {
+                    println!("{}", ui::success(&format!("Successfully upgraded {} Flatpak package(s)", updates.len())));
+                    let _ = tx.finish(true);
+                }
+                Err(e) => {
+                    let _ = tx.fail(&e);
+                    eprintln!("{}", ui::error(&format!("Failed to upgrade Flatpak packages: {}", e)));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Record the runtime an application brought in as a dependency, so the orphan
+/// check can remove it once no application uses it. Runtimes that were already
+/// installed keep the reason they have.
+fn record_pulled_runtime(app_id: &str, runtimes_before: &[String]) {
+    let Ok(apps) = flatpak::install::get_app_runtimes() else {
+        return;
+    };
+    for (app, runtime) in apps {
+        if app == app_id && !runtimes_before.contains(&runtime) {
+            let _ = crate::reasons::record(SourceKind::Flatpak, &runtime, crate::reasons::InstallReason::Dependency);
+        }
+    }
+}
+
+/// Runtimes (full refs) used by the apps being removed that no other installed app uses.
+/// Runtimes recorded as explicitly installed are kept.
+fn unused_runtimes(apps: &[String]) -> Result<Vec<String>> {
+    if apps.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let app_runtimes = flatpak::install::get_app_runtime_refs()?;
+    let still_used: HashSet<&str> = app_runtimes
+        .iter()
+        .filter(|(app, _)| !apps.contains(app))
+        .map(|(_, runtime)| runtime.as_str())
+        .collect();
+    let reasons = crate::reasons::ReasonDb::load()?;
+
+    let mut unused: Vec<String> = Vec::new();
+    for (app, runtime) in &app_runtimes {
+        if !apps.contains(app) || still_used.contains(runtime.as_str()) || unused.contains(runtime) {
+            continue;
+        }
+        if reasons.get(SourceKind::Flatpak, runtime_id(runtime)) == Some(crate::reasons::InstallReason::Explicit) {
+            continue;
+        }
+        unused.push(runtime.clone());
+    }
+
+    Ok(unused)
+}
+
+/// org.gnome.Platform/x86_64/46 -> org.gnome.Platform
+fn runtime_id(runtime_ref: &str) -> &str {
+    runtime_ref.split('/').next().unwrap_or(runtime_ref)
+}
@@ -0,0 +1,424 @@
+mod appimage;
+mod aur;
+mod debian;
+mod flatpak;
+mod plugin;
+mod repo;
+mod snap;
+
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::ui;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+
+/// Future returned by backend methods, boxed so backends can be used as trait objects
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// A package installed from one source
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub name: String,
+    /// Empty when the source doesn't report versions
+    pub version: String,
+    /// Where the package came from, shown next to it in listings
+    pub origin: Option<String>,
+    /// Installed to satisfy another package rather than on request
+    pub dependency: bool,
+}
+
+/// A newer version of an installed package
+#[derive(Debug, Clone)]
+pub struct Update {
+    /// Name holds and history refer to the package by
+    pub name: String,
+    pub current_version: String,
+    pub new_version: String,
+    /// Shown instead of the name when set
+    pub display_name: Option<String>,
+    /// The newer package, for sources that need more than the name to upgrade
+    pub package: Option<PackageSource>,
+}
+
+/// A package source: official repositories, AUR, Flatpak, Snap, Debian, AppImages or
+/// an external plugin. Commands go through the registered backends instead of handling each source.
+pub trait PackageBackend {
+    fn kind(&self) -> SourceKind;
+
+    /// Name used in messages and summaries ("AUR", "Flatpak")
+    fn name(&self) -> &str;
+
+    /// Heading for lists of this source's packages ("AUR Packages")
+    fn title(&self) -> &str;
+
+    /// Prefix picking this source in `source/package`, also its label in listings
+    fn prefix(&self) -> &str {
+        self.kind().label()
+    }
+
+    /// Name a package is recorded under in holds, install reasons and the history.
+    /// Plugins share one source kind, so they put their id in front (`cargo/ripgrep`)
+    fn record_name(&self, name: &str) -> String {
+        name.to_string()
+    }
+
+    /// Whether the tools the source needs are installed
+    fn is_available(&self, config: &Config) -> bool;
+
+    /// Offer to install the tools the source needs when they are missing
+    fn ensure_available<'a>(&'a self, _config: &'a mut Config) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Packages matching a search query, by name or description
+    fn search<'a>(&'a self, query: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>>;
+
+    /// Packages an install request for `name` could mean
+    fn find<'a>(&'a self, name: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>>;
+
+    /// Detailed information on a package, None when the source doesn't have it
+    fn info<'a>(&'a self, name: &'a str, config: &'a Config) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            let found = self.find(name, config).await?;
+            Ok(found.iter().find(|pkg| pkg.name() == name).map(ui::format_source_info))
+        })
+    }
+
+    /// Install packages this backend found, recording them in the history
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>>;
+
+    /// Install packages known only by name, as a backup lists them
+    fn install_by_name<'a>(
+        &'a self,
+        names: &'a [String],
+        config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let mut packages = Vec::new();
+            for name in names {
+                let found = self.find(name, config).await?;
+                match found.into_iter().find(|pkg| pkg.name() == name) {
+                    Some(pkg) => packages.push(pkg),
+                    None => eprintln!("{}", ui::warning(&format!("{} not found in {}", name, self.name()))),
+                }
+            }
+            if packages.is_empty() {
+                return Ok(());
+            }
+            self.install(&packages, config, noconfirm).await
+        })
+    }
+
+    /// Remove an installed package
+    fn remove(&self, name: &str) -> Result<()>;
+
+    /// Everything installed from this source
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>>;
+
+    /// Installed packages whose name contains the query
+    fn find_installed(&self, query: &str) -> Result<Vec<InstalledPackage>> {
+        let query = query.to_lowercase();
+        Ok(self
+            .list_installed()?
+            .into_iter()
+            .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+            .collect())
+    }
+
+    /// Installed packages that go with the given ones because nothing else needs them,
+    /// such as the runtimes of Flatpak apps. Pacman works this out itself.
+    fn removal_cascade(&self, _names: &[String]) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Disk space an installed package takes, when the source can tell
+    fn installed_size(&self, _name: &str) -> Option<u64> {
+        None
+    }
+
+    /// Installed packages with a newer version available
+    fn check_updates<'a>(&'a self, config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>>;
+
+    /// Install the given updates, recording them in the history
+    fn upgrade<'a>(&'a self, updates: &'a [Update], config: &'a mut Config, noconfirm: bool) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Every backend, in the order sources are listed and upgraded: the built-in
+/// ones, then discovered plugins. `no_timeout` lets Flatpak searches run as long as they need.
+pub fn all(no_timeout: bool) -> Result<Vec<Box<dyn PackageBackend>>> {
+    let mut backends: Vec<Box<dyn PackageBackend>> = vec![
+        Box::new(repo::RepoBackend),
+        Box::new(aur::AurBackend::new()?),
+        Box::new(debian::DebianBackend),
+        Box::new(flatpak::FlatpakBackend { no_timeout }),
+        Box::new(snap::SnapBackend),
+        Box::new(appimage::AppImageBackend),
+    ];
+    for plugin in crate::plugin::discover() {
+        backends.push(Box::new(plugin::PluginBackend { plugin }));
+    }
+    Ok(backends)
+}
+
+/// Find the backend an installed package belongs to, as its index in `backends`,
+/// and the package name. Accepts the `source/package` prefix syntax to skip detection.
+pub fn locate_installed(backends: &[Box<dyn PackageBackend>], query: &str) -> Result<Option<(usize, String)>> {
+    let named = |prefix: &str| -> Vec<usize> {
+        backends
+            .iter()
+            .enumerate()
+            .filter(|(_, backend)| backend.prefix() == prefix || SourceKind::from_prefix(prefix) == Some(backend.kind()))
+            .map(|(index, _)| index)
+            .collect()
+    };
+
+    // Flatpak refs contain slashes too, so an unknown prefix is part of the name
+    let (candidates, name) = match query.split_once('/') {
+        Some((prefix, name)) if !named(prefix).is_empty() => (named(prefix), name),
+        _ => ((0..backends.len()).collect(), query),
+    };
+
+    for index in candidates {
+        let backend = &backends[index];
+        match backend.find_installed(name) {
+            Ok(found) if found.iter().any(|pkg| pkg.name == name) => return Ok(Some((index, name.to_string()))),
+            Ok(_) => {}
+            Err(e) => eprintln!("{}", ui::warning(&format!("Failed to list {}: {}", backend.title(), e))),
+        }
+    }
+
+    Ok(None)
+}
+
+/// Install each backend's share of the selected packages, in backend order. Plugins
+/// share a kind and each picks out its own packages. A failing source is reported
+/// and doesn't stop the others; returns the names of those that failed.
+pub async fn install_selected(
+    backends: &[Box<dyn PackageBackend>],
+    selected: &BTreeMap<SourceKind, Vec<PackageSource>>,
+    config: &mut Config,
+    noconfirm: bool,
+) -> Vec<String> {
+    let mut failed = Vec::new();
+    for backend in backends {
+        if let Some(packages) = selected.get(&backend.kind())
+            && let Err(e) = backend.install(packages, config, noconfirm).await
+        {
+            eprintln!("{}", ui::error(&format!("Failed to install {} packages: {}", backend.name(), e)));
+            failed.push(backend.name().to_string());
+        }
+    }
+    failed
+}
+
+/// Install the updates found per backend. Like `install_selected`, a failing
+/// source doesn't keep the others from being upgraded.
+pub async fn upgrade_all(updates: &[(&dyn PackageBackend, Vec<Update>)], config: &mut Config, noconfirm: bool) -> Vec<String> {
+    let mut failed = Vec::new();
+    for (backend, found) in updates {
+        if !found.is_empty()
+            && let Err(e) = backend.upgrade(found, config, noconfirm).await
+        {
+            eprintln!("{}", ui::error(&format!("Failed to upgrade {} packages: {}", backend.name(), e)));
+            failed.push(backend.name().to_string());
+        }
+    }
+    failed
+}
+
+/// What goes with the selected packages (pairs of backend index and name), per
+/// backend index. Pacman backends are left empty: pacman removes its cascade itself.
+pub fn removal_cascades(backends: &[Box<dyn PackageBackend>], selected: &[(usize, String)]) -> Result<Vec<Vec<String>>> {
+    let mut cascades = Vec::new();
+    for (index, backend) in backends.iter().enumerate() {
+        let names: Vec<String> = selected.iter().filter(|(i, _)| *i == index).map(|(_, name)| name.clone()).collect();
+        cascades.push(if names.is_empty() || backend.kind().is_pacman() {
+            Vec::new()
+        } else {
+            backend.removal_cascade(&names)?
+        });
+    }
+    Ok(cascades)
+}
+
+/// The backends for the given sources, or every backend when none are given.
+/// Plugins can't be picked with source flags, so they only take part in the latter.
+pub fn selected(sources: &[SourceKind], no_timeout: bool) -> Result<Vec<Box<dyn PackageBackend>>> {
+    let mut backends = all(no_timeout)?;
+    if !sources.is_empty() {
+        backends.retain(|backend| sources.contains(&backend.kind()));
+    }
+    Ok(backends)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::KhazaurError;
+    use std::cell::Cell;
+
+    /// A source with a fixed set of installed packages that records calls
+    struct Stub {
+        kind: SourceKind,
+        prefix: &'static str,
+        installed: &'static [&'static str],
+        cascade: &'static [&'static str],
+        fails: bool,
+        calls: Cell<usize>,
+    }
+
+    impl Stub {
+        fn new(kind: SourceKind, prefix: &'static str, installed: &'static [&'static str]) -> Self {
+            Self { kind, prefix, installed, cascade: &[], fails: false, calls: Cell::new(0) }
+        }
+
+        fn run(&self) -> Result<()> {
+            self.calls.set(self.calls.get() + 1);
+            if self.fails {
+                return Err(KhazaurError::Plugin(format!("{} is broken", self.prefix)));
+            }
+            Ok(())
+        }
+    }
+
+    impl PackageBackend for Stub {
+        fn kind(&self) -> SourceKind {
+            self.kind
+        }
+
+        fn name(&self) -> &str {
+            self.prefix
+        }
+
+        fn title(&self) -> &str {
+            self.prefix
+        }
+
+        fn prefix(&self) -> &str {
+            self.prefix
+        }
+
+        fn is_available(&self, _config: &Config) -> bool {
+            true
+        }
+
+        fn search<'a>(&'a self, _query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn find<'a>(&'a self, _name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn install<'a>(&'a self, _packages: &'a [PackageSource], _config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move { self.run() })
+        }
+
+        fn remove(&self, _name: &str) -> Result<()> {
+            self.run()
+        }
+
+        fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+            if self.fails {
+                return Err(KhazaurError::Plugin(format!("{} is broken", self.prefix)));
+            }
+            Ok(self
+                .installed
+                .iter()
+                .map(|name| InstalledPackage { name: name.to_string(), version: String::new(), origin: None, dependency: false })
+                .collect())
+        }
+
+        fn removal_cascade(&self, _names: &[String]) -> Result<Vec<String>> {
+            Ok(self.cascade.iter().map(|name| name.to_string()).collect())
+        }
+
+        fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn upgrade<'a>(&'a self, _updates: &'a [Update], _config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move { self.run() })
+        }
+    }
+
+    fn update(name: &str) -> Update {
+        Update {
+            name: name.to_string(),
+            current_version: "1".to_string(),
+            new_version: "2".to_string(),
+            display_name: None,
+            package: None,
+        }
+    }
+
+    #[test]
+    fn test_locate_installed_picks_the_backend() {
+        let backends: Vec<Box<dyn PackageBackend>> = vec![
+            Box::new(Stub { fails: true, ..Stub::new(SourceKind::Repo, "repo", &[]) }),
+            Box::new(Stub::new(SourceKind::Flatpak, "flatpak", &["org.gnome.Platform", "org.mozilla.firefox"])),
+            Box::new(Stub::new(SourceKind::Plugin, "cargo", &["ripgrep"])),
+            Box::new(Stub::new(SourceKind::Plugin, "npm", &["ripgrep", "ripgrep-extra"])),
+        ];
+
+        // A broken source is skipped, the first exact match wins
+        assert_eq!(locate_installed(&backends, "ripgrep").unwrap(), Some((2, "ripgrep".to_string())));
+        assert_eq!(locate_installed(&backends, "npm/ripgrep").unwrap(), Some((3, "ripgrep".to_string())));
+        assert_eq!(locate_installed(&backends, "flatpak/org.mozilla.firefox").unwrap(), Some((1, "org.mozilla.firefox".to_string())));
+        // Only the named source is searched
+        assert_eq!(locate_installed(&backends, "cargo/org.gnome.Platform").unwrap(), None);
+        // Substrings don't count
+        assert_eq!(locate_installed(&backends, "ripgrep-ex").unwrap(), None);
+    }
+
+    #[test]
+    fn test_failing_backend_does_not_stop_the_others() {
+        let repo = Stub { fails: true, ..Stub::new(SourceKind::Repo, "repo", &[]) };
+        let flatpak = Stub::new(SourceKind::Flatpak, "flatpak", &[]);
+        let snap = Stub { fails: true, ..Stub::new(SourceKind::Snap, "snap", &[]) };
+        let appimage = Stub::new(SourceKind::AppImage, "appimage", &[]);
+        let updates: Vec<(&dyn PackageBackend, Vec<Update>)> = vec![
+            (&repo, vec![update("linux")]),
+            (&flatpak, vec![update("org.mozilla.firefox")]),
+            (&snap, vec![update("discord")]),
+            (&appimage, Vec::new()),
+        ];
+
+        let mut config = Config::default();
+        let failed = tokio_test::block_on(upgrade_all(&updates, &mut config, true));
+        assert_eq!(failed, vec!["repo", "snap"]);
+        assert_eq!([repo.calls.get(), flatpak.calls.get(), snap.calls.get(), appimage.calls.get()], [1, 1, 1, 0]);
+
+        let backends: Vec<Box<dyn PackageBackend>> = vec![
+            Box::new(Stub { fails: true, ..Stub::new(SourceKind::Repo, "repo", &[]) }),
+            Box::new(Stub::new(SourceKind::Flatpak, "flatpak", &[])),
+        ];
+        let selected: BTreeMap<SourceKind, Vec<PackageSource>> =
+            [(SourceKind::Repo, Vec::new()), (SourceKind::Flatpak, Vec::new())].into_iter().collect();
+        let failed = tokio_test::block_on(install_selected(&backends, &selected, &mut config, true));
+        assert_eq!(failed, vec!["repo"]);
+    }
+
+    #[test]
+    fn test_removal_cascades_per_backend() {
+        let backends: Vec<Box<dyn PackageBackend>> = vec![
+            Box::new(Stub { cascade: &["glibc"], ..Stub::new(SourceKind::Repo, "repo", &[]) }),
+            Box::new(Stub { cascade: &["org.gnome.Platform"], ..Stub::new(SourceKind::Flatpak, "flatpak", &[]) }),
+            Box::new(Stub { cascade: &["core22"], ..Stub::new(SourceKind::Snap, "snap", &[]) }),
+        ];
+        let selected = vec![(0, "firefox".to_string()), (1, "org.mozilla.firefox".to_string())];
+
+        // Pacman works out its own cascade, and nothing was selected from snap
+        let cascades = removal_cascades(&backends, &selected).unwrap();
+        assert_eq!(cascades, vec![Vec::<String>::new(), vec!["org.gnome.Platform".to_string()], Vec::new()]);
+    }
+}
@@ -0,0 +1,167 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::plugin::Plugin;
+use crate::ui;
+use colored::*;
+
+/// Packages of an external `khazaur-backend-*` executable
+pub struct PluginBackend {
+    pub plugin: &'static Plugin,
+}
+
+impl PluginBackend {
+    /// Names of the packages this plugin offered; other plugins share the source kind
+    fn own_names(&self, packages: &[PackageSource]) -> Vec<String> {
+        packages
+            .iter()
+            .filter_map(|pkg| match pkg {
+                PackageSource::Plugin(pkg) if pkg.backend == self.plugin.id => Some(pkg.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl PackageBackend for PluginBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Plugin
+    }
+
+    fn name(&self) -> &str {
+        &self.plugin.name
+    }
+
+    fn title(&self) -> &str {
+        &self.plugin.title
+    }
+
+    fn prefix(&self) -> &str {
+        &self.plugin.id
+    }
+
+    fn record_name(&self, name: &str) -> String {
+        format!("{}/{}", self.plugin.id, name)
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        self.plugin.available
+    }
+
+    fn search<'a>(&'a self, query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move { Ok(self.plugin.search(query)?.into_iter().map(PackageSource::Plugin).collect()) })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            // Search results may match descriptions, only names count here
+            let query = name.to_lowercase();
+            Ok(self
+                .plugin
+                .search(name)?
+                .into_iter()
+                .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+                .map(PackageSource::Plugin)
+                .collect())
+        })
+    }
+
+    fn info<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            let info = self.plugin.info(name)?;
+            Ok(info.package.map(|pkg| {
+                let mut output = ui::format_source_info(&PackageSource::Plugin(pkg));
+                for (key, value) in &info.details {
+                    output.push_str(&format!("{:<15} {}\n", format!("{}:", key).bold(), value));
+                }
+                output
+            }))
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        _config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let names = self.own_names(packages);
+            if names.is_empty() {
+                return Ok(());
+            }
+
+            println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} {} packages...", names.len(), self.plugin.name).bold());
+            let recorded: Vec<String> = names.iter().map(|name| self.record_name(name)).collect();
+            let tx = Transaction::begin("install", SourceKind::Plugin, &recorded);
+            match self.plugin.install(&names, noconfirm, false) {
+                Ok(()) => {
+                    println!("{}", ui::success(&format!("{} package(s) installed", self.plugin.name)));
+                    let _ = tx.finish(true);
+                }
+                Err(e) => {
+                    eprintln!("{}", ui::error(&format!("Failed to install {} packages: {}", self.plugin.name, e)));
+                    let _ = tx.fail(&e);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        self.plugin.remove(&[name.to_string()])
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        Ok(self
+            .plugin
+            .list()?
+            .into_iter()
+            .map(|pkg| InstalledPackage {
+                name: pkg.name,
+                version: pkg.version,
+                origin: pkg.origin,
+                dependency: pkg.dependency,
+            })
+            .collect())
+    }
+
+    fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(self
+                .plugin
+                .updates()?
+                .into_iter()
+                .map(|update| Update {
+                    name: update.name,
+                    current_version: update.current_version,
+                    new_version: update.new_version,
+                    display_name: None,
+                    package: None,
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], _config: &'a mut Config, noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), format!("Upgrading {} packages...", self.plugin.name).bold());
+            let names: Vec<String> = updates.iter().map(|update| update.name.clone()).collect();
+            let recorded: Vec<String> = names.iter().map(|name| self.record_name(name)).collect();
+            let tx = Transaction::begin("update", SourceKind::Plugin, &recorded);
+            match self.plugin.install(&names, noconfirm, true) {
+                Ok(()) => {
+                    println!("{}", ui::success(&format!("Successfully upgraded {} {} package(s)", names.len(), self.plugin.name)));
+                    let _ = tx.finish(true);
+                }
+                Err(e) => {
+                    let _ = tx.fail(&e);
+                    eprintln!("{}", ui::error(&format!("Failed to upgrade {} packages: {}", self.plugin.name, e)));
+                }
+            }
+            Ok(())
+        })
+    }
+}
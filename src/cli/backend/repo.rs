@@ -0,0 +1,174 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::pacman;
+use crate::ui;
+use colored::*;
+use std::collections::HashSet;
+use tracing::debug;
+
+/// Official repositories, through pacman
+pub struct RepoBackend;
+
+impl PackageBackend for RepoBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Repo
+    }
+
+    fn name(&self) -> &str {
+        "Repositories"
+    }
+
+    fn title(&self) -> &str {
+        "Repository Packages"
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        true
+    }
+
+    fn search<'a>(&'a self, query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move { Ok(pacman::search_repos(query)?.into_iter().map(PackageSource::Repo).collect()) })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            let found = match pacman::search_repos(name) {
+                Ok(packages) => packages.into_iter().find(|pkg| pkg.name == name),
+                Err(e) => {
+                    debug!("Repo search error: {}", e);
+                    None
+                }
+            };
+
+            // Search can miss a package that exists (installed but not in a sync
+            // database), look the name up directly then
+            let found = match found {
+                Some(pkg) => Some(pkg),
+                None => pacman::get_package_details(name).ok().flatten(),
+            };
+            Ok(found.map(PackageSource::Repo).into_iter().collect())
+        })
+    }
+
+    fn info<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Option<String>>> {
+        Box::pin(async move {
+            Ok(pacman::get_repo_info(name)?
+                .map(|info| format!("{}\n{}", ui::section_header(&format!("Repository Package: {}", name)), info)))
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        _config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let names: Vec<String> = packages.iter().map(|pkg| pkg.name().to_string()).collect();
+            install_names(&names, noconfirm)
+        })
+    }
+
+    fn install_by_name<'a>(
+        &'a self,
+        names: &'a [String],
+        _config: &'a mut Config,
+        noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        // Pacman finds the packages itself, no need to search for each
+        Box::pin(async move { install_names(names, noconfirm) })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        pacman::remove_packages(&[name.to_string()], &["--noconfirm".to_string()])
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        let foreign: HashSet<String> = pacman::get_installed_aur_packages()?.into_iter().map(|(name, _)| name).collect();
+        let packages = pacman::get_installed_packages()?
+            .into_iter()
+            .filter(|(name, _)| !foreign.contains(name))
+            .collect();
+        pacman_installed(packages)
+    }
+
+    fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(pacman::get_repo_updates()?
+                .into_iter()
+                .map(|(name, current_version, new_version)| Update {
+                    name,
+                    current_version,
+                    new_version,
+                    display_name: None,
+                    package: None,
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], _config: &'a mut Config, noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), "Upgrading repository packages...".bold());
+            let names: Vec<String> = updates.iter().map(|update| update.name.clone()).collect();
+            let extra_args = if noconfirm { vec!["--noconfirm".to_string()] } else { vec![] };
+            let tx = Transaction::begin("update", SourceKind::Repo, &names);
+            match pacman::install_packages(&names, &extra_args) {
+                Ok(()) => {
+                    let _ = tx.finish(true);
+                    Ok(())
+                }
+                Err(e) => {
+                    let _ = tx.fail(&e);
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+/// Install repository packages that aren't installed yet. A failure is reported
+/// and the other sources carry on.
+fn install_names(names: &[String], noconfirm: bool) -> Result<()> {
+    let mut to_install = Vec::new();
+    for name in names {
+        if pacman::is_installed(name)? {
+            println!("{} {} {}", "::".bright_blue().bold(), name.bold(), "is already installed".dimmed());
+        } else {
+            to_install.push(name.clone());
+        }
+    }
+    if to_install.is_empty() {
+        return Ok(());
+    }
+
+    println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} repository packages...", to_install.len()).bold());
+    let extra_args = if noconfirm { vec!["--noconfirm".to_string()] } else { vec![] };
+    let tx = Transaction::begin("install", SourceKind::Repo, &to_install);
+    if let Err(e) = pacman::install_packages(&to_install, &extra_args) {
+        let _ = tx.fail(&e);
+        eprintln!("{}", ui::error(&format!("Failed to install repository packages: {}", e)));
+        eprintln!("{}", ui::info("Continuing with other packages..."));
+    } else {
+        let _ = tx.finish(true);
+    }
+    Ok(())
+}
+
+/// Installed pacman packages with whether each was installed as a dependency
+pub(super) fn pacman_installed(packages: Vec<(String, String)>) -> Result<Vec<InstalledPackage>> {
+    let dependencies = pacman::get_dependency_packages()?;
+    Ok(packages
+        .into_iter()
+        .map(|(name, version)| InstalledPackage {
+            dependency: dependencies.contains(&name),
+            name,
+            version,
+            origin: None,
+        })
+        .collect())
+}
+
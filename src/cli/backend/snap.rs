@@ -0,0 +1,145 @@
+use super::{BoxFuture, InstalledPackage, PackageBackend, Update};
+use crate::cli::{PackageSource, SourceKind};
+use crate::config::Config;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::snap;
+use crate::ui;
+use colored::*;
+
+/// Snaps from the Snap Store, through snapd
+pub struct SnapBackend;
+
+impl PackageBackend for SnapBackend {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Snap
+    }
+
+    fn name(&self) -> &str {
+        "Snap"
+    }
+
+    fn title(&self) -> &str {
+        "Snap Packages"
+    }
+
+    fn is_available(&self, _config: &Config) -> bool {
+        snap::is_available()
+    }
+
+    fn ensure_available<'a>(&'a self, config: &'a mut Config) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            if !snap::is_available() {
+                crate::cli::optional_deps::check_and_prompt_snapd(config).await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move { Ok(snap::search_snap(query)?.into_iter().map(PackageSource::Snap).collect()) })
+    }
+
+    fn find<'a>(&'a self, name: &'a str, _config: &'a Config) -> BoxFuture<'a, Result<Vec<PackageSource>>> {
+        Box::pin(async move {
+            // Match if the query appears in the name
+            let query = name.to_lowercase();
+            Ok(snap::search_snap(name)?
+                .into_iter()
+                .filter(|pkg| pkg.name.to_lowercase().contains(&query))
+                .map(PackageSource::Snap)
+                .collect())
+        })
+    }
+
+    fn install<'a>(
+        &'a self,
+        packages: &'a [PackageSource],
+        _config: &'a mut Config,
+        _noconfirm: bool,
+    ) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} Snap packages...", packages.len()).bold());
+            for pkg in packages {
+                let name = pkg.name().to_string();
+                let tx = Transaction::begin("install", SourceKind::Snap, std::slice::from_ref(&name));
+                let installed_before = snap::get_installed_snaps("").unwrap_or_default();
+                if let Err(e) = snap::install_snap(&name).await {
+                    eprintln!("{}", ui::error(&format!("Failed to install {}: {}", name, e)));
+                    let _ = tx.fail(&e);
+                } else {
+                    let _ = crate::reasons::record(SourceKind::Snap, &name, crate::reasons::InstallReason::Explicit);
+                    record_pulled_dependencies(&name, &installed_before);
+                    let _ = tx.finish(true);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn remove(&self, name: &str) -> Result<()> {
+        snap::uninstall_snap(name)
+    }
+
+    fn list_installed(&self) -> Result<Vec<InstalledPackage>> {
+        Ok(snap::get_installed_snaps("")?
+            .into_iter()
+            .map(|name| InstalledPackage {
+                name,
+                version: String::new(),
+                origin: None,
+                dependency: false,
+            })
+            .collect())
+    }
+
+    fn installed_size(&self, name: &str) -> Option<u64> {
+        snap::get_snap_size(name)
+    }
+
+    fn check_updates<'a>(&'a self, _config: &'a Config) -> BoxFuture<'a, Result<Vec<Update>>> {
+        Box::pin(async move {
+            Ok(snap::get_updates()?
+                .into_iter()
+                .map(|(name, current_version, new_version)| Update {
+                    name,
+                    current_version,
+                    new_version,
+                    display_name: None,
+                    package: None,
+                })
+                .collect())
+        })
+    }
+
+    fn upgrade<'a>(&'a self, updates: &'a [Update], _config: &'a mut Config, _noconfirm: bool) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            println!("\n{} {}", "::".bright_blue().bold(), "Upgrading Snap packages...".bold());
+            println!("{}", ui::warning("Note: Snap update support is experimental and not fully tested"));
+            let names: Vec<String> = updates.iter().map(|update| update.name.clone()).collect();
+            let tx = Transaction::begin("update", SourceKind::Snap, &names);
+            match snap::update_all() {
+                Ok(_) => {
+                    println!("{}", ui::success(&format!("Successfully upgraded {} Snap package(s)", updates.len())));
+                    let _ = tx.finish(true);
+                }
+                Err(e) => {
+                    let _ = tx.fail(&e);
+                    eprintln!("{}", ui::error(&format!("Failed to upgrade Snap packages: {}", e)));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Record the base and content snaps a snap brought in as dependencies, so the
+/// orphan check can remove them once no snap uses them
+fn record_pulled_dependencies(name: &str, installed_before: &[String]) {
+    let installed = snap::get_installed_snaps("").unwrap_or_default();
+    for dep in snap::get_snap_dependencies(name) {
+        if !installed_before.contains(&dep) && installed.contains(&dep) {
+            let _ = crate::reasons::record(SourceKind::Snap, &dep, crate::reasons::InstallReason::Dependency);
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use crate::aur::{download, AurClient, AurPackage};
 use crate::build;
+use crate::cli::SourceKind;
 use crate::config::Config;
 use crate::error::Result;
+use crate::history::Transaction;
+use crate::reasons::InstallReason;
 use crate::resolver::Resolver;
 use crate::ui;
 use colored::*;
@@ -81,16 +84,36 @@ pub async fn install_aur_packages(
         println!("{} {}", "::".bright_blue().bold(), format!("Build order: {}", build_order.join(" -> ")).bold());
     }
 
+    // Packages to build in order, with whether each one is only a dependency of the others
+    let mut queue: Vec<(AurPackage, bool)> = Vec::new();
+    for name in &build_order {
+        if let Some(pkg) = to_install.iter().find(|pkg| &pkg.name == name) {
+            queue.push((pkg.clone(), false));
+        } else {
+            match client.info(name).await {
+                Ok(pkg) => queue.push((pkg, true)),
+                Err(e) => {
+                    eprintln!("{}", ui::error(&format!("Failed to get info for dependency {}: {}", name, e)));
+                }
+            }
+        }
+    }
+    for pkg in &to_install {
+        if !queue.iter().any(|(queued, _)| queued.name == pkg.name) {
+            queue.push((pkg.clone(), false));
+        }
+    }
+
     // Download all PKGBUILDs first (they're small, pre-download for instant viewing)
     println!("\n{} {}", "::".bright_blue().bold(), "Downloading PKGBUILDs...".bold());
-    let mut package_dirs = Vec::new();
+    let mut downloaded = Vec::new();
 
-    for pkg in &to_install {
+    for (pkg, dependency) in queue {
         let spinner = ui::spinner(&format!("Downloading {}...", pkg.name));
         match download::download_package(&client, &pkg.name, config).await {
             Ok(pkg_dir) => {
                 spinner.finish_with_message(format!("✓ {}", pkg.name));
-                package_dirs.push(pkg_dir);
+                downloaded.push((pkg, pkg_dir, dependency));
             }
             Err(e) => {
                 spinner.finish_and_clear();
@@ -106,14 +129,14 @@ pub async fn install_aur_packages(
     if !noconfirm {
         println!("\n{} {}", "::".bright_blue().bold(), "Reviewing PKGBUILDs...".bold());
 
-        for (idx, pkg) in to_install.iter().enumerate() {
+        for (idx, (pkg, pkg_dir, _)) in downloaded.iter().enumerate() {
             println!("\n{} {} {}",
                 "::".bright_blue().bold(),
-                format!("({}/{})", idx + 1, to_install.len()).bright_black(),
+                format!("({}/{})", idx + 1, downloaded.len()).bright_black(),
                 format!("Review {}...", pkg.name).bold()
             );
 
-            let pkgbuild_path = package_dirs[idx].join("PKGBUILD");
+            let pkgbuild_path = pkg_dir.join("PKGBUILD");
             let should_continue = ui::view_pkgbuild_interactive(&pkgbuild_path, config)?;
             if should_continue {
                 packages_to_build.push(idx);
@@ -125,7 +148,7 @@ pub async fn install_aur_packages(
         // Show summary of what will be built
         if !packages_to_build.is_empty() {
             let packages_list: Vec<&str> = packages_to_build.iter()
-                .map(|&idx| to_install[idx].name.as_str())
+                .map(|&idx| downloaded[idx].0.name.as_str())
                 .collect();
             println!("\n{} {}: {}",
                 "::".bright_blue().bold(),
@@ -138,30 +161,42 @@ pub async fn install_aur_packages(
         }
     } else {
         // If noconfirm, build all packages
-        packages_to_build = (0..to_install.len()).collect();
+        packages_to_build = (0..downloaded.len()).collect();
     }
 
-    // Phase 2: Build and install packages
+    // Phase 2: Build and install packages, dependencies first
     let mut installed_count = 0;
     if !packages_to_build.is_empty() {
         println!("\n{} {}", "::".bright_blue().bold(), "Building packages...".bold());
 
         for &idx in &packages_to_build {
-            let pkg = &to_install[idx];
-            let pkg_dir = &package_dirs[idx];
+            let (pkg, pkg_dir, dependency) = &downloaded[idx];
 
             // Prompt user about removing make dependencies after installation
             let remove_make_deps = prompt_remove_make_deps(pkg, noconfirm)?;
 
             println!("\n{} {}", "::".bright_cyan(), format!("Building {}...", pkg.name).bold());
 
+            // Dependencies get their own history entry, the caller records the requested packages
+            let tx = dependency.then(|| Transaction::begin("install", SourceKind::Aur, std::slice::from_ref(&pkg.name)));
+
             // Build and install with makepkg, with optional make dependency removal
             match build::build_and_install_with_make_deps_cleanup(pkg_dir, true, pkg, config, remove_make_deps) {
                 Ok(_) => {
                     println!("{}", ui::success(&format!("{} installed successfully", pkg.name)));
+                    if let Some(tx) = tx {
+                        let _ = tx.finish(true);
+                        // makepkg installs it as explicit; mark it so orphan checks can find it
+                        if let Err(e) = crate::pacman::set_install_reason(std::slice::from_ref(&pkg.name), InstallReason::Dependency) {
+                            eprintln!("{}", ui::warning(&format!("Could not mark {} as a dependency: {}", pkg.name, e)));
+                        }
+                    }
                     installed_count += 1;
                 }
                 Err(e) => {
+                    if let Some(tx) = tx {
+                        let _ = tx.fail(&e);
+                    }
                     eprintln!("{}", ui::error(&format!("Build failed for {}: {}", pkg.name, e)));
                 }
             }
@@ -175,4 +210,116 @@ pub async fn install_aur_packages(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// Upgrade installed AUR packages to the given newer versions: download all PKGBUILDs,
+/// let the user review them, then build and install the ones kept
+pub async fn upgrade_aur_packages(aur_pkgs: &[AurPackage], config: &mut Config, noconfirm: bool) -> Result<()> {
+    println!("\n{} {}", "::".bright_blue().bold(), "Upgrading AUR packages...".bold());
+
+    let client = AurClient::new()?;
+
+    // Download all PKGBUILDs
+    println!("\n{} {}", "::".bright_blue().bold(), "Downloading PKGBUILDs...".bold());
+    let mut package_dirs = Vec::<std::path::PathBuf>::new();
+
+    for pkg in aur_pkgs {
+        let spinner = ui::spinner(&format!("Downloading {}...", pkg.name));
+        match download::download_package(&client, &pkg.name, config).await {
+            Ok(pkg_dir) => {
+                spinner.finish_with_message(format!("✓ {}", pkg.name));
+                package_dirs.push(pkg_dir);
+            }
+            Err(e) => {
+                spinner.finish_and_clear();
+                eprintln!("{}", ui::error(&format!("Failed to download {}: {}", pkg.name, e)));
+                continue;
+            }
+        }
+    }
+
+    // Review PKGBUILDs if not noconfirm
+    let mut packages_to_build: Vec<usize> = Vec::new();
+
+    if !noconfirm && config.review_pkgbuild {
+        println!("\n{} {}", "::".bright_blue().bold(), "Reviewing PKGBUILDs...".bold());
+
+        for (idx, pkg) in aur_pkgs.iter().enumerate() {
+            if idx >= package_dirs.len() {
+                continue;
+            }
+
+            println!("\n{} {} {}",
+                "::".bright_blue().bold(),
+                format!("({}/{})", idx + 1, aur_pkgs.len()).bright_black(),
+                format!("Review {}...", pkg.name).bold()
+            );
+
+            let pkgbuild_path = package_dirs[idx].join("PKGBUILD");
+            let should_continue = ui::view_pkgbuild_interactive(&pkgbuild_path, config)?;
+            if should_continue {
+                packages_to_build.push(idx);
+            } else {
+                println!("{} {}", "::".yellow().bold(), format!("Skipping {}", pkg.name).bold());
+            }
+        }
+
+        if packages_to_build.is_empty() {
+            println!("\n{} {}", "::".yellow().bold(), "No AUR packages selected for upgrade".bold());
+            return Ok(());
+        }
+    } else {
+        // Build all packages
+        packages_to_build = (0..aur_pkgs.len().min(package_dirs.len())).collect();
+    }
+
+    // Build and install packages
+    println!("\n{} {}", "::".bright_blue().bold(), "Building and installing AUR packages...".bold());
+    let mut upgraded_count = 0;
+
+    for &idx in &packages_to_build {
+        let pkg = &aur_pkgs[idx];
+        let pkg_dir = &package_dirs[idx];
+
+        // For upgrades, we'll check if user wants to remove make dependencies
+        // but we'll default to not removing them during upgrades to be safe
+        let remove_make_deps = if !noconfirm {
+            let make_deps_list = pkg.make_depends.join(", ");
+            let prompt = format!(
+                "Remove make dependencies ({}) after upgrading {}?",
+                make_deps_list,
+                pkg.name
+            );
+
+            Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt(prompt)
+                .default(false)  // Default to false for upgrades
+                .interact()?
+        } else {
+            false  // Don't remove make deps during upgrades with noconfirm
+        };
+
+        println!("\n{} {}", "::".bright_cyan(), format!("Building {}...", pkg.name).bold());
+
+        let tx = Transaction::begin("update", SourceKind::Aur, std::slice::from_ref(&pkg.name));
+        match build::build_and_install_with_make_deps_cleanup(pkg_dir, true, pkg, config, remove_make_deps) {
+            Ok(_) => {
+                println!("{}", ui::success(&format!("{} upgraded successfully", pkg.name)));
+                let _ = tx.finish(true);
+                upgraded_count += 1;
+            }
+            Err(e) => {
+                let _ = tx.fail(&e);
+                eprintln!("{}", ui::error(&format!("Build failed for {}: {}", pkg.name, e)));
+            }
+        }
+    }
+
+    if upgraded_count > 0 {
+        println!("\n{} {}",
+            "::".bright_green().bold(),
+            format!("Successfully upgraded {} AUR package(s)", upgraded_count).bold()
+        );
+    }
+
+    Ok(())
+}
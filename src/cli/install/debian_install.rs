@@ -0,0 +1,152 @@
+use crate::cli::SourceKind;
+use crate::config::Config;
+use crate::debian::plan::InstallPlan;
+use crate::debian::DebianPackage;
+use crate::error::Result;
+use crate::history::Transaction;
+use crate::reasons::InstallReason;
+use crate::ui;
+use colored::*;
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+/// Install Debian packages with their dependencies: the Arch packages standing in for
+/// Debian ones from the repositories first, then the Debian packages in dependency order
+pub async fn install_debian_packages(packages: &[DebianPackage], config: &Config, noconfirm: bool) -> Result<()> {
+    let spinner = ui::Spinner::new("Resolving Debian dependencies...");
+    let plan = InstallPlan::for_packages(packages, config).await;
+    spinner.inner().finish_and_clear();
+    let plan = plan?;
+
+    print_plan(&plan);
+
+    let needs_review = !plan.arch.is_empty() || plan.dependencies().next().is_some() || !plan.unresolved.is_empty();
+    if needs_review && !noconfirm {
+        let confirmed = Confirm::with_theme(&ColorfulTheme::default())
+            .with_prompt("Proceed with installation?")
+            .default(plan.unresolved.is_empty())
+            .interact()?;
+        if !confirmed {
+            println!("{}", ui::info("Installation cancelled"));
+            return Ok(());
+        }
+    }
+
+    if !plan.arch.is_empty() {
+        println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} repository dependencies...", plan.arch.len()).bold());
+        let tx = Transaction::begin("install", SourceKind::Repo, &plan.arch);
+        if let Err(e) = crate::pacman::install_packages(&plan.arch, &["--needed".to_string(), "--asdeps".to_string()]) {
+            let _ = tx.fail(&e);
+            return Err(e);
+        }
+        let _ = tx.finish(true);
+    }
+
+    println!("\n{} {}", "::".bright_blue().bold(), format!("Installing {} Debian packages...", plan.debian.len()).bold());
+    let packages: Vec<&DebianPackage> = plan.debian.iter().map(|planned| &planned.package).collect();
+    let downloads = crate::debian::download_debians(&packages, config.concurrent_downloads).await;
+
+    for (planned, download) in plan.debian.iter().zip(downloads) {
+        let pkg = &planned.package;
+        let tx = Transaction::begin("install", SourceKind::Debian, std::slice::from_ref(&pkg.name));
+
+        let installed = match download {
+            Ok(deb_path) => {
+                crate::debian::install_deb(&deb_path, Some(pkg), Some(&planned.depends), planned.reason, config).await
+            }
+            Err(e) => Err(e),
+        };
+
+        match installed {
+            Ok(tracked) => {
+                let _ = crate::reasons::record(SourceKind::Debian, &tracked.arch_name, planned.reason);
+                let _ = tx.finish(true);
+            }
+            Err(e) => {
+                eprintln!("{}", ui::error(&format!("Failed to install {}: {}", pkg.name, e)));
+                let _ = tx.fail(&e);
+                // Packages depending on this one can't be installed either
+                if planned.reason == InstallReason::Dependency {
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_plan(plan: &InstallPlan) {
+    if !plan.arch.is_empty() {
+        println!("\n{} {}", "::".bright_blue().bold(), "Repository packages for Debian dependencies:".bold());
+        println!("   {}", plan.arch.join("  "));
+    }
+
+    let dependencies: Vec<String> = plan
+        .dependencies()
+        .map(|planned| format!("{}-{} ({})", planned.package.name, planned.package.version, planned.package.suite))
+        .collect();
+    if !dependencies.is_empty() {
+        println!("\n{} {}", "::".bright_blue().bold(), "Debian packages pulled in as dependencies:".bold());
+        println!("   {}", dependencies.join("  "));
+    }
+
+    if !plan.unresolved.is_empty() {
+        println!("\n{}", ui::warning("No Arch or Debian package found for these dependencies:"));
+        for dep in &plan.unresolved {
+            println!("   {}", dep);
+        }
+        println!(
+            "{}",
+            ui::info("Map them to Arch packages in ~/.config/khazaur/debian-map.toml if they have another name")
+        );
+    }
+}
+
+/// Upgrade converted Debian packages to newer versions from the index, keeping
+/// the install reason each was recorded with
+pub async fn upgrade_debian_packages(updates: &[(String, DebianPackage)], config: &Config) -> Result<()> {
+    println!("\n{} {}", "::".bright_blue().bold(), "Upgrading Debian packages...".bold());
+
+    let mut upgraded_count = 0;
+
+    let packages: Vec<&DebianPackage> = updates.iter().map(|(_, debian_pkg)| debian_pkg).collect();
+    let downloads = crate::debian::download_debians(&packages, config.concurrent_downloads).await;
+
+    for ((name, debian_pkg), download) in updates.iter().zip(downloads) {
+        println!("\n{} {}", "::".bright_cyan(), format!("Converting {}...", name).bold());
+        let tx = Transaction::begin("update", SourceKind::Debian, std::slice::from_ref(name));
+
+        let installed = match download {
+            Ok(deb_path) => {
+                // Convert and install, keeping the recorded install reason
+                let reason = crate::reasons::ReasonDb::load()
+                    .ok()
+                    .and_then(|reasons| reasons.get(SourceKind::Debian, name))
+                    .unwrap_or(crate::reasons::InstallReason::Explicit);
+                crate::debian::install_deb(&deb_path, Some(debian_pkg), None, reason, config).await
+            }
+            Err(e) => Err(e),
+        };
+
+        match installed {
+            Ok(_) => {
+                println!("{}", ui::success(&format!("{} upgraded successfully", name)));
+                let _ = tx.finish(true);
+                upgraded_count += 1;
+            }
+            Err(e) => {
+                let _ = tx.fail(&e);
+                eprintln!("{}", ui::error(&format!("Failed to upgrade {}: {}", name, e)));
+            }
+        }
+    }
+
+    if upgraded_count > 0 {
+        println!("\n{} {}",
+            "::".bright_green().bold(),
+            format!("Successfully upgraded {} Debian package(s)", upgraded_count).bold()
+        );
+    }
+
+    Ok(())
+}
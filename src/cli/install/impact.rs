@@ -0,0 +1,409 @@
+use crate::aur::AurClient;
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use crate::pacman::db::{dep_name, PackageDb};
+use crate::ui;
+use colored::*;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Free space an AUR build is expected to need in the clone directory.
+/// Build sizes aren't known up front, so this is only used for a warning.
+const AUR_BUILD_RESERVE: u64 = 1024 * 1024 * 1024;
+
+/// Space to keep free on a filesystem after installing before warning
+const LOW_SPACE_MARGIN: u64 = 512 * 1024 * 1024;
+
+/// One package the installation would add
+struct ImpactItem {
+    source: &'static str,
+    name: String,
+    version: Option<String>,
+    download_size: Option<u64>,
+    installed_size: Option<u64>,
+    /// Pulled in as a dependency rather than requested
+    dependency: bool,
+}
+
+/// Everything an installation would add, across sources
+pub struct InstallImpact {
+    items: Vec<ImpactItem>,
+}
+
+impl InstallImpact {
+    /// Compute the packages to be installed: the repo and AUR dependency closure
+    /// (from the pacman databases, AUR for the rest) plus Flatpak runtimes and snaps
+    pub async fn compute(repo: &[String], aur: &[String], flatpak: &[String], snap: &[String]) -> Result<Self> {
+        let mut items = Vec::new();
+
+        if !repo.is_empty() || !aur.is_empty() {
+            pacman_closure(repo, aur, &mut items).await?;
+        }
+
+        let mut runtimes = HashSet::new();
+        for app_id in flatpak {
+            if crate::flatpak::install::is_flatpak_installed(app_id)? {
+                continue;
+            }
+            let info = crate::flatpak::install::remote_info(app_id).unwrap_or_default();
+            items.push(ImpactItem {
+                source: "flatpak",
+                name: app_id.clone(),
+                version: None,
+                download_size: info.download_size,
+                installed_size: info.installed_size,
+                dependency: false,
+            });
+
+            let Some(runtime) = info.runtime else {
+                continue;
+            };
+            if !runtimes.insert(runtime.clone()) || crate::flatpak::install::is_flatpak_ref_installed(&runtime)? {
+                continue;
+            }
+            let runtime_info = crate::flatpak::install::remote_info(&format!("runtime/{}", runtime)).unwrap_or_default();
+            items.push(ImpactItem {
+                source: "flatpak",
+                name: runtime,
+                version: None,
+                download_size: runtime_info.download_size,
+                installed_size: runtime_info.installed_size,
+                dependency: true,
+            });
+        }
+
+        for name in snap {
+            if crate::snap::is_snap_installed(name)? {
+                continue;
+            }
+            // Snaps stay compressed on disk, so the download is also the installed size
+            let size = crate::snap::get_download_size(name).await;
+            items.push(ImpactItem {
+                source: "snap",
+                name: name.clone(),
+                version: None,
+                download_size: size,
+                installed_size: size,
+                dependency: false,
+            });
+        }
+
+        Ok(Self { items })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("\n{} {}", "::".bright_blue().bold(), "Packages to install:".bold());
+
+        let size = |s: Option<u64>| s.map(ui::format_size).unwrap_or_else(|| "?".to_string());
+        for item in &self.items {
+            let name = match item.version {
+                Some(ref version) => format!("{} {}", item.name, version),
+                None => item.name.clone(),
+            };
+            let note = if item.dependency { "dependency" } else { "" };
+            let line = format!(
+                "  {:<45} {:>10} {:>10}  {:<8} {}",
+                name,
+                size(item.download_size),
+                size(item.installed_size),
+                item.source,
+                note.dimmed()
+            );
+            if item.dependency {
+                println!("{}", line);
+            } else {
+                println!("{}", line.bold());
+            }
+        }
+
+        let download: u64 = self.items.iter().filter_map(|i| i.download_size).sum();
+        let installed: u64 = self.items.iter().filter_map(|i| i.installed_size).sum();
+        let unknown = self.items.iter().filter(|i| i.installed_size.is_none()).count();
+
+        println!();
+        println!("{}", ui::info(&format!(
+            "{} package(s), download {}, installed {}",
+            self.items.len(),
+            ui::format_size(download),
+            ui::format_size(installed)
+        )));
+        if unknown > 0 {
+            println!("{}", ui::info(&format!("Size of {} package(s) unknown (AUR packages are built locally)", unknown)));
+        }
+    }
+
+    /// Check free space on every filesystem the installation writes to.
+    /// Errors when a filesystem is too small, warns when it would end up nearly full.
+    pub fn check_disk_space(&self, config: &Config) -> Result<()> {
+        let mounts = group_by_mount(&self.space_needs(&config.clone_dir), free_space);
+        if mounts.is_empty() {
+            return Ok(());
+        }
+
+        let building_aur = self.items.iter().any(|i| i.source == "aur");
+        let clone_mount = free_space(&config.clone_dir).map(|(mount, _)| mount);
+        let mut short = false;
+
+        println!("\n{} {}", "::".bright_blue().bold(), "Disk space:".bold());
+        for (mount, (need, available)) in &mounts {
+            let line = format!("  {:<30} needs {:>10}, {:>10} free", mount, ui::format_size(*need), ui::format_size(*available));
+            let reserve = if building_aur && clone_mount.as_ref() == Some(mount) { AUR_BUILD_RESERVE } else { 0 };
+
+            match space_status(*need, *available, reserve) {
+                SpaceStatus::Short => {
+                    println!("{}", line.red());
+                    short = true;
+                }
+                SpaceStatus::Low => {
+                    println!("{}", line.yellow());
+                    if reserve > 0 {
+                        println!("{}", ui::warning(&format!("{} may not have enough room to build AUR packages", mount)));
+                    } else {
+                        println!("{}", ui::warning(&format!("{} will be nearly full after installing", mount)));
+                    }
+                }
+                SpaceStatus::Enough => println!("{}", line),
+            }
+        }
+
+        if short {
+            return Err(KhazaurError::Config("Not enough free disk space for this installation".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Bytes written under each path the installation touches. The clone directory
+    /// is listed with nothing for AUR builds so its filesystem is checked for the reserve.
+    fn space_needs(&self, clone_dir: &Path) -> Vec<(PathBuf, u64)> {
+        let sum = |source: &str, installed: bool| -> u64 {
+            self.items
+                .iter()
+                .filter(|i| i.source == source)
+                .filter_map(|i| if installed { i.installed_size } else { i.download_size })
+                .sum()
+        };
+
+        let mut needs: Vec<(PathBuf, u64)> = Vec::new();
+        let repo_installed = sum("repo", true);
+        if repo_installed > 0 {
+            needs.push((PathBuf::from("/"), repo_installed));
+            needs.push((PathBuf::from("/var/cache/pacman/pkg"), sum("repo", false)));
+        }
+        if self.items.iter().any(|i| i.source == "aur") {
+            needs.push((clone_dir.to_path_buf(), 0));
+        }
+        let flatpak_installed = sum("flatpak", true);
+        if flatpak_installed > 0 {
+            needs.push((PathBuf::from("/var/lib/flatpak"), flatpak_installed));
+        }
+        let snap_download = sum("snap", false);
+        if snap_download > 0 {
+            needs.push((PathBuf::from("/var/lib/snapd"), snap_download));
+        }
+        needs
+    }
+}
+
+/// How a filesystem fares after the installation
+#[derive(Debug, PartialEq)]
+enum SpaceStatus {
+    Enough,
+    /// Fits, but leaves less than the margin (plus any build reserve) free
+    Low,
+    Short,
+}
+
+fn space_status(need: u64, available: u64, reserve: u64) -> SpaceStatus {
+    if need > available {
+        SpaceStatus::Short
+    } else if available - need < LOW_SPACE_MARGIN + reserve {
+        SpaceStatus::Low
+    } else {
+        SpaceStatus::Enough
+    }
+}
+
+/// Add up the needs per filesystem, as mount point -> (needed, available).
+/// Several paths usually live on the same filesystem; paths `free_space` can't
+/// place are left out.
+fn group_by_mount(
+    needs: &[(PathBuf, u64)],
+    free_space: impl Fn(&Path) -> Option<(String, u64)>,
+) -> BTreeMap<String, (u64, u64)> {
+    let mut mounts: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for (path, need) in needs {
+        let Some((mount, available)) = free_space(path) else {
+            continue;
+        };
+        let entry = mounts.entry(mount).or_insert((0, available));
+        entry.0 += need;
+    }
+    mounts
+}
+
+/// Walk the dependencies of repo and AUR targets, skipping what is installed.
+/// Sizes come from the sync databases; AUR packages have none until built.
+async fn pacman_closure(repo: &[String], aur: &[String], items: &mut Vec<ImpactItem>) -> Result<()> {
+    let local = PackageDb::load_local()?;
+    let sync = PackageDb::load_sync().unwrap_or_default();
+    let client = AurClient::new()?;
+
+    let targets: HashSet<&str> = repo.iter().chain(aur).map(String::as_str).collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut frontier: Vec<String> = repo.iter().chain(aur).cloned().collect();
+
+    while !frontier.is_empty() {
+        let mut next = Vec::new();
+        let mut unresolved = Vec::new();
+
+        for dep in &frontier {
+            let name = dep_name(dep);
+            if local.resolve(dep).is_some() || !seen.insert(name.to_string()) {
+                continue;
+            }
+
+            // Explicit AUR targets skip the repos, everything else prefers them
+            let from_aur = aur.iter().any(|a| a == name);
+            match sync.resolve(dep).filter(|_| !from_aur) {
+                Some(pkg) => {
+                    // Resolved through provides: the provider may already be listed
+                    if pkg.name != name && !seen.insert(pkg.name.clone()) {
+                        continue;
+                    }
+                    items.push(ImpactItem {
+                        source: "repo",
+                        name: pkg.name.clone(),
+                        version: Some(pkg.version.clone()),
+                        download_size: Some(pkg.download_size),
+                        installed_size: Some(pkg.installed_size),
+                        dependency: !targets.contains(pkg.name.as_str()),
+                    });
+                    next.extend(pkg.depends.iter().cloned());
+                }
+                None => unresolved.push(name.to_string()),
+            }
+        }
+
+        if !unresolved.is_empty() {
+            for pkg in client.info_batch(&unresolved).await? {
+                items.push(ImpactItem {
+                    source: "aur",
+                    name: pkg.name.clone(),
+                    version: Some(pkg.version.clone()),
+                    download_size: None,
+                    installed_size: None,
+                    dependency: !targets.contains(pkg.name.as_str()),
+                });
+                next.extend(pkg.depends.iter().cloned());
+                next.extend(pkg.make_depends.iter().cloned());
+            }
+        }
+
+        frontier = next;
+    }
+
+    Ok(())
+}
+
+/// Mount point and available bytes for the filesystem holding `path`,
+/// using the nearest existing parent for paths not created yet
+fn free_space(path: &Path) -> Option<(String, u64)> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    let output = Command::new("df")
+        .args(["-B1", "--output=target,avail"])
+        .arg(existing)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_df(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Mount point and available bytes from `df -B1 --output=target,avail`:
+/// a header line, then the mount point (which may contain spaces) and the count
+fn parse_df(output: &str) -> Option<(String, u64)> {
+    let line = output.lines().nth(1)?;
+    let (mount, available) = line.trim().rsplit_once(char::is_whitespace)?;
+    Some((mount.trim().to_string(), available.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn item(source: &'static str, download: Option<u64>, installed: Option<u64>) -> ImpactItem {
+        ImpactItem {
+            source,
+            name: format!("{}-pkg", source),
+            version: None,
+            download_size: download,
+            installed_size: installed,
+            dependency: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_df() {
+        let output = "Mounted on            Avail\n/                 53687091200\n";
+        assert_eq!(parse_df(output), Some(("/".to_string(), 53687091200)));
+
+        let output = "Mounted on      Avail\n/mnt/my disk    1024\n";
+        assert_eq!(parse_df(output), Some(("/mnt/my disk".to_string(), 1024)));
+
+        assert_eq!(parse_df("Mounted on Avail\n"), None);
+        assert_eq!(parse_df("Mounted on Avail\n/ -\n"), None);
+    }
+
+    #[test]
+    fn test_needs_are_summed_per_mount() {
+        let impact = InstallImpact {
+            items: vec![
+                item("repo", Some(100), Some(300)),
+                item("repo", Some(50), Some(200)),
+                item("aur", None, None),
+                item("flatpak", Some(1000), Some(4000)),
+            ],
+        };
+        let clone_dir = PathBuf::from("/home/user/.cache/khazaur/clone");
+        let needs = impact.space_needs(&clone_dir);
+        assert_eq!(needs, vec![
+            (PathBuf::from("/"), 500),
+            (PathBuf::from("/var/cache/pacman/pkg"), 150),
+            (clone_dir, 0),
+            (PathBuf::from("/var/lib/flatpak"), 4000),
+        ]);
+
+        // Everything on / except the home directory
+        let mounts = group_by_mount(&needs, |path| {
+            Some(if path.starts_with("/home") { ("/home".to_string(), 20 * GIB) } else { ("/".to_string(), 10 * GIB) })
+        });
+        assert_eq!(mounts.len(), 2);
+        assert_eq!(mounts["/"], (4650, 10 * GIB));
+        assert_eq!(mounts["/home"], (0, 20 * GIB));
+
+        // Paths without a filesystem are skipped
+        assert!(group_by_mount(&needs, |_| None).is_empty());
+    }
+
+    #[test]
+    fn test_space_status() {
+        assert_eq!(space_status(2 * GIB, GIB, 0), SpaceStatus::Short);
+        assert_eq!(space_status(GIB, GIB, 0), SpaceStatus::Low);
+        assert_eq!(space_status(GIB, GIB + LOW_SPACE_MARGIN - 1, 0), SpaceStatus::Low);
+        assert_eq!(space_status(GIB, GIB + LOW_SPACE_MARGIN, 0), SpaceStatus::Enough);
+        // The AUR build reserve raises the bar on the clone directory's filesystem
+        assert_eq!(space_status(0, LOW_SPACE_MARGIN, AUR_BUILD_RESERVE), SpaceStatus::Low);
+        assert_eq!(space_status(0, LOW_SPACE_MARGIN + AUR_BUILD_RESERVE, AUR_BUILD_RESERVE), SpaceStatus::Enough);
+    }
+}
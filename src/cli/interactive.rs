@@ -20,7 +20,7 @@ pub async fn search_interactive(_config: &mut Config) -> Result<()> {
     }
     
     // Perform search with the query
-    crate::cli::search::search(&query, _config, false, false, false, false, false, false, false).await?;
+    crate::cli::search::search(&query, _config, &[]).await?;
     
     Ok(())
 }
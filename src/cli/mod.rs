@@ -1,4 +1,5 @@
 pub mod args;
+pub mod backend;
 pub mod install;
 pub mod search;
 pub mod interactive;
@@ -1,3 +1,4 @@
+use crate::cli::SourceKind;
 use crate::config::Config;
 use crate::error::{KhazaurError, Result};
 use crate::ui;
@@ -114,11 +115,7 @@ pub async fn check_and_prompt_snapd(config: &mut Config) -> Result<()> {
                 &packages,
                 config,
                 true, // noconfirm
-                true, // only_aur - force AUR since snapd is only in AUR
-                false, // only_repos
-                false, // only_flatpak
-                false, // only_snap
-                false, // only_debian
+                &[SourceKind::Aur], // force AUR, it is only packaged there
                 false, // no_timeout
             )).await;
             
@@ -206,11 +203,7 @@ pub async fn check_and_prompt_debtap(config: &mut Config) -> Result<()> {
                 &packages,
                 config,
                 true, // noconfirm
-                true, // only_aur - force AUR to avoid recursion
-                false, // only_repos
-                false, // only_flatpak
-                false, // only_snap
-                false, // only_debian
+                &[SourceKind::Aur], // force AUR, it is only packaged there
                 false, // no_timeout
             )).await;
             
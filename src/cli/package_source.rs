@@ -2,6 +2,8 @@ use crate::aur::AurPackage;
 use crate::flatpak::FlatpakPackage;
 use crate::snap::SnapPackage;
 use crate::debian::DebianPackage;
+use crate::appimage::AppImagePackage;
+use crate::plugin::PluginPackage;
 use colored::Colorize;
 
 /// Represents a package found in a specific source
@@ -12,6 +14,53 @@ pub struct PackageCandidate {
     pub source: PackageSource,
 }
 
+/// Package source without the package data attached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Repo,
+    Aur,
+    Flatpak,
+    Snap,
+    Debian,
+    AppImage,
+    /// Any external backend plugin; the package names which one
+    Plugin,
+}
+
+impl SourceKind {
+    /// Short lowercase label, matching the `source/package` prefix syntax
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceKind::Repo => "repo",
+            SourceKind::Aur => "aur",
+            SourceKind::Flatpak => "flatpak",
+            SourceKind::Snap => "snap",
+            SourceKind::Debian => "debian",
+            SourceKind::AppImage => "appimage",
+            SourceKind::Plugin => "plugin",
+        }
+    }
+
+    /// Parse a `source/package` prefix; repository names map to `Repo`
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_lowercase().as_str() {
+            "aur" => Some(SourceKind::Aur),
+            "repo" | "core" | "extra" | "multilib" | "community" => Some(SourceKind::Repo),
+            "flatpak" => Some(SourceKind::Flatpak),
+            "snap" => Some(SourceKind::Snap),
+            "debian" => Some(SourceKind::Debian),
+            "appimage" => Some(SourceKind::AppImage),
+            _ => None,
+        }
+    }
+
+    /// Whether packages from this source are managed by pacman
+    pub fn is_pacman(&self) -> bool {
+        matches!(self, SourceKind::Repo | SourceKind::Aur | SourceKind::Debian)
+    }
+}
+
 /// Different sources where a package can be found
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum PackageSource {
@@ -25,9 +74,52 @@ pub enum PackageSource {
     Snap(SnapPackage),
     /// Debian package
     Debian(DebianPackage),
+    /// AppImage from the catalog
+    AppImage(AppImagePackage),
+    /// Package of an external backend plugin
+    Plugin(PluginPackage),
 }
 
 impl PackageSource {
+    /// The source this package comes from
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            PackageSource::Repo(_) => SourceKind::Repo,
+            PackageSource::Aur(_) => SourceKind::Aur,
+            PackageSource::Flatpak(_) => SourceKind::Flatpak,
+            PackageSource::Snap(_) => SourceKind::Snap,
+            PackageSource::Debian(_) => SourceKind::Debian,
+            PackageSource::AppImage(_) => SourceKind::AppImage,
+            PackageSource::Plugin(_) => SourceKind::Plugin,
+        }
+    }
+
+    /// Name the source installs the package by (the app ID for Flatpak)
+    pub fn name(&self) -> &str {
+        match self {
+            PackageSource::Repo(pkg) => &pkg.name,
+            PackageSource::Aur(pkg) => &pkg.name,
+            PackageSource::Flatpak(pkg) => &pkg.app_id,
+            PackageSource::Snap(pkg) => &pkg.name,
+            PackageSource::Debian(pkg) => &pkg.name,
+            PackageSource::AppImage(pkg) => &pkg.name,
+            PackageSource::Plugin(pkg) => &pkg.name,
+        }
+    }
+
+    /// Version the source offers
+    pub fn version(&self) -> &str {
+        match self {
+            PackageSource::Repo(pkg) => &pkg.version,
+            PackageSource::Aur(pkg) => &pkg.version,
+            PackageSource::Flatpak(pkg) => &pkg.version,
+            PackageSource::Snap(pkg) => &pkg.version,
+            PackageSource::Debian(pkg) => &pkg.version,
+            PackageSource::AppImage(pkg) => &pkg.version,
+            PackageSource::Plugin(pkg) => &pkg.version,
+        }
+    }
+
     /// Get the source type as a string
     pub fn source_type(&self) -> &str {
         match self {
@@ -36,6 +128,8 @@ impl PackageSource {
             PackageSource::Flatpak(_) => "Flatpak",
             PackageSource::Snap(_) => "Snap",
             PackageSource::Debian(_) => "Debian",
+            PackageSource::AppImage(_) => "AppImage",
+            PackageSource::Plugin(pkg) => &pkg.source,
         }
     }
     
@@ -48,7 +142,10 @@ impl PackageSource {
             PackageSource::Aur(pkg) => format!("aur/{} {}", pkg.name, pkg.version),
             PackageSource::Flatpak(pkg) => format!("flatpak/{} {}", pkg.name, pkg.version),
             PackageSource::Snap(pkg) => format!("snap/{} {}", pkg.name, pkg.version),
-            PackageSource::Debian(pkg) => format!("debian/{} {}", pkg.name, pkg.version),
+            PackageSource::Debian(pkg) if pkg.suite.is_empty() => format!("debian/{} {}", pkg.name, pkg.version),
+            PackageSource::Debian(pkg) => format!("debian/{} {} ({})", pkg.name, pkg.version, pkg.suite),
+            PackageSource::AppImage(pkg) => format!("appimage/{} {}", pkg.name, pkg.version),
+            PackageSource::Plugin(pkg) => format!("{}/{} {}", pkg.backend, pkg.name, pkg.version),
         }
     }
     
@@ -59,6 +156,8 @@ impl PackageSource {
             PackageSource::Snap(pkg) => Some(&pkg.description),
             PackageSource::Repo(pkg) => Some(&pkg.description),
             PackageSource::Debian(pkg) => Some(&pkg.description),
+            PackageSource::AppImage(pkg) => Some(&pkg.description),
+            PackageSource::Plugin(pkg) => Some(&pkg.description),
         }
     }
 }
@@ -0,0 +1,494 @@
+pub mod schema;
+
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// System-wide configuration, layered under the user's file
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/khazaur/config.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    /// Cache directory for khazaur
+    #[serde(skip)]
+    pub cache_dir: PathBuf,
+    
+    /// Clone directory for PKGBUILDs
+    #[serde(skip)]
+    pub clone_dir: PathBuf,
+    
+    /// Package cache directory
+    #[serde(skip)]
+    pub pkg_dir: PathBuf,
+    
+    /// Whether to use colors in output
+    pub use_color: bool,
+    
+    /// Whether to ask for confirmation before operations
+    pub confirm: bool,
+    
+    /// Whether to review PKGBUILDs before building
+    pub review_pkgbuild: bool,
+    
+    /// Number of concurrent downloads
+    pub concurrent_downloads: usize,
+
+    /// Default text editor for editing PKGBUILDs
+    pub default_editor: Option<String>,
+
+    /// Use git clone instead of tarball download (faster)
+    pub use_git_clone: bool,
+
+    /// Maximum concurrent AUR RPC requests
+    pub max_concurrent_requests: usize,
+
+    /// Delay between requests in milliseconds
+    pub request_delay_ms: u64,
+    
+    /// Show unread Arch Linux news before system upgrades
+    #[serde(default = "default_true")]
+    pub check_news: bool,
+
+    /// News feed to read (URL, file:// URL or local path)
+    #[serde(default = "default_news_url")]
+    pub news_url: String,
+
+    /// Arch security tracker feed (URL, file:// URL or local path)
+    #[serde(default = "default_security_tracker_url")]
+    pub security_tracker_url: String,
+
+    /// Arch Linux Archive base URL, used to find older package versions
+    #[serde(default = "default_archive_url")]
+    pub archive_url: String,
+
+    /// pacman log merged into `khazaur history`
+    #[serde(default = "default_pacman_log")]
+    pub pacman_log: PathBuf,
+
+    /// AUR base URL, for mirrors and local stand-ins
+    #[serde(default = "default_aur_url")]
+    pub aur_url: String,
+
+    /// Proxy for all HTTP requests and git clones (http://, https://, socks5://)
+    #[serde(default)]
+    pub proxy: Option<String>,
+
+    /// Extra PEM certificate bundle to trust
+    #[serde(default)]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Seconds to wait for a connection
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+
+    /// Seconds to wait for data before a request is abandoned
+    #[serde(default = "default_read_timeout_secs")]
+    pub read_timeout_secs: u64,
+
+    /// Retries for failed requests
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+
+    /// Delay before the first retry in milliseconds, doubled for each one after
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+
+    /// Debian-style archives searched for packages
+    #[serde(default = "crate::debian::sources::default_sources")]
+    pub debian_sources: Vec<crate::debian::DebianSource>,
+
+    /// Keyring Debian InRelease signatures are checked against
+    #[serde(default = "default_debian_keyring")]
+    pub debian_keyring: PathBuf,
+
+    /// Which source's package is used when several have it
+    #[serde(default)]
+    pub debian_suite_policy: crate::debian::SuitePolicy,
+
+    /// How .deb files are turned into pacman packages
+    #[serde(default)]
+    pub debian_converter: crate::debian::convert::Converter,
+
+    /// Download Debian Contents indexes so `-F` finds files of Debian packages
+    #[serde(default)]
+    pub debian_contents: bool,
+
+    /// Directory AppImages are installed into (~/Applications when unset)
+    #[serde(default)]
+    pub appimage_dir: Option<PathBuf>,
+
+    /// AppImage catalog searched for apps: a path or URL (~/.config/khazaur/appimages.toml when unset)
+    #[serde(default)]
+    pub appimage_catalog: Option<String>,
+
+    /// Track which optional dependencies user has rejected
+    #[serde(default)]
+    pub rejected_dependencies: RejectedDependencies,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_news_url() -> String {
+    crate::news::DEFAULT_NEWS_URL.to_string()
+}
+
+fn default_security_tracker_url() -> String {
+    crate::security::DEFAULT_TRACKER_URL.to_string()
+}
+
+fn default_archive_url() -> String {
+    "https://archive.archlinux.org".to_string()
+}
+
+pub const DEFAULT_AUR_URL: &str = "https://aur.archlinux.org";
+
+fn default_aur_url() -> String {
+    DEFAULT_AUR_URL.to_string()
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_read_timeout_secs() -> u64 {
+    30
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_debian_keyring() -> PathBuf {
+    PathBuf::from(crate::debian::DEFAULT_KEYRING)
+}
+
+fn default_pacman_log() -> PathBuf {
+    PathBuf::from(crate::pacman::log::DEFAULT_LOG_PATH)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RejectedDependencies {
+    #[serde(default)]
+    pub flatpak: bool,
+    #[serde(default)]
+    pub snapd: bool,
+    #[serde(default)]
+    pub debtap: bool,
+}
+
+impl Config {
+    /// Create a new config with default values
+    pub fn new() -> Result<Self> {
+        let cache_dir = crate::dirs::default_cache_dir()?;
+        
+        let clone_dir = cache_dir.join("clone");
+        let pkg_dir = cache_dir.join("pkg");
+        
+        Ok(Self {
+            cache_dir,
+            clone_dir,
+            pkg_dir,
+            use_color: true,
+            confirm: true,
+            review_pkgbuild: false,
+            concurrent_downloads: 4,
+            default_editor: None,
+            use_git_clone: true,
+            max_concurrent_requests: 10,
+            request_delay_ms: 100,
+            check_news: true,
+            news_url: default_news_url(),
+            security_tracker_url: default_security_tracker_url(),
+            archive_url: default_archive_url(),
+            pacman_log: default_pacman_log(),
+            aur_url: default_aur_url(),
+            proxy: None,
+            ca_bundle: None,
+            connect_timeout_secs: default_connect_timeout_secs(),
+            read_timeout_secs: default_read_timeout_secs(),
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            debian_sources: crate::debian::sources::default_sources(),
+            debian_keyring: default_debian_keyring(),
+            debian_suite_policy: crate::debian::SuitePolicy::default(),
+            debian_converter: crate::debian::convert::Converter::default(),
+            debian_contents: false,
+            appimage_dir: None,
+            appimage_catalog: None,
+            rejected_dependencies: RejectedDependencies::default(),
+        })
+    }
+
+    /// Get the config file path
+    pub fn config_file_path() -> Result<PathBuf> {
+        let config_dir = dirs::config_dir()
+            .ok_or_else(|| KhazaurError::Config("Could not determine config directory".to_string()))?
+            .join("khazaur");
+        
+        Ok(config_dir.join("config.toml"))
+    }
+
+    /// Load the effective configuration: defaults, then /etc/khazaur/config.toml,
+    /// the user's file and KHAZAUR_* environment variables
+    pub fn load() -> Result<Self> {
+        Layers::read()?.resolve()
+    }
+
+    /// Save settings changed at runtime to the user's file. Only values that differ
+    /// from the defaults and system file are written, and environment overrides never are.
+    pub fn save(&self) -> Result<()> {
+        let layers = Layers::read()?;
+        let mut below = defaults_table()?;
+        merge(&mut below, &layers.system);
+        let current = table_of(self)?;
+        let mut user = layers.user;
+
+        for key in schema::KEYS {
+            if schema::PATH_KEYS.contains(&key.name) || std::env::var_os(key.env_var()).is_some() {
+                continue;
+            }
+            match schema::lookup(&current, key.name) {
+                Some(value) => {
+                    if schema::lookup(&user, key.name).is_some() || schema::lookup(&below, key.name) != Some(value) {
+                        schema::insert(&mut user, key.name, value.clone());
+                    }
+                }
+                None => {
+                    schema::remove(&mut user, key.name);
+                }
+            }
+        }
+
+        write_user_table(&user)
+    }
+
+    /// Effective value of a key, including the resolved directories
+    pub fn value_of(&self, name: &str) -> Option<toml::Value> {
+        let path = |p: &Path| Some(toml::Value::String(p.display().to_string()));
+        match name {
+            "cache_dir" => path(&self.cache_dir),
+            "clone_dir" => path(&self.clone_dir),
+            "pkg_dir" => path(&self.pkg_dir),
+            _ => schema::lookup(&table_of(self).ok()?, name).cloned(),
+        }
+    }
+
+    /// Ensure all directories exist
+    pub fn ensure_dirs(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.cache_dir)?;
+        std::fs::create_dir_all(&self.clone_dir)?;
+        std::fs::create_dir_all(&self.pkg_dir)?;
+        Ok(())
+    }
+}
+
+/// Where a configuration value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    System,
+    User,
+    Env,
+}
+
+impl Origin {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Origin::Default => "default",
+            Origin::System => "system",
+            Origin::User => "user",
+            Origin::Env => "env",
+        }
+    }
+}
+
+/// Configuration sources above the defaults, lowest priority first
+pub struct Layers {
+    pub system: toml::Table,
+    pub user: toml::Table,
+    pub env: toml::Table,
+}
+
+impl Layers {
+    /// Read and validate the system file, the user file and the environment
+    pub fn read() -> Result<Self> {
+        Self::read_with_user(&Config::config_file_path()?)
+    }
+
+    /// Like `read`, with another file in place of the user's, such as a draft
+    /// of it. The user's own file isn't read, so it may be broken.
+    pub fn read_with_user(user: &Path) -> Result<Self> {
+        let mut env = toml::Table::new();
+        for key in schema::KEYS {
+            if let Ok(text) = std::env::var(key.env_var()) {
+                let value = key.parse(&text).map_err(|e| match e {
+                    KhazaurError::Config(msg) => KhazaurError::Config(format!("{} (from {})", msg, key.env_var())),
+                    other => other,
+                })?;
+                schema::insert(&mut env, key.name, value);
+            }
+        }
+
+        let layers = Self {
+            system: read_table(Path::new(SYSTEM_CONFIG_PATH))?,
+            user: read_table(user)?,
+            env,
+        };
+        schema::check_table(&layers.system, SYSTEM_CONFIG_PATH)?;
+        schema::check_table(&layers.user, &user.display().to_string())?;
+        Ok(layers)
+    }
+
+    /// Highest priority layer that sets a key
+    pub fn origin(&self, name: &str) -> Origin {
+        if schema::lookup(&self.env, name).is_some() {
+            Origin::Env
+        } else if schema::lookup(&self.user, name).is_some() {
+            Origin::User
+        } else if schema::lookup(&self.system, name).is_some() {
+            Origin::System
+        } else {
+            Origin::Default
+        }
+    }
+
+    /// Merge the layers over the defaults into a configuration
+    pub fn resolve(&self) -> Result<Config> {
+        let mut merged = defaults_table()?;
+        merge(&mut merged, &self.system);
+        merge(&mut merged, &self.user);
+        merge(&mut merged, &self.env);
+
+        let mut config: Config = toml::Value::Table(merged.clone())
+            .try_into()
+            .map_err(|e| KhazaurError::Config(format!("Failed to parse config: {}", e)))?;
+
+        // Directories aren't serialized with the rest: clone_dir and pkg_dir
+        // follow cache_dir unless they are set themselves
+        let path = |name: &str| schema::lookup(&merged, name).and_then(|v| v.as_str()).map(expand_home);
+        config.cache_dir = match path("cache_dir") {
+            Some(dir) => dir,
+            None => crate::dirs::default_cache_dir()?,
+        };
+        config.clone_dir = path("clone_dir").unwrap_or_else(|| config.cache_dir.join("clone"));
+        config.pkg_dir = path("pkg_dir").unwrap_or_else(|| config.cache_dir.join("pkg"));
+        config.pacman_log = expand_home(&config.pacman_log.to_string_lossy());
+        config.debian_keyring = expand_home(&config.debian_keyring.to_string_lossy());
+        config.ca_bundle = config.ca_bundle.as_deref().map(|p| expand_home(&p.to_string_lossy()));
+        config.appimage_dir = config.appimage_dir.as_deref().map(|p| expand_home(&p.to_string_lossy()));
+        config.appimage_catalog = config.appimage_catalog.as_deref().map(|p| expand_home(p).to_string_lossy().into_owned());
+
+        crate::dirs::init(&config.cache_dir, &config.clone_dir, &config.pkg_dir);
+        crate::net::init(crate::net::Settings::from_config(&config));
+        Ok(config)
+    }
+}
+
+/// Replace the user's configuration file with a table
+pub fn write_user_table(table: &toml::Table) -> Result<()> {
+    let config_path = Config::config_file_path()?;
+
+    // Ensure config directory exists
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let toml_string = toml::to_string_pretty(table).map_err(|e| {
+        KhazaurError::Config(format!("Failed to serialize config: {}", e))
+    })?;
+
+    fs::write(&config_path, toml_string)?;
+    Ok(())
+}
+
+/// Parse a TOML file into a table, empty when the file doesn't exist
+pub fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    let contents = fs::read_to_string(path)?;
+    contents.parse().map_err(|e| {
+        KhazaurError::Config(format!("Failed to parse {}: {}", path.display(), e))
+    })
+}
+
+fn defaults_table() -> Result<toml::Table> {
+    table_of(&Config::new()?)
+}
+
+fn table_of(config: &Config) -> Result<toml::Table> {
+    match toml::Value::try_from(config) {
+        Ok(toml::Value::Table(table)) => Ok(table),
+        Ok(_) => Err(KhazaurError::Config("Config did not serialize to a table".to_string())),
+        Err(e) => Err(KhazaurError::Config(format!("Failed to serialize config: {}", e))),
+    }
+}
+
+/// Overlay one table on another, merging nested tables key by key
+fn merge(base: &mut toml::Table, over: &toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(inner)), toml::Value::Table(over_inner)) => merge(inner, over_inner),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Expand a leading `~/` to the home directory
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new().expect("Failed to create default config")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_layers() {
+        let mut base: toml::Table = "confirm = true\n[rejected_dependencies]\nflatpak = false\nsnapd = false\n".parse().unwrap();
+        let over: toml::Table = "confirm = false\n[rejected_dependencies]\nsnapd = true\n".parse().unwrap();
+        merge(&mut base, &over);
+
+        assert_eq!(schema::lookup(&base, "confirm"), Some(&toml::Value::Boolean(false)));
+        assert_eq!(schema::lookup(&base, "rejected_dependencies.flatpak"), Some(&toml::Value::Boolean(false)));
+        assert_eq!(schema::lookup(&base, "rejected_dependencies.snapd"), Some(&toml::Value::Boolean(true)));
+    }
+
+    #[test]
+    fn test_settings_from_config() {
+        let user: toml::Table = "aur_url = \"https://aur.example.org/\"\nread_timeout_secs = 5\nproxy = \"\"\n".parse().unwrap();
+        let mut merged = defaults_table().unwrap();
+        merge(&mut merged, &user);
+        let config: Config = toml::Value::Table(merged).try_into().unwrap();
+
+        let settings = crate::net::Settings::from_config(&config);
+        // Overridden
+        assert_eq!(settings.aur_url, "https://aur.example.org");
+        assert_eq!(settings.read_timeout, std::time::Duration::from_secs(5));
+        assert_eq!(settings.proxy, None);
+        // Defaults
+        assert_eq!(settings.connect_timeout, std::time::Duration::from_secs(default_connect_timeout_secs()));
+        assert_eq!(settings.retry.max_retries, default_max_retries());
+        assert_eq!(settings.retry.initial_backoff_ms, default_retry_backoff_ms());
+        assert_eq!(settings.ca_bundle, None);
+    }
+}
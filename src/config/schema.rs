@@ -0,0 +1,299 @@
+use crate::error::{KhazaurError, Result};
+use std::sync::Mutex;
+use toml::Value;
+
+/// Kind of value a configuration key holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Bool,
+    Integer { min: i64, max: i64 },
+    /// Free text that may be left unset
+    OptionalText,
+    /// HTTP(S) URL
+    Url,
+    /// HTTP(S) URL, file:// URL or local path
+    Source,
+    /// Filesystem path, `~/` is expanded
+    Path,
+    /// Optional http(s):// or socks5(h):// URL
+    Proxy,
+    /// One of a fixed set of words
+    Choice(&'static [&'static str]),
+    /// List of `{ url, suite, components, priority, name }` tables
+    DebianSources,
+}
+
+impl ValueType {
+    pub fn label(&self) -> String {
+        match self {
+            ValueType::Bool => "boolean".to_string(),
+            ValueType::Integer { min, max } => format!("integer ({}-{})", min, max),
+            ValueType::OptionalText => "string (optional)".to_string(),
+            ValueType::Url => "URL".to_string(),
+            ValueType::Source => "URL or path".to_string(),
+            ValueType::Path => "path".to_string(),
+            ValueType::Proxy => "proxy URL (optional)".to_string(),
+            ValueType::Choice(choices) => choices.join(" | "),
+            ValueType::DebianSources => "list of sources".to_string(),
+        }
+    }
+}
+
+/// One configuration key. Nested keys use dots: `rejected_dependencies.flatpak`
+#[derive(Debug)]
+pub struct Key {
+    pub name: &'static str,
+    pub value_type: ValueType,
+    pub description: &'static str,
+}
+
+pub const KEYS: &[Key] = &[
+    Key { name: "cache_dir", value_type: ValueType::Path, description: "Cache directory for khazaur (default ~/.cache/khazaur)" },
+    Key { name: "clone_dir", value_type: ValueType::Path, description: "Directory PKGBUILDs are cloned and built in (default <cache_dir>/clone)" },
+    Key { name: "pkg_dir", value_type: ValueType::Path, description: "Package cache directory (default <cache_dir>/pkg)" },
+    Key { name: "use_color", value_type: ValueType::Bool, description: "Whether to use colors in output" },
+    Key { name: "confirm", value_type: ValueType::Bool, description: "Whether to ask for confirmation before operations" },
+    Key { name: "review_pkgbuild", value_type: ValueType::Bool, description: "Whether to review PKGBUILDs before building" },
+    Key { name: "concurrent_downloads", value_type: ValueType::Integer { min: 1, max: 64 }, description: "Number of Debian packages downloaded at the same time" },
+    Key { name: "default_editor", value_type: ValueType::OptionalText, description: "Text editor for PKGBUILDs and `config edit`" },
+    Key { name: "use_git_clone", value_type: ValueType::Bool, description: "Use git clone instead of tarball download" },
+    Key { name: "max_concurrent_requests", value_type: ValueType::Integer { min: 1, max: 100 }, description: "Maximum concurrent AUR RPC requests" },
+    Key { name: "request_delay_ms", value_type: ValueType::Integer { min: 0, max: 60_000 }, description: "Delay between AUR requests in milliseconds" },
+    Key { name: "check_news", value_type: ValueType::Bool, description: "Show unread Arch Linux news before system upgrades" },
+    Key { name: "news_url", value_type: ValueType::Source, description: "News feed to read" },
+    Key { name: "security_tracker_url", value_type: ValueType::Source, description: "Arch security tracker feed" },
+    Key { name: "archive_url", value_type: ValueType::Url, description: "Arch Linux Archive base URL, used to find older package versions" },
+    Key { name: "pacman_log", value_type: ValueType::Path, description: "pacman log merged into `khazaur history`" },
+    Key { name: "aur_url", value_type: ValueType::Url, description: "AUR base URL, for mirrors and local stand-ins" },
+    Key { name: "proxy", value_type: ValueType::Proxy, description: "Proxy for HTTP requests and git clones (default: the *_proxy environment variables)" },
+    Key { name: "ca_bundle", value_type: ValueType::OptionalText, description: "Extra PEM certificate bundle to trust for HTTPS" },
+    Key { name: "connect_timeout_secs", value_type: ValueType::Integer { min: 1, max: 600 }, description: "Seconds to wait for a connection" },
+    Key { name: "read_timeout_secs", value_type: ValueType::Integer { min: 1, max: 3600 }, description: "Seconds to wait for data before a request is abandoned" },
+    Key { name: "max_retries", value_type: ValueType::Integer { min: 0, max: 10 }, description: "Retries for failed requests" },
+    Key { name: "retry_backoff_ms", value_type: ValueType::Integer { min: 0, max: 60_000 }, description: "Delay before the first retry in milliseconds, doubled for each one after" },
+    Key { name: "debian_sources", value_type: ValueType::DebianSources, description: "Debian-style archives to search: [{ url, suite, components, priority, name }]" },
+    Key { name: "debian_keyring", value_type: ValueType::Path, description: "Keyring Debian InRelease signatures are checked against" },
+    Key { name: "debian_suite_policy", value_type: ValueType::Choice(crate::debian::SuitePolicy::NAMES), description: "Which source wins when several have a package: the highest priority, or the newest version" },
+    Key { name: "debian_converter", value_type: ValueType::Choice(crate::debian::convert::Converter::NAMES), description: "How .deb files become pacman packages: the built-in converter, falling back to debtap, or debtap only" },
+    Key { name: "debian_contents", value_type: ValueType::Bool, description: "Download Debian Contents indexes (tens of MB per suite) so `-F` searches Debian files too" },
+    Key { name: "appimage_dir", value_type: ValueType::OptionalText, description: "Directory AppImages are installed into (default ~/Applications)" },
+    Key { name: "appimage_catalog", value_type: ValueType::OptionalText, description: "AppImage catalog to search, a path or URL (default ~/.config/khazaur/appimages.toml)" },
+    Key { name: "rejected_dependencies.flatpak", value_type: ValueType::Bool, description: "Never offer to install flatpak" },
+    Key { name: "rejected_dependencies.snapd", value_type: ValueType::Bool, description: "Never offer to install snapd" },
+    Key { name: "rejected_dependencies.debtap", value_type: ValueType::Bool, description: "Never offer to install debtap" },
+];
+
+/// Directory keys resolved by hand, since their defaults depend on each other
+pub const PATH_KEYS: [&str; 3] = ["cache_dir", "clone_dir", "pkg_dir"];
+
+pub fn find(name: &str) -> Result<&'static Key> {
+    KEYS.iter()
+        .find(|key| key.name == name)
+        .ok_or_else(|| KhazaurError::Config(format!("Unknown config key: {}", name)))
+}
+
+impl Key {
+    /// Environment variable that overrides the key: KHAZAUR_REVIEW_PKGBUILD,
+    /// KHAZAUR_REJECTED_DEPENDENCIES__FLATPAK
+    pub fn env_var(&self) -> String {
+        format!("KHAZAUR_{}", self.name.to_uppercase().replace('.', "__"))
+    }
+
+    /// Parse a value given on the command line or in the environment
+    pub fn parse(&self, text: &str) -> Result<Value> {
+        let text = text.trim();
+        let value = match self.value_type {
+            ValueType::Bool => match text.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Value::Boolean(true),
+                "false" | "no" | "off" | "0" => Value::Boolean(false),
+                _ => return Err(self.invalid(text)),
+            },
+            ValueType::Integer { .. } => Value::Integer(text.parse().map_err(|_| self.invalid(text))?),
+            // Written as a TOML inline array: [{ url = "...", suite = "trixie" }]
+            ValueType::DebianSources => format!("value = {}", text)
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut table| table.remove("value"))
+                .ok_or_else(|| self.invalid(text))?,
+            _ => Value::String(text.to_string()),
+        };
+
+        match self.problem(&value) {
+            Some(problem) => Err(KhazaurError::Config(problem)),
+            None => Ok(value),
+        }
+    }
+
+    /// What is wrong with a value, if anything
+    fn problem(&self, value: &Value) -> Option<String> {
+        let valid = match (self.value_type, value) {
+            (ValueType::Bool, Value::Boolean(_)) => true,
+            (ValueType::Integer { min, max }, Value::Integer(n)) => (min..=max).contains(n),
+            (ValueType::OptionalText, Value::String(_)) => true,
+            (ValueType::Url, Value::String(s)) => s.starts_with("http://") || s.starts_with("https://"),
+            (ValueType::Source, Value::String(s)) => !s.is_empty(),
+            (ValueType::Path, Value::String(s)) => !s.is_empty(),
+            (ValueType::Choice(choices), Value::String(s)) => choices.contains(&s.as_str()),
+            (ValueType::DebianSources, Value::Array(sources)) => !sources.is_empty() && sources.iter().all(valid_debian_source),
+            (ValueType::Proxy, Value::String(s)) => ["http://", "https://", "socks5://", "socks5h://"]
+                .iter()
+                .any(|scheme| s.starts_with(scheme)),
+            _ => false,
+        };
+
+        (!valid).then(|| self.describe_invalid(&value.to_string()))
+    }
+
+    fn invalid(&self, text: &str) -> KhazaurError {
+        KhazaurError::Config(self.describe_invalid(text))
+    }
+
+    fn describe_invalid(&self, text: &str) -> String {
+        format!("Invalid value {} for '{}': expected {}", text, self.name, self.value_type.label())
+    }
+}
+
+fn valid_debian_source(value: &Value) -> bool {
+    let Some(table) = value.as_table() else {
+        return false;
+    };
+    let text = |name: &str| table.get(name).and_then(Value::as_str).is_some_and(|s| !s.is_empty());
+    let url = table.get("url").and_then(Value::as_str).unwrap_or_default();
+
+    (url.starts_with("http://") || url.starts_with("https://"))
+        && text("suite")
+        && table.get("components").is_none_or(|components| {
+            components.as_array().is_some_and(|list| !list.is_empty() && list.iter().all(|c| c.is_str()))
+        })
+        && table.get("priority").is_none_or(Value::is_integer)
+        && table.get("name").is_none_or(|_| text("name"))
+        && table.get("keyring").is_none_or(|_| text("keyring"))
+        && table.get("trusted").is_none_or(Value::is_bool)
+}
+
+/// Look up a dotted key in a table
+pub fn lookup<'a>(table: &'a toml::Table, name: &str) -> Option<&'a Value> {
+    let mut parts = name.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Set a dotted key in a table, creating intermediate tables
+pub fn insert(table: &mut toml::Table, name: &str, value: Value) {
+    match name.split_once('.') {
+        Some((head, rest)) => {
+            let entry = table
+                .entry(head)
+                .or_insert_with(|| Value::Table(toml::Table::new()));
+            if !entry.is_table() {
+                *entry = Value::Table(toml::Table::new());
+            }
+            if let Value::Table(inner) = entry {
+                insert(inner, rest, value);
+            }
+        }
+        None => {
+            table.insert(name.to_string(), value);
+        }
+    }
+}
+
+/// Remove a dotted key from a table, dropping tables left empty
+pub fn remove(table: &mut toml::Table, name: &str) -> Option<Value> {
+    match name.split_once('.') {
+        Some((head, rest)) => {
+            let inner = table.get_mut(head)?.as_table_mut()?;
+            let removed = remove(inner, rest);
+            if inner.is_empty() {
+                table.remove(head);
+            }
+            removed
+        }
+        None => table.remove(name),
+    }
+}
+
+static WARNED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Dotted names in a table that aren't configuration keys
+fn unknown_keys(table: &toml::Table, prefix: &str) -> Vec<String> {
+    let mut unknown = Vec::new();
+    for (name, value) in table {
+        let name = format!("{}{}", prefix, name);
+        if KEYS.iter().any(|k| k.name == name) {
+            continue;
+        }
+        match value {
+            Value::Table(inner) if KEYS.iter().any(|k| k.name.starts_with(&format!("{}.", name))) => {
+                unknown.extend(unknown_keys(inner, &format!("{}.", name)));
+            }
+            _ => unknown.push(name),
+        }
+    }
+    unknown
+}
+
+/// Validate every known key in a table; unknown keys are reported but allowed
+pub fn check_table(table: &toml::Table, origin: &str) -> Result<()> {
+    for key in KEYS {
+        if let Some(problem) = lookup(table, key.name).and_then(|value| key.problem(value)) {
+            return Err(KhazaurError::Config(format!("{} in {}", problem, origin)));
+        }
+    }
+
+    for name in unknown_keys(table, "") {
+        // The configuration is loaded more than once per run, warn only the first time
+        let message = format!("Unknown config key '{}' in {}", name, origin);
+        let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+        if !warned.contains(&message) {
+            tracing::warn!("{}", message);
+            warned.push(message);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_check() {
+        let key = find("concurrent_downloads").unwrap();
+        assert_eq!(key.parse("8").unwrap(), Value::Integer(8));
+        assert!(key.parse("0").is_err());
+        assert!(key.parse("many").is_err());
+
+        let key = find("rejected_dependencies.snapd").unwrap();
+        assert_eq!(key.parse("yes").unwrap(), Value::Boolean(true));
+        assert_eq!(key.env_var(), "KHAZAUR_REJECTED_DEPENDENCIES__SNAPD");
+
+        assert!(find("archive_url").unwrap().parse("ftp://example.org").is_err());
+        assert!(find("debian_suite_policy").unwrap().parse("oldest").is_err());
+
+        let key = find("debian_sources").unwrap();
+        assert!(key.parse(r#"[{ url = "http://deb.debian.org/debian", suite = "trixie", components = ["main", "contrib"] }]"#).is_ok());
+        assert!(key.parse(r#"[{ url = "http://deb.debian.org/debian" }]"#).is_err());
+        assert!(find("no_such_key").is_err());
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        let mut table = toml::Table::new();
+        insert(&mut table, "rejected_dependencies.flatpak", Value::Boolean(true));
+        assert_eq!(lookup(&table, "rejected_dependencies.flatpak"), Some(&Value::Boolean(true)));
+
+        insert(&mut table, "rejected_dependencies.clone_dir", Value::Boolean(true));
+        insert(&mut table, "colour", Value::Boolean(true));
+        assert_eq!(unknown_keys(&table, ""), vec!["colour", "rejected_dependencies.clone_dir"]);
+
+        remove(&mut table, "rejected_dependencies.flatpak");
+        remove(&mut table, "rejected_dependencies.clone_dir");
+        remove(&mut table, "colour");
+        assert!(table.is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+use super::{DebianPackage, DebianSource};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the layout of the cache or of DebianPackage changes
+const FORMAT_VERSION: u32 = 1;
+
+/// One parsed Packages index, stored next to the Packages.gz it was built from,
+/// with a trigram index over package names and a word index over descriptions
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IndexCache {
+    format: u32,
+    /// The Packages.gz and source settings the cache was built from
+    stamp: String,
+    packages: Vec<DebianPackage>,
+    /// Three-character substrings of lowercase names -> positions in `packages`
+    name_grams: HashMap<String, Vec<u32>>,
+    /// Lowercase description words -> positions in `packages`
+    words: HashMap<String, Vec<u32>>,
+}
+
+/// Cache file of an index
+pub fn cache_path(index_file: &Path) -> PathBuf {
+    index_file.with_extension("idx")
+}
+
+/// Identifies an index file and the source settings copied into its packages;
+/// the cache is rebuilt when any of them changes
+pub fn stamp(index_file: &Path, source: &DebianSource, component: &str) -> Option<String> {
+    let metadata = std::fs::metadata(index_file).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_nanos();
+    Some(format!(
+        "{} {} {} {} {} {}",
+        metadata.len(),
+        modified,
+        source.base_url(),
+        source.label(),
+        component,
+        source.priority
+    ))
+}
+
+impl IndexCache {
+    pub fn build(packages: Vec<DebianPackage>, stamp: String) -> Self {
+        let mut name_grams: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut words: HashMap<String, Vec<u32>> = HashMap::new();
+
+        for (position, pkg) in packages.iter().enumerate() {
+            let position = position as u32;
+            for gram in grams(&pkg.name.to_lowercase()) {
+                name_grams.entry(gram).or_default().push(position);
+            }
+            for word in tokens(&pkg.description) {
+                words.entry(word).or_default().push(position);
+            }
+        }
+
+        Self {
+            format: FORMAT_VERSION,
+            stamp,
+            packages,
+            name_grams,
+            words,
+        }
+    }
+
+    /// Read a cache, None when it is missing, unreadable or was built from something else
+    pub fn load(path: &Path, stamp: &str) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        let cache: Self = rmp_serde::from_slice(&bytes).ok()?;
+        (cache.format == FORMAT_VERSION && cache.stamp == stamp).then_some(cache)
+    }
+
+    /// Write the cache, replacing any previous one at once so readers never see half of it
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let bytes = rmp_serde::to_vec(self).map_err(std::io::Error::other)?;
+        let partial = path.with_extension("idx.part");
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, path)
+    }
+
+    pub fn into_packages(self) -> Vec<DebianPackage> {
+        self.packages
+    }
+
+    /// Packages whose name contains the query, ignoring case
+    pub fn search_names(&self, query: &str) -> Vec<&DebianPackage> {
+        self.resolve(self.name_matches(query))
+    }
+
+    /// Packages whose name contains the query or whose description has all of its words
+    pub fn search(&self, query: &str) -> Vec<&DebianPackage> {
+        let mut positions = self.name_matches(query);
+        let query_words = tokens(query);
+        if !query_words.is_empty() {
+            let lists: Vec<&[u32]> = query_words
+                .iter()
+                .map(|word| self.words.get(word).map_or(&[][..], Vec::as_slice))
+                .collect();
+            let by_name: HashSet<u32> = positions.iter().copied().collect();
+            positions.extend(intersect(&lists).into_iter().filter(|position| !by_name.contains(position)));
+        }
+
+        self.resolve(positions)
+    }
+
+    fn name_matches(&self, query: &str) -> Vec<u32> {
+        let query = query.to_lowercase();
+        let query_grams = grams(&query);
+
+        // Queries shorter than a trigram are rare enough to scan for
+        let candidates: Vec<u32> = if query_grams.is_empty() {
+            (0..self.packages.len() as u32).collect()
+        } else {
+            let lists: Vec<&[u32]> = query_grams
+                .iter()
+                .map(|gram| self.name_grams.get(gram).map_or(&[][..], Vec::as_slice))
+                .collect();
+            intersect(&lists)
+        };
+
+        // Trigrams can match out of order, so check the whole query
+        candidates
+            .into_iter()
+            .filter(|&position| self.packages[position as usize].name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    fn resolve(&self, positions: Vec<u32>) -> Vec<&DebianPackage> {
+        positions.into_iter().map(|position| &self.packages[position as usize]).collect()
+    }
+}
+
+/// Distinct three-character substrings, none for strings shorter than that
+fn grams(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut grams: Vec<String> = chars.windows(3).map(|window| window.iter().collect()).collect();
+    grams.sort();
+    grams.dedup();
+    grams
+}
+
+/// Distinct lowercase words of two or more letters or digits
+fn tokens(text: &str) -> Vec<String> {
+    let mut words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 2)
+        .map(str::to_lowercase)
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+/// Positions present in every sorted list
+fn intersect(lists: &[&[u32]]) -> Vec<u32> {
+    let Some(shortest) = lists.iter().min_by_key(|list| list.len()) else {
+        return Vec::new();
+    };
+
+    shortest
+        .iter()
+        .copied()
+        .filter(|position| lists.iter().all(|list| list.binary_search(position).is_ok()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, description: &str) -> DebianPackage {
+        DebianPackage {
+            name: name.to_string(),
+            description: description.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_search() {
+        let cache = IndexCache::build(
+            vec![
+                package("htop", "interactive processes viewer"),
+                package("btop", "Modern and colorful command line resource monitor"),
+                package("libhtop-dev", "development files"),
+                package("glances", "curses-based monitoring tool"),
+            ],
+            "stamp".to_string(),
+        );
+
+        let names = |found: Vec<&DebianPackage>| found.iter().map(|pkg| pkg.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(cache.search_names("HTOP")), vec!["htop", "libhtop-dev"]);
+        assert_eq!(names(cache.search_names("op")), vec!["htop", "btop", "libhtop-dev"]);
+        assert_eq!(names(cache.search("monitor")), vec!["btop"]);
+        assert_eq!(names(cache.search("btop")), vec!["btop"]);
+        assert_eq!(names(cache.search("resource monitor")), vec!["btop"]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Packages.idx");
+        cache.save(&path).unwrap();
+        assert_eq!(IndexCache::load(&path, "stamp").unwrap().packages.len(), 4);
+        assert!(IndexCache::load(&path, "other").is_none());
+    }
+}
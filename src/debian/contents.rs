@@ -0,0 +1,224 @@
+use super::{DebianSource, ReleaseHashes};
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use flate2::read::GzDecoder;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+/// A file of a Debian package, from a Contents index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentsMatch {
+    /// Path the file is installed to on Arch, without the leading slash
+    pub path: String,
+    pub package: String,
+    /// Section of the package, prefixed by its component outside main (`contrib/utils`)
+    pub section: String,
+    /// Label of the source listing the package
+    pub suite: String,
+}
+
+/// Contents indexes of a component: the system architecture's, then the one of
+/// architecture-independent packages, which newer archives list separately
+fn architectures() -> [String; 2] {
+    [super::debian_arch(), "all".to_string()]
+}
+
+fn contents_cache_file(source: &DebianSource, component: &str, arch: &str) -> Result<PathBuf> {
+    Ok(crate::dirs::cache_dir()?.join("debian").join(source.contents_cache_name(component, arch)))
+}
+
+/// Download the Contents indexes of every configured source that are missing or
+/// older than a day, checked against the Release hashes like the package indexes.
+/// A source that can't be fetched is skipped with a warning; a failed check always fails.
+pub async fn update(config: &Config, show_progress: bool) -> Result<()> {
+    'sources: for source in &config.debian_sources {
+        let mut release: Option<ReleaseHashes> = None;
+
+        for component in &source.components {
+            for arch in architectures() {
+                let cache_file = contents_cache_file(source, component, &arch)?;
+                if !super::is_stale(&cache_file) {
+                    continue;
+                }
+
+                if release.is_none() && !source.trusted {
+                    let keyring = source.keyring.as_deref().unwrap_or(&config.debian_keyring);
+                    match super::fetch_release(source, keyring).await {
+                        Ok(hashes) => release = Some(hashes),
+                        Err(e @ KhazaurError::Verification(_)) => return Err(e),
+                        Err(e) => {
+                            tracing::warn!("Skipping Debian Contents of {}: {}", source.label(), e);
+                            continue 'sources;
+                        }
+                    }
+                }
+
+                let path = DebianSource::contents_path(component, &arch);
+                // Older archives have no separate index for architecture-independent packages
+                if arch == "all" && release.as_ref().is_some_and(|hashes| !hashes.contains_key(&path)) {
+                    continue;
+                }
+
+                let message = format!("Updating Debian file list ({}/{} {})...", source.label(), component, arch);
+                let downloaded = super::download_verified(
+                    source,
+                    &path,
+                    &cache_file,
+                    release.as_ref(),
+                    show_progress.then_some(message.as_str()),
+                )
+                .await;
+
+                match downloaded {
+                    Ok(()) => {}
+                    Err(e @ KhazaurError::Verification(_)) => return Err(e),
+                    Err(_) if arch == "all" && source.trusted => {}
+                    Err(e) => tracing::warn!("Skipping Debian Contents {} of {}: {}", path, source.label(), e),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether any configured source has a Contents index to search
+pub fn available(config: &Config) -> bool {
+    config.debian_sources.iter().any(|source| {
+        source.components.iter().any(|component| {
+            contents_cache_file(source, component, &super::debian_arch()).is_ok_and(|file| file.exists())
+        })
+    })
+}
+
+/// Files of Debian packages matching `query` in the downloaded Contents indexes.
+/// A query with a slash is a path (`/usr/bin/bar`), anything else a file name (`libfoo.so.3`),
+/// both compared as installed on Arch, with /bin, /sbin and /lib merged into /usr.
+pub fn search(query: &str, config: &Config) -> Result<Vec<ContentsMatch>> {
+    let query = Query::new(query);
+    let mut matches = Vec::new();
+
+    for source in &config.debian_sources {
+        for component in &source.components {
+            for arch in architectures() {
+                let cache_file = contents_cache_file(source, component, &arch)?;
+                if !cache_file.exists() {
+                    continue;
+                }
+
+                let reader = BufReader::new(GzDecoder::new(std::fs::File::open(&cache_file)?));
+                matches.extend(search_index(reader, &query, source.label())?);
+            }
+        }
+    }
+
+    Ok(matches)
+}
+
+/// What a lookup compares entries against
+struct Query {
+    file_name: String,
+    /// Whole path as installed on Arch, for queries with a slash
+    path: Option<String>,
+}
+
+impl Query {
+    fn new(query: &str) -> Self {
+        let path = query.contains('/').then(|| super::convert::arch_path(query)).flatten();
+        let file_name = query.rsplit('/').next().unwrap_or(query).to_string();
+        Self { file_name, path }
+    }
+
+    /// The Arch path of a Contents entry when it matches
+    fn matches(&self, entry: &str) -> Option<String> {
+        // Compare file names first, the full path of every line would be costly
+        if entry.rsplit('/').next() != Some(self.file_name.as_str()) {
+            return None;
+        }
+        let path = super::convert::arch_path(entry)?;
+        match &self.path {
+            Some(wanted) if *wanted != path => None,
+            _ => Some(path),
+        }
+    }
+}
+
+fn search_index(mut reader: impl BufRead, query: &Query, suite: &str) -> Result<Vec<ContentsMatch>> {
+    let mut matches = Vec::new();
+    let mut line = Vec::new();
+
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        // Paths are bytes; ones that aren't UTF-8 can't be asked for anyway
+        if let Ok(text) = std::str::from_utf8(&line)
+            && let Some((entry, locations)) = parse_line(text)
+            && let Some(path) = query.matches(entry)
+        {
+            for location in locations.split(',') {
+                let (section, package) = location.rsplit_once('/').unwrap_or(("", location));
+                matches.push(ContentsMatch {
+                    path: path.clone(),
+                    package: package.to_string(),
+                    section: section.to_string(),
+                    suite: suite.to_string(),
+                });
+            }
+        }
+        line.clear();
+    }
+
+    Ok(matches)
+}
+
+/// Split a `path   section/package,section/package` line. Header lines of older
+/// indexes have no `section/package` column and are skipped.
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let (entry, locations) = line.trim_end().rsplit_once(|c: char| c.is_whitespace())?;
+    let entry = entry.trim_end();
+    (!entry.is_empty() && locations.contains('/')).then_some((entry, locations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENTS: &str = "\
+This file maps each file available in the Debian GNU/Linux system to
+the package from which it originates.
+
+FILE                                                    LOCATION
+bin/bash                                                shells/bash
+usr/bin/bar                                             utils/bar,contrib/utils/bar-extra
+usr/lib/x86_64-linux-gnu/libfoo.so.3                    libs/libfoo3
+usr/share/doc/my file/libfoo.so.3                       doc/foo-doc
+";
+
+    fn search(query: &str) -> Vec<(String, String, String)> {
+        search_index(CONTENTS.as_bytes(), &Query::new(query), "trixie")
+            .unwrap()
+            .into_iter()
+            .map(|found| (found.path, found.section, found.package))
+            .collect()
+    }
+
+    #[test]
+    fn test_search_index() {
+        let owned = |path: &str, section: &str, package: &str| (path.to_string(), section.to_string(), package.to_string());
+
+        assert_eq!(search("/usr/bin/bash"), vec![owned("usr/bin/bash", "shells", "bash")]);
+        assert_eq!(search("bash"), vec![owned("usr/bin/bash", "shells", "bash")]);
+        assert_eq!(
+            search("usr/bin/bar"),
+            vec![owned("usr/bin/bar", "utils", "bar"), owned("usr/bin/bar", "contrib/utils", "bar-extra")]
+        );
+        assert_eq!(
+            search("libfoo.so.3"),
+            vec![
+                owned("usr/lib/libfoo.so.3", "libs", "libfoo3"),
+                owned("usr/share/doc/my file/libfoo.so.3", "doc", "foo-doc"),
+            ]
+        );
+        assert_eq!(search("/usr/lib/libfoo.so.3"), vec![owned("usr/lib/libfoo.so.3", "libs", "libfoo3")]);
+        assert_eq!(search("/usr/lib/x86_64-linux-gnu/libfoo.so.3"), search("/usr/lib/libfoo.so.3"));
+        assert!(search("LOCATION").is_empty());
+    }
+}
@@ -0,0 +1,830 @@
+use super::DebianPackage;
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// How .deb files are turned into pacman packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Converter {
+    /// khazaur's own converter, falling back to debtap when it fails and debtap is installed
+    #[default]
+    Builtin,
+    /// Always use debtap
+    Debtap,
+}
+
+impl Converter {
+    pub const NAMES: &'static [&'static str] = &["builtin", "debtap"];
+}
+
+/// Directories that are symlinks into /usr on Arch, and where their contents go
+const MERGED_DIRS: &[(&str, &str)] = &[
+    ("bin", "usr/bin"),
+    ("sbin", "usr/bin"),
+    ("usr/sbin", "usr/bin"),
+    ("lib", "usr/lib"),
+    ("lib64", "usr/lib"),
+    ("usr/lib64", "usr/lib"),
+];
+
+/// Each dpkg maintainer script, the pacman install function running it and the arguments
+/// dpkg would pass. pacman passes the new version, then the old one on upgrades.
+const SCRIPT_HOOKS: &[(&str, &str, &str)] = &[
+    ("preinst", "pre_install", "install"),
+    ("preinst", "pre_upgrade", "upgrade \"$2\""),
+    ("postinst", "post_install", "configure"),
+    ("postinst", "post_upgrade", "configure \"$2\""),
+    ("prerm", "pre_remove", "remove"),
+    ("postrm", "post_remove", "remove"),
+];
+
+/// Commands only found on Debian systems; scripts using them are not converted
+const DEBIAN_ONLY_COMMANDS: &[&str] = &[
+    "dpkg",
+    "debconf",
+    "db_",
+    "update-alternatives",
+    "update-rc.d",
+    "invoke-rc.d",
+    "deb-systemd-",
+    "ucf",
+    "adduser",
+    "addgroup",
+    "deluser",
+    "delgroup",
+    "update-initramfs",
+];
+
+/// A .deb file: an ar archive with `debian-binary`, `control.tar.*` and `data.tar.*`
+pub struct DebFile {
+    /// Package metadata from the control file
+    pub package: DebianPackage,
+    fields: Vec<(String, String)>,
+    /// Maintainer scripts by name (preinst, postinst, prerm, postrm)
+    scripts: HashMap<String, String>,
+    /// Configuration files, as absolute paths
+    conffiles: Vec<String>,
+    /// The uncompressed data tarball
+    data: File,
+}
+
+/// A pacman package written from a .deb
+#[derive(Debug)]
+pub struct Converted {
+    pub path: PathBuf,
+    /// Package name, the same as the Debian one
+    pub name: String,
+    pub version: String,
+    pub debian_version: String,
+    /// Maintainer scripts that were left out because they rely on Debian tools
+    pub skipped_scripts: Vec<String>,
+}
+
+impl DebFile {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut archive = ar::Archive::new(File::open(path)?);
+        let mut control = None;
+        let mut data = None;
+
+        while let Some(entry) = archive.next_entry() {
+            let mut entry = entry.map_err(|e| conversion_error(path, e))?;
+            let name = String::from_utf8_lossy(entry.header().identifier()).trim_end_matches('/').to_string();
+
+            if let Some(compression) = name.strip_prefix("control.tar") {
+                let mut tarball = Vec::new();
+                decompress(&mut entry, compression, &mut tarball).map_err(|e| conversion_error(path, e))?;
+                control = Some(tarball);
+            } else if let Some(compression) = name.strip_prefix("data.tar") {
+                let mut tarball = tempfile::tempfile()?;
+                decompress(&mut entry, compression, &mut tarball).map_err(|e| conversion_error(path, e))?;
+                data = Some(tarball);
+            }
+        }
+
+        let (Some(control), Some(data)) = (control, data) else {
+            return Err(KhazaurError::Conversion(format!(
+                "{}: not a Debian package (no control or data archive)",
+                path.display()
+            )));
+        };
+
+        let mut fields = Vec::new();
+        let mut scripts = HashMap::new();
+        let mut conffiles = Vec::new();
+
+        for entry in tar::Archive::new(&control[..]).entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().trim_start_matches("./").to_string();
+            let mut contents = String::new();
+            match name.as_str() {
+                "control" => {
+                    entry.read_to_string(&mut contents)?;
+                    fields = parse_control(&contents);
+                }
+                "conffiles" => {
+                    entry.read_to_string(&mut contents)?;
+                    // `remove-on-upgrade` entries are conffiles the package no longer ships
+                    conffiles = contents
+                        .lines()
+                        .map(str::trim)
+                        .filter(|line| line.starts_with('/'))
+                        .map(str::to_string)
+                        .collect();
+                }
+                "preinst" | "postinst" | "prerm" | "postrm" => {
+                    entry.read_to_string(&mut contents)?;
+                    scripts.insert(name, contents);
+                }
+                _ => {}
+            }
+        }
+
+        let package = package_from_control(&fields);
+        if package.name.is_empty() || package.version.is_empty() {
+            return Err(KhazaurError::Conversion(format!(
+                "{}: control file has no Package or Version",
+                path.display()
+            )));
+        }
+
+        Ok(Self {
+            package,
+            fields,
+            scripts,
+            conffiles,
+            data,
+        })
+    }
+
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Write a .pkg.tar.zst for the package to `out_dir`, with `depends` as its
+    /// pacman dependencies
+    pub fn convert(&mut self, depends: &[String], out_dir: &Path) -> Result<Converted> {
+        let entries = self.scan_data()?;
+        let (install, skipped_scripts) = install_script(&self.scripts);
+
+        let name = self.package.name.clone();
+        let version = pacman_version(&self.package.version);
+        let arch = pacman_arch(&self.package.architecture);
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+
+        let pkginfo = self.pkginfo(&version, &arch, depends, &entries, now);
+        let mut metadata = vec![(".PKGINFO", pkginfo.into_bytes())];
+        if let Some(install) = install {
+            metadata.push((".INSTALL", install.into_bytes()));
+        }
+        let mtree = mtree(&metadata, &entries, now)?;
+        metadata.push((".MTREE", mtree));
+
+        std::fs::create_dir_all(out_dir)?;
+        let path = out_dir.join(format!("{}-{}-{}.pkg.tar.zst", name, version, arch));
+        let encoder = zstd::stream::write::Encoder::new(File::create(&path)?, 0)?;
+        let mut builder = tar::Builder::new(encoder);
+
+        // pacman reads .PKGINFO first
+        for (file, contents) in &metadata {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(now);
+            builder.append_data(&mut header, file, &contents[..])?;
+        }
+
+        self.data.seek(SeekFrom::Start(0))?;
+        let mut archive = tar::Archive::new(BufReader::new(&self.data));
+        for (entry, kept) in archive.entries()?.zip(&entries) {
+            let entry = entry?;
+            let Some(kept) = kept else {
+                continue;
+            };
+
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(kept.mode);
+            header.set_uid(kept.uid);
+            header.set_gid(kept.gid);
+            header.set_mtime(kept.mtime);
+
+            match &kept.kind {
+                EntryKind::Dir => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_size(0);
+                    builder.append_data(&mut header, &kept.path, std::io::empty())?;
+                }
+                EntryKind::File => {
+                    header.set_entry_type(tar::EntryType::Regular);
+                    header.set_size(kept.size);
+                    builder.append_data(&mut header, &kept.path, entry)?;
+                }
+                EntryKind::Symlink(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &kept.path, target)?;
+                }
+                EntryKind::Hardlink(target) => {
+                    header.set_entry_type(tar::EntryType::Link);
+                    header.set_size(0);
+                    builder.append_link(&mut header, &kept.path, target)?;
+                }
+            }
+        }
+
+        builder.into_inner()?.finish()?;
+
+        Ok(Converted {
+            path,
+            name,
+            version,
+            debian_version: self.package.version.clone(),
+            skipped_scripts,
+        })
+    }
+
+    /// Read the data tarball once, for the entries to keep under their Arch paths,
+    /// their sizes and hashes. The result lines up with the tarball's entries.
+    fn scan_data(&mut self) -> Result<Vec<Option<DataEntry>>> {
+        self.data.seek(SeekFrom::Start(0))?;
+        let mut archive = tar::Archive::new(BufReader::new(&self.data));
+        let mut entries: Vec<Option<DataEntry>> = Vec::new();
+        let mut seen = HashMap::new();
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let raw_path = entry.path()?.to_string_lossy().into_owned();
+            let header = entry.header();
+            let kind = match header.entry_type() {
+                tar::EntryType::Directory => Some(EntryKind::Dir),
+                tar::EntryType::Regular | tar::EntryType::Continuous => Some(EntryKind::File),
+                // Absolute targets move with the files they point to
+                tar::EntryType::Symlink => entry.link_name()?.map(|target| {
+                    let target = target.to_string_lossy().into_owned();
+                    match arch_path(&target) {
+                        Some(moved) if target.starts_with('/') => EntryKind::Symlink(format!("/{}", moved)),
+                        _ => EntryKind::Symlink(target),
+                    }
+                }),
+                tar::EntryType::Link => entry
+                    .link_name()?
+                    .and_then(|target| arch_path(&target.to_string_lossy()))
+                    .map(EntryKind::Hardlink),
+                // Device nodes and fifos don't belong in packages
+                _ => None,
+            };
+
+            let (Some(path), Some(kind)) = (arch_path(&raw_path), kind) else {
+                entries.push(None);
+                continue;
+            };
+            // /usr/bin and its merged counterparts are one directory on Arch
+            if MERGED_DIRS.iter().any(|(from, _)| *from == path) || seen.contains_key(&path) {
+                entries.push(None);
+                continue;
+            }
+
+            let mut data_entry = DataEntry {
+                path: path.clone(),
+                mode: header.mode()? & 0o7777,
+                uid: header.uid()?,
+                gid: header.gid()?,
+                mtime: header.mtime()?,
+                size: 0,
+                sha256: None,
+                kind,
+            };
+
+            match &data_entry.kind {
+                EntryKind::File => {
+                    let mut contents = Vec::new();
+                    entry.read_to_end(&mut contents)?;
+                    data_entry.size = contents.len() as u64;
+                    data_entry.sha256 = Some(super::verify::sha256_hex(&contents));
+                }
+                EntryKind::Hardlink(target) => {
+                    if let Some(&index) = seen.get(target) {
+                        let linked: &Option<DataEntry> = &entries[index];
+                        if let Some(linked) = linked {
+                            data_entry.size = linked.size;
+                            data_entry.sha256 = linked.sha256.clone();
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            seen.insert(path, entries.len());
+            entries.push(Some(data_entry));
+        }
+
+        Ok(entries)
+    }
+
+    fn pkginfo(&self, version: &str, arch: &str, depends: &[String], entries: &[Option<DataEntry>], now: u64) -> String {
+        let package = &self.package;
+        let size: u64 = entries
+            .iter()
+            .flatten()
+            .filter(|entry| entry.kind == EntryKind::File)
+            .map(|entry| entry.size)
+            .sum();
+
+        let mut lines = vec![
+            "# Generated by khazaur from a Debian package".to_string(),
+            format!("pkgname = {}", package.name),
+            format!("pkgbase = {}", package.name),
+            format!("pkgver = {}", version),
+            format!("pkgdesc = {}", package.description),
+        ];
+        if let Some(homepage) = self.field("Homepage") {
+            lines.push(format!("url = {}", homepage));
+        }
+        lines.push(format!("builddate = {}", now));
+        lines.push(format!(
+            "packager = {}",
+            package.maintainer.as_deref().unwrap_or("Unknown Packager")
+        ));
+        lines.push(format!("size = {}", size));
+        lines.push(format!("arch = {}", arch));
+        lines.push("license = custom".to_string());
+
+        // Breaks is a weaker Conflicts; both only name Debian packages
+        for field in ["Conflicts", "Breaks"] {
+            for relation in super::relations::parse_provides(self.field(field).unwrap_or_default()) {
+                if relation.name != package.name {
+                    lines.push(format!("conflict = {}", relation.name));
+                }
+            }
+        }
+        for provide in &package.provides {
+            lines.push(format!("provides = {}", provide.pacman_dep(&provide.name)));
+        }
+        for conffile in &self.conffiles {
+            if let Some(path) = arch_path(conffile) {
+                lines.push(format!("backup = {}", path));
+            }
+        }
+        for depend in depends {
+            lines.push(format!("depend = {}", depend));
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum EntryKind {
+    Dir,
+    File,
+    Symlink(String),
+    /// A hard link to another entry, by its Arch path
+    Hardlink(String),
+}
+
+#[derive(Debug)]
+struct DataEntry {
+    path: String,
+    kind: EntryKind,
+    mode: u32,
+    uid: u64,
+    gid: u64,
+    mtime: u64,
+    size: u64,
+    sha256: Option<String>,
+}
+
+fn conversion_error(path: &Path, e: impl std::fmt::Display) -> KhazaurError {
+    KhazaurError::Conversion(format!("{}: {}", path.display(), e))
+}
+
+/// Copy an archive member, decompressing it according to its file name suffix
+fn decompress(reader: &mut impl Read, compression: &str, out: &mut impl Write) -> std::io::Result<u64> {
+    match compression {
+        "" => std::io::copy(reader, out),
+        ".gz" => std::io::copy(&mut flate2::read::GzDecoder::new(reader), out),
+        ".xz" => std::io::copy(&mut xz2::read::XzDecoder::new(reader), out),
+        ".zst" => std::io::copy(&mut zstd::stream::read::Decoder::new(reader)?, out),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("unsupported compression {}", other.trim_start_matches('.')),
+        )),
+    }
+}
+
+/// Fields of a control file; continuation lines are joined with newlines
+fn parse_control(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+
+    for line in text.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    fields
+}
+
+fn package_from_control(fields: &[(String, String)]) -> DebianPackage {
+    let mut package = DebianPackage::default();
+
+    for (key, value) in fields {
+        match key.as_str() {
+            "Package" => package.name = value.clone(),
+            "Version" => package.version = value.clone(),
+            "Architecture" => package.architecture = value.clone(),
+            "Maintainer" => package.maintainer = Some(value.clone()),
+            "Description" => package.description = value.lines().next().unwrap_or_default().to_string(),
+            "Pre-Depends" => package.pre_depends = super::relations::parse_depends(value),
+            "Depends" => package.depends = super::relations::parse_depends(value),
+            "Provides" => package.provides = super::relations::parse_provides(value),
+            _ => {}
+        }
+    }
+
+    package
+}
+
+/// Path of a data tarball entry on Arch, None for the root directory
+pub(super) fn arch_path(path: &str) -> Option<String> {
+    let path = path.trim_start_matches("./").trim_matches('/');
+    if path.is_empty() || path == "." {
+        return None;
+    }
+
+    let path = MERGED_DIRS
+        .iter()
+        .find_map(|(from, to)| {
+            let rest = path.strip_prefix(from)?.strip_prefix('/')?;
+            Some(format!("{}/{}", to, rest))
+        })
+        .unwrap_or_else(|| path.to_string());
+
+    // Debian keeps libraries in multiarch directories, Arch directly in /usr/lib:
+    // usr/lib/x86_64-linux-gnu/libfoo.so -> usr/lib/libfoo.so
+    if let Some(rest) = path.strip_prefix("usr/lib/") {
+        let (dir, below) = rest.split_once('/').unwrap_or((rest, ""));
+        if is_multiarch_triplet(dir) {
+            return Some(if below.is_empty() { "usr/lib".to_string() } else { format!("usr/lib/{}", below) });
+        }
+    }
+    Some(path)
+}
+
+/// A Debian multiarch tuple such as `x86_64-linux-gnu` or `arm-linux-gnueabihf`
+fn is_multiarch_triplet(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('-').collect();
+    matches!(parts.as_slice(), [cpu, "linux", abi] if !cpu.is_empty() && (abi.starts_with("gnu") || abi.starts_with("musl")))
+}
+
+/// pacman's `[epoch:]pkgver-pkgrel` for a Debian version. A numeric Debian revision
+/// becomes the pkgrel; any other revision is kept in the pkgver so upgrades still sort.
+pub fn pacman_version(version: &str) -> String {
+    let (epoch, upstream, revision) = super::version::split(version);
+    let mut pkgver = upstream.replace(['-', ':'], "_");
+    let numeric = !revision.is_empty()
+        && revision.split('.').count() <= 2
+        && revision.split('.').all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+
+    let pkgrel = if numeric {
+        revision.to_string()
+    } else {
+        if !revision.is_empty() {
+            pkgver = format!("{}_{}", pkgver, revision.replace('-', "_"));
+        }
+        "1".to_string()
+    };
+
+    match epoch {
+        0 => format!("{}-{}", pkgver, pkgrel),
+        epoch => format!("{}:{}-{}", epoch, pkgver, pkgrel),
+    }
+}
+
+fn pacman_arch(architecture: &str) -> String {
+    match architecture {
+        "amd64" => "x86_64".to_string(),
+        "arm64" => "aarch64".to_string(),
+        "i386" => "i686".to_string(),
+        "armhf" => "armv7h".to_string(),
+        "all" | "" => "any".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Drop the blocks debhelper generates, which only call Debian tools
+fn strip_debhelper(script: &str) -> String {
+    let mut kept = Vec::new();
+    let mut generated = false;
+
+    for line in script.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("# Automatically added by dh_") {
+            generated = true;
+        } else if trimmed.starts_with("# End automatically added section") {
+            generated = false;
+        } else if !generated && trimmed != "#DEBHELPER#" {
+            kept.push(line);
+        }
+    }
+
+    kept.join("\n")
+}
+
+/// Whether a maintainer script still does something once debhelper's blocks are gone
+fn has_commands(script: &str) -> bool {
+    script.lines().map(str::trim).any(|line| {
+        !line.is_empty() && !line.starts_with('#') && line != "set -e" && line != "exit 0"
+    })
+}
+
+/// Whether a maintainer script is a shell script using no Debian-only tools
+fn is_portable(script: &str) -> bool {
+    let shebang = script.lines().next().unwrap_or_default();
+    if !(shebang.starts_with("#!/bin/sh") || shebang.starts_with("#!/bin/bash")) {
+        return false;
+    }
+
+    !script
+        .split(|c: char| c.is_whitespace() || ";|&()`$\"'=<>".contains(c))
+        .any(|word| {
+            let command = word.rsplit('/').next().unwrap_or(word);
+            DEBIAN_ONLY_COMMANDS.iter().any(|debian| command.starts_with(debian))
+                || word.contains("debconf")
+                || word.contains("dpkg")
+        })
+}
+
+/// Build a pacman .INSTALL file running the maintainer scripts that are safe on Arch,
+/// returning it with the names of the scripts left out
+fn install_script(scripts: &HashMap<String, String>) -> (Option<String>, Vec<String>) {
+    let mut portable = HashMap::new();
+    let mut skipped = Vec::new();
+
+    for name in ["preinst", "postinst", "prerm", "postrm"] {
+        let Some(script) = scripts.get(name) else {
+            continue;
+        };
+        let script = strip_debhelper(script);
+        if !has_commands(&script) {
+            continue;
+        }
+        if is_portable(&script) {
+            portable.insert(name, script);
+        } else {
+            skipped.push(name.to_string());
+        }
+    }
+
+    if portable.is_empty() {
+        return (None, skipped);
+    }
+
+    let mut install = String::from("# Maintainer scripts of the Debian package, run with dpkg's arguments\n");
+    for (script_name, function, args) in SCRIPT_HOOKS {
+        if let Some(script) = portable.get(script_name) {
+            let shell = if script.starts_with("#!/bin/bash") { "bash" } else { "sh" };
+            install.push_str(&format!(
+                "\n{}() {{\n    {} -s {} <<'KHAZAUR_{}'\n{}\nKHAZAUR_{}\n}}\n",
+                function,
+                shell,
+                args,
+                script_name.to_uppercase(),
+                script,
+                script_name.to_uppercase()
+            ));
+        }
+    }
+
+    (Some(install), skipped)
+}
+
+/// The gzipped mtree pacman uses to check installed files, as bsdtar writes it for makepkg
+fn mtree(metadata: &[(&str, Vec<u8>)], entries: &[Option<DataEntry>], now: u64) -> Result<Vec<u8>> {
+    let mut mtree = String::from("#mtree\n/set type=file uid=0 gid=0 mode=644\n");
+
+    for (file, contents) in metadata {
+        mtree.push_str(&format!(
+            "./{} time={}.0 size={} sha256digest={}\n",
+            file,
+            now,
+            contents.len(),
+            super::verify::sha256_hex(contents)
+        ));
+    }
+
+    for entry in entries.iter().flatten() {
+        let mut line = format!(
+            "./{} time={}.0 uid={} gid={} mode={:o}",
+            mtree_escape(&entry.path),
+            entry.mtime,
+            entry.uid,
+            entry.gid,
+            entry.mode
+        );
+        match &entry.kind {
+            EntryKind::Dir => line.push_str(" type=dir"),
+            EntryKind::Symlink(target) => line.push_str(&format!(" type=link link={}", mtree_escape(target))),
+            EntryKind::File | EntryKind::Hardlink(_) => {
+                line.push_str(&format!(" size={}", entry.size));
+                if let Some(sha256) = &entry.sha256 {
+                    line.push_str(&format!(" sha256digest={}", sha256));
+                }
+            }
+        }
+        mtree.push_str(&line);
+        mtree.push('\n');
+    }
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(mtree.as_bytes())?;
+    Ok(encoder.finish()?)
+}
+
+/// mtree writes whitespace, `#`, `\` and non-ASCII bytes as octal escapes
+fn mtree_escape(path: &str) -> String {
+    path.bytes()
+        .map(|b| {
+            if b.is_ascii_graphic() && b != b'\\' && b != b'#' {
+                (b as char).to_string()
+            } else {
+                format!("\\{:03o}", b)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pacman_version() {
+        assert_eq!(pacman_version("1.2.3-4"), "1.2.3-4");
+        assert_eq!(pacman_version("1:2.36-9+deb12u4"), "1:2.36_9+deb12u4-1");
+        assert_eq!(pacman_version("2.0~rc1"), "2.0~rc1-1");
+        assert_eq!(pacman_version("1.0-beta-2"), "1.0_beta-2");
+    }
+
+    #[test]
+    fn test_arch_path() {
+        assert_eq!(arch_path("./"), None);
+        assert_eq!(arch_path("./usr/share/foo/"), Some("usr/share/foo".to_string()));
+        assert_eq!(arch_path("./sbin/foo"), Some("usr/bin/foo".to_string()));
+        assert_eq!(arch_path("./lib64/ld.so"), Some("usr/lib/ld.so".to_string()));
+        assert_eq!(arch_path("./libexec/foo"), Some("libexec/foo".to_string()));
+        assert_eq!(arch_path("/etc/foo.conf"), Some("etc/foo.conf".to_string()));
+        assert_eq!(arch_path("./usr/lib/x86_64-linux-gnu/libfoo.so.1"), Some("usr/lib/libfoo.so.1".to_string()));
+        assert_eq!(arch_path("./usr/lib/x86_64-linux-gnu/"), Some("usr/lib".to_string()));
+        assert_eq!(arch_path("./lib/aarch64-linux-gnu/libbar.so"), Some("usr/lib/libbar.so".to_string()));
+        assert_eq!(arch_path("./lib/arm-linux-gnueabihf/gio/x.so"), Some("usr/lib/gio/x.so".to_string()));
+        assert_eq!(arch_path("./usr/lib/python3-dist/foo.py"), Some("usr/lib/python3-dist/foo.py".to_string()));
+    }
+
+    /// A tar archive with the given directories and files
+    fn tarball(dirs: &[&str], files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for dir in dirs {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Directory);
+            header.set_mode(0o755);
+            header.set_uid(0);
+            header.set_gid(0);
+            header.set_mtime(0);
+            header.set_size(0);
+            builder.append_data(&mut header, dir, std::io::empty()).unwrap();
+        }
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o755);
+            header.set_uid(0);
+            header.set_gid(0);
+            header.set_mtime(0);
+            header.set_size(contents.len() as u64);
+            builder.append_data(&mut header, path, contents.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn compress(data: &[u8], compression: &str) -> Vec<u8> {
+        match compression {
+            ".gz" => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ".xz" => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            }
+            ".zst" => zstd::encode_all(data, 0).unwrap(),
+            _ => data.to_vec(),
+        }
+    }
+
+    /// Write a .deb with its control and data archives compressed as given
+    fn write_deb(path: &Path, compression: &str) {
+        let control = tarball(
+            &["./"],
+            &[(
+                "./control",
+                "Package: foo\nVersion: 1.2-3\nArchitecture: amd64\nMaintainer: Jane <jane@example.com>\n\
+                 Depends: libc6\nHomepage: https://example.com/foo\nDescription: Foo tool\n Longer text\n",
+            )],
+        );
+        let data = tarball(
+            &["./", "./bin/", "./lib/", "./lib/x86_64-linux-gnu/", "./usr/", "./usr/lib/", "./usr/lib/x86_64-linux-gnu/"],
+            &[
+                ("./bin/foo", "#!/bin/sh\necho foo\n"),
+                ("./lib/x86_64-linux-gnu/libbar.so.2", "bar"),
+                ("./usr/lib/x86_64-linux-gnu/libfoo.so.1", "foo"),
+            ],
+        );
+
+        let mut builder = ar::Builder::new(File::create(path).unwrap());
+        let members = [
+            ("debian-binary".to_string(), b"2.0\n".to_vec()),
+            (format!("control.tar{}", compression), compress(&control, compression)),
+            (format!("data.tar{}", compression), compress(&data, compression)),
+        ];
+        for (name, contents) in members {
+            let header = ar::Header::new(name.into_bytes(), contents.len() as u64);
+            builder.append(&header, &contents[..]).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_convert_deb() {
+        let dir = tempfile::tempdir().unwrap();
+
+        for compression in [".gz", ".xz", ".zst"] {
+            let deb = dir.path().join(format!("foo{}.deb", compression));
+            write_deb(&deb, compression);
+
+            let out_dir = dir.path().join(compression.trim_start_matches('.'));
+            let converted = DebFile::open(&deb).unwrap().convert(&["glibc".to_string()], &out_dir).unwrap();
+            assert_eq!(converted.name, "foo");
+            assert_eq!(converted.version, "1.2-3");
+            assert_eq!(converted.path, out_dir.join("foo-1.2-3-x86_64.pkg.tar.zst"));
+
+            let package = zstd::decode_all(File::open(&converted.path).unwrap()).unwrap();
+            let mut pkginfo = String::new();
+            let mut paths = Vec::new();
+            for entry in tar::Archive::new(&package[..]).entries().unwrap() {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_string_lossy().into_owned();
+                if path == ".PKGINFO" {
+                    entry.read_to_string(&mut pkginfo).unwrap();
+                }
+                paths.push(path);
+            }
+
+            assert_eq!(paths[0], ".PKGINFO");
+            assert!(paths.iter().any(|path| path == ".MTREE"));
+            for line in ["pkgname = foo", "pkgver = 1.2-3", "pkgdesc = Foo tool", "url = https://example.com/foo",
+                "packager = Jane <jane@example.com>", "arch = x86_64", "size = 25", "depend = glibc"] {
+                assert!(pkginfo.lines().any(|l| l == line), "{} missing from .PKGINFO:\n{}", line, pkginfo);
+            }
+
+            for path in ["usr/bin/foo", "usr/lib/libbar.so.2", "usr/lib/libfoo.so.1"] {
+                assert!(paths.iter().any(|p| p == path), "{} missing from {:?}", path, paths);
+            }
+            assert!(!paths.iter().any(|p| p.starts_with("bin") || p.starts_with("lib") || p.contains("linux-gnu")));
+            assert_eq!(paths.iter().filter(|p| p.trim_end_matches('/') == "usr/lib").count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_install_script() {
+        let mut scripts = HashMap::new();
+        scripts.insert(
+            "postinst".to_string(),
+            "#!/bin/sh\nset -e\n# Automatically added by dh_installsystemd/13.11\ndeb-systemd-helper enable foo.service\n# End automatically added section\nmkdir -p /var/lib/foo\n".to_string(),
+        );
+        scripts.insert("prerm".to_string(), "#!/bin/sh\nset -e\n#DEBHELPER#\nexit 0\n".to_string());
+        scripts.insert("postrm".to_string(), "#!/bin/sh\n. /usr/share/debconf/confmodule\ndb_purge\n".to_string());
+
+        let (install, skipped) = install_script(&scripts);
+        let install = install.unwrap();
+        assert!(install.contains("post_install() {\n    sh -s configure <<'KHAZAUR_POSTINST'"));
+        assert!(install.contains("mkdir -p /var/lib/foo"));
+        assert!(!install.contains("deb-systemd-helper"));
+        assert!(!install.contains("pre_remove"));
+        assert_eq!(skipped, vec!["postrm"]);
+    }
+}
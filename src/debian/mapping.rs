@@ -0,0 +1,214 @@
+use crate::error::{KhazaurError, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// debtap's table of Debian packages and the Arch packages replacing them, written by `debtap -u`
+pub const DEBTAP_VIRTUAL_PACKAGES: &str = "/var/cache/pkgtools/lists/virtual-packages";
+
+/// Common libraries whose Arch package name can't be guessed from the Debian one.
+/// None means nothing needs to be installed on Arch.
+const BUILTIN: &[(&str, Option<&str>)] = &[
+    ("libc6", Some("glibc")),
+    ("libc-bin", Some("glibc")),
+    ("libstdc++6", Some("gcc-libs")),
+    ("libgcc-s1", Some("gcc-libs")),
+    ("libgcc1", Some("gcc-libs")),
+    ("zlib1g", Some("zlib")),
+    ("libssl3", Some("openssl")),
+    ("libssl3t64", Some("openssl")),
+    ("libglib2.0-0", Some("glib2")),
+    ("libglib2.0-0t64", Some("glib2")),
+    ("libgtk-3-0", Some("gtk3")),
+    ("libgtk-3-0t64", Some("gtk3")),
+    ("libgtk-4-1", Some("gtk4")),
+    ("libasound2", Some("alsa-lib")),
+    ("libasound2t64", Some("alsa-lib")),
+    ("libdbus-1-3", Some("dbus")),
+    ("libgdk-pixbuf-2.0-0", Some("gdk-pixbuf2")),
+    ("libgdk-pixbuf2.0-0", Some("gdk-pixbuf2")),
+    ("libfreetype6", Some("freetype2")),
+    ("libatk1.0-0", Some("at-spi2-core")),
+    ("libatk1.0-0t64", Some("at-spi2-core")),
+    ("libatk-bridge2.0-0", Some("at-spi2-core")),
+    ("libatspi2.0-0", Some("at-spi2-core")),
+    ("libcups2", Some("libcups")),
+    ("libgbm1", Some("mesa")),
+    ("libgl1", Some("libglvnd")),
+    ("libegl1", Some("libglvnd")),
+    ("libcurl4", Some("curl")),
+    ("libsqlite3-0", Some("sqlite")),
+    ("libpng16-16", Some("libpng")),
+    ("libjpeg62-turbo", Some("libjpeg-turbo")),
+    ("libudev1", Some("systemd-libs")),
+    ("libsystemd0", Some("systemd-libs")),
+    ("libuuid1", Some("util-linux-libs")),
+    ("libbz2-1.0", Some("bzip2")),
+    ("liblzma5", Some("xz")),
+    ("libzstd1", Some("zstd")),
+    ("libwayland-client0", Some("wayland")),
+    ("libpango-1.0-0", Some("pango")),
+    ("libpangocairo-1.0-0", Some("pango")),
+    ("libcairo2", Some("cairo")),
+    ("libexpat1", Some("expat")),
+    ("libfontconfig1", Some("fontconfig")),
+    ("libpulse0", Some("libpulse")),
+    ("python3", Some("python")),
+    ("perl-base", Some("perl")),
+    // Debian packaging infrastructure with no Arch counterpart
+    ("debconf", None),
+    ("debconf-2.0", None),
+    ("dpkg", None),
+    ("init-system-helpers", None),
+    ("lsb-base", None),
+    ("sysvinit-utils", None),
+    ("install-info", None),
+];
+
+/// What a Debian package name stands for on Arch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mapped {
+    /// An Arch package (by name or provides), and whether it is installed
+    Arch { name: String, installed: bool },
+    /// Not needed on Arch
+    Ignored,
+    /// No Arch equivalent known
+    Unmapped,
+}
+
+/// Maps Debian package names to Arch ones: the user's table
+/// (~/.config/khazaur/debian-map.toml) first, then the built-in table,
+/// then debtap's database, then the same or a similar name in the pacman databases
+#[derive(Debug, Default)]
+pub struct ArchMap {
+    overrides: HashMap<String, Option<String>>,
+    debtap: HashMap<String, String>,
+}
+
+impl ArchMap {
+    pub fn load() -> Result<Self> {
+        Ok(Self {
+            overrides: read_overrides(&Self::overrides_path()?)?,
+            debtap: read_debtap(Path::new(DEBTAP_VIRTUAL_PACKAGES)),
+        })
+    }
+
+    pub fn overrides_path() -> Result<PathBuf> {
+        Ok(dirs::config_dir()
+            .ok_or_else(|| KhazaurError::Config("Could not determine config directory".to_string()))?
+            .join("khazaur")
+            .join("debian-map.toml"))
+    }
+
+    /// Mapping from a table, if any: Some(None) when the package isn't needed
+    fn listed(&self, name: &str) -> Option<Option<String>> {
+        if let Some(mapped) = self.overrides.get(name) {
+            return Some(mapped.clone());
+        }
+        if let Some((_, mapped)) = BUILTIN.iter().find(|(deb, _)| *deb == name) {
+            return Some(mapped.map(str::to_string));
+        }
+        self.debtap.get(name).map(|arch| Some(arch.clone()))
+    }
+
+    /// Map a Debian package name. `find_arch` looks a name up in the pacman databases,
+    /// by name or provides, returning the package and whether it is installed.
+    pub fn map(&self, name: &str, find_arch: &dyn Fn(&str) -> Option<(String, bool)>) -> Mapped {
+        let found = |(name, installed): (String, bool)| Mapped::Arch { name, installed };
+
+        match self.listed(name) {
+            Some(None) => return Mapped::Ignored,
+            // Trust the tables even when the package isn't in a configured repository
+            Some(Some(arch)) => return find_arch(&arch).map_or(Mapped::Arch { name: arch, installed: false }, found),
+            None => {}
+        }
+
+        guesses(name)
+            .iter()
+            .find_map(|guess| find_arch(guess))
+            .map_or(Mapped::Unmapped, found)
+    }
+}
+
+/// Arch names a Debian package may have: itself, then without the `t64` and soname
+/// suffixes Debian adds to libraries (libx11-6 -> libx11, libfoo2.1 -> libfoo)
+fn guesses(name: &str) -> Vec<String> {
+    let mut guesses = vec![name.to_string()];
+    let mut current = name.strip_suffix("t64").unwrap_or(name);
+
+    loop {
+        let trimmed = current.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+        let trimmed = trimmed.strip_suffix('-').unwrap_or(trimmed);
+        if trimmed == current || trimmed.len() < 3 {
+            break;
+        }
+        guesses.push(trimmed.to_string());
+        current = trimmed;
+    }
+
+    guesses.dedup();
+    guesses
+}
+
+/// `libfoo1 = "foo"` maps a package, `debconf = false` drops it
+fn read_overrides(path: &Path) -> Result<HashMap<String, Option<String>>> {
+    let table = crate::config::read_table(path)?;
+    let mut overrides = HashMap::new();
+
+    for (name, value) in table {
+        let mapped = match value {
+            toml::Value::String(arch) if !arch.is_empty() => Some(arch),
+            toml::Value::String(_) | toml::Value::Boolean(false) => None,
+            other => {
+                return Err(KhazaurError::Config(format!(
+                    "Invalid mapping {} = {} in {}: expected an Arch package name or false",
+                    name,
+                    other,
+                    path.display()
+                )));
+            }
+        };
+        overrides.insert(name, mapped);
+    }
+
+    Ok(overrides)
+}
+
+/// Lines of `debian-name arch-name`, optionally separated by a colon
+fn read_debtap(path: &Path) -> HashMap<String, String> {
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(|c: char| c.is_whitespace() || c == ':').filter(|f| !f.is_empty());
+            Some((fields.next()?.to_string(), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let mut map = ArchMap::default();
+        map.overrides.insert("libfoo1".to_string(), Some("foo".to_string()));
+        map.overrides.insert("libc6".to_string(), None);
+
+        let arch = |name: &str| {
+            ["glibc", "libx11", "python", "foo"]
+                .contains(&name)
+                .then(|| (name.to_string(), name == "glibc"))
+        };
+
+        assert_eq!(map.map("libc6", &arch), Mapped::Ignored);
+        assert_eq!(map.map("libfoo1", &arch), Mapped::Arch { name: "foo".to_string(), installed: false });
+        assert_eq!(map.map("python3", &arch), Mapped::Arch { name: "python".to_string(), installed: false });
+        assert_eq!(map.map("libx11-6", &arch), Mapped::Arch { name: "libx11".to_string(), installed: false });
+        assert_eq!(map.map("libbar2", &arch), Mapped::Unmapped);
+        assert_eq!(guesses("libglib2.0-0t64"), vec!["libglib2.0-0t64", "libglib2.0", "libglib"]);
+    }
+}
@@ -1,126 +1,287 @@
+pub mod cache;
+pub mod contents;
+pub mod convert;
+pub mod mapping;
+pub mod plan;
+pub mod relations;
+pub mod sources;
+pub mod tracking;
+pub mod verify;
+pub mod version;
+
+use crate::config::Config;
 use crate::error::{KhazaurError, Result};
 use flate2::read::GzDecoder;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub use relations::Relation;
+pub use sources::{DebianSource, SuitePolicy};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DebianPackage {
     pub name: String,
     pub version: String,
     pub description: String,
     pub filename: String,
-    pub md5sum: String,
+    /// SHA256 of the .deb, from the index
+    pub sha256: String,
     pub architecture: String,
     pub maintainer: Option<String>,
+    /// Mirror the package is downloaded from
+    #[serde(default)]
+    pub mirror: String,
+    /// Label of the source that lists the package, usually its suite
+    #[serde(default)]
+    pub suite: String,
+    #[serde(default)]
+    pub component: String,
+    /// Priority of the source that lists the package
+    #[serde(default)]
+    pub priority: i64,
+    /// Groups of alternatives that must be installed before the package is unpacked
+    #[serde(default)]
+    pub pre_depends: Vec<Vec<Relation>>,
+    #[serde(default)]
+    pub depends: Vec<Vec<Relation>>,
+    #[serde(default)]
+    pub provides: Vec<Relation>,
 }
 
-const DEBIAN_MIRROR: &str = "http://deb.debian.org/debian";
-const RELEASE: &str = "bookworm";
-const COMPONENT: &str = "main";
+/// Indexes older than this are downloaded again
+const INDEX_MAX_AGE_SECS: u64 = 86400;
+
+/// Keyring installed by the debian-archive-keyring package
+pub const DEFAULT_KEYRING: &str = "/usr/share/keyrings/debian-archive-keyring.gpg";
 
-/// Get system architecture
-fn get_system_arch() -> String {
-    std::env::consts::ARCH.to_string()
+/// SHA256 and size of each file listed in a verified Release file
+type ReleaseHashes = std::collections::HashMap<String, (String, u64)>;
+
+/// Debian name of the system architecture
+fn debian_arch() -> String {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64".to_string(),
+        "aarch64" => "arm64".to_string(),
+        other => other.to_string(),
+    }
 }
 
-/// Fetch and parse the Packages.gz index
-async fn fetch_and_parse_index(show_progress: bool) -> Result<Vec<DebianPackage>> {
-    let arch = get_system_arch();
-    let arch_mapped = match arch.as_str() {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        _ => &arch,
-    };
-    
-    // Cache the Packages.gz file
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| KhazaurError::Config("Could not find cache directory".to_string()))?
-        .join("khazaur")
-        .join("debian");
-    
-    std::fs::create_dir_all(&cache_dir)?;
-    let cache_file = cache_dir.join(format!("Packages-{}-{}.gz", RELEASE, arch_mapped));
-    
-    // Check if cache exists and is less than 24 hours old
-    let should_download = if cache_file.exists() {
-        if let Ok(metadata) = std::fs::metadata(&cache_file) {
-            if let Ok(modified) = metadata.modified() {
-                if let Ok(elapsed) = modified.elapsed() {
-                    elapsed.as_secs() > 86400 // Re-download if older than 24 hours
-                } else {
-                    true
+fn index_cache_file(source: &DebianSource, component: &str, arch: &str) -> Result<PathBuf> {
+    Ok(crate::dirs::cache_dir()?.join("debian").join(source.cache_name(component, arch)))
+}
+
+fn is_stale(cache_file: &Path) -> bool {
+    std::fs::metadata(cache_file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|elapsed| elapsed.as_secs() > INDEX_MAX_AGE_SECS)
+}
+
+/// Fetch the Packages.gz index of every configured source and component and load
+/// its parsed cache. A source that can't be fetched is skipped with a warning, unless
+/// all of them fail. A failed signature or hash check always fails.
+async fn load_indexes(config: &Config, show_progress: bool) -> Result<Vec<cache::IndexCache>> {
+    let arch = debian_arch();
+    let mut indexes = Vec::new();
+    let mut last_error = None;
+
+    for source in &config.debian_sources {
+        let stale = source.components.iter().any(|component| {
+            index_cache_file(source, component, &arch).map_or(true, |file| is_stale(&file))
+        });
+
+        // Indexes are only cached after they were verified, so the Release file is
+        // only needed when one is downloaded
+        let release = if stale && !source.trusted {
+            let keyring = source.keyring.as_deref().unwrap_or(&config.debian_keyring);
+            match fetch_release(source, keyring).await {
+                Ok(release) => Some(release),
+                Err(e @ KhazaurError::Verification(_)) => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Skipping Debian source {}: {}", source.label(), e);
+                    last_error = Some(e);
+                    continue;
                 }
-            } else {
-                true
             }
         } else {
-            true
+            None
+        };
+
+        for component in &source.components {
+            let indexed = match fetch_index(source, component, &arch, release.as_ref(), show_progress).await {
+                Ok(cache_file) => cached_index(&cache_file, source, component, &arch),
+                Err(e) => Err(e),
+            };
+
+            match indexed {
+                Ok(index) => indexes.push(index),
+                Err(e @ KhazaurError::Verification(_)) => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Skipping Debian source {} {}: {}", source.label(), component, e);
+                    last_error = Some(e);
+                }
+            }
         }
-    } else {
-        true
-    };
+    }
+
+    match last_error {
+        Some(e) if indexes.is_empty() => Err(e),
+        _ => Ok(indexes),
+    }
+}
+
+/// Every package of every configured source and component
+async fn fetch_and_parse_index(config: &Config, show_progress: bool) -> Result<Vec<DebianPackage>> {
+    Ok(load_indexes(config, show_progress)
+        .await?
+        .into_iter()
+        .flat_map(cache::IndexCache::into_packages)
+        .collect())
+}
+
+/// Download a source's InRelease file and check its signature, returning the
+/// index hashes it lists
+async fn fetch_release(source: &DebianSource, keyring: &Path) -> Result<ReleaseHashes> {
+    let url = source.inrelease_url();
+    let response = crate::net::get(&url).await?;
+    if !response.status().is_success() {
+        return Err(KhazaurError::DownloadFailed(format!("{}: HTTP {}", url, response.status())));
+    }
+
+    let mut inrelease = tempfile::NamedTempFile::new()?;
+    inrelease.write_all(&response.bytes().await?)?;
+
+    let release = verify::verify_inrelease(inrelease.path(), keyring).map_err(|e| match e {
+        KhazaurError::Verification(msg) => KhazaurError::Verification(format!("{}: {}", url, msg)),
+        other => other,
+    })?;
+    Ok(verify::release_sha256(&release))
+}
+
+/// Download one component's index unless the cached copy is recent, returning the cache file.
+/// The download is checked against the Release hashes unless the source is trusted.
+async fn fetch_index(
+    source: &DebianSource,
+    component: &str,
+    arch: &str,
+    release: Option<&ReleaseHashes>,
+    show_progress: bool,
+) -> Result<PathBuf> {
+    let cache_file = index_cache_file(source, component, arch)?;
+    if !is_stale(&cache_file) {
+        return Ok(cache_file);
+    }
+
+    let message = format!("Updating Debian package index ({}/{})...", source.label(), component);
+    download_verified(
+        source,
+        &DebianSource::index_path(component, arch),
+        &cache_file,
+        release,
+        show_progress.then_some(message.as_str()),
+    )
+    .await?;
+    Ok(cache_file)
+}
+
+/// Download a file of a source's dists directory to `cache_file`, given its path
+/// relative to the Release file, checking it against the Release hashes when given.
+/// `progress` is printed above a progress bar; without it the download is silent.
+async fn download_verified(
+    source: &DebianSource,
+    path: &str,
+    cache_file: &Path,
+    release: Option<&ReleaseHashes>,
+    progress: Option<&str>,
+) -> Result<()> {
+    if let Some(dir) = cache_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let url = source.dists_url(path);
+    let response = crate::net::get(&url).await
+        .map_err(|e| KhazaurError::Config(format!("Failed to fetch Debian index: {}", e)))?;
     
-    if should_download {
-        let index_url = format!(
-            "{}/dists/{}/{}/binary-{}/Packages.gz",
-            DEBIAN_MIRROR, RELEASE, COMPONENT, arch_mapped
-        );
+    if !response.status().is_success() {
+        return Err(KhazaurError::Config(format!(
+            "Failed to fetch Debian index {}: HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+    
+    let bytes_vec = if let Some(message) = progress {
+        // Download with progress bar
+        use indicatif::{ProgressBar, ProgressStyle};
+        use futures_util::StreamExt;
         
-        let response = reqwest::get(&index_url).await
-            .map_err(|e| KhazaurError::Config(format!("Failed to fetch Debian index: {}", e)))?;
+        // Show message before starting download
+        eprintln!("{}", message);
         
-        if !response.status().is_success() {
-            return Err(KhazaurError::Config(format!(
-                "Failed to fetch Debian index: HTTP {}",
-                response.status()
-            )));
-        }
+        let total_size = response.content_length().unwrap_or(0);
+        let pb = ProgressBar::new(total_size);
+        pb.set_style(ProgressStyle::default_bar()
+            .template("  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-"));
         
-        let bytes_vec = if show_progress {
-            // Download with progress bar
-            use indicatif::{ProgressBar, ProgressStyle};
-            use futures_util::StreamExt;
-            
-            // Show message before starting download
-            eprintln!("Updating Debian package index...");
-            
-            let total_size = response.content_length().unwrap_or(0);
-            let pb = ProgressBar::new(total_size);
-            pb.set_style(ProgressStyle::default_bar()
-                .template("  [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                .unwrap()
-                .progress_chars("#>-"));
-            
-            let mut downloaded: u64 = 0;
-            let mut bytes_vec = Vec::new();
-            let mut stream = response.bytes_stream();
-            
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| KhazaurError::Config(format!("Download error: {}", e)))?;
-                bytes_vec.extend_from_slice(&chunk);
-                downloaded += chunk.len() as u64;
-                pb.set_position(downloaded);
-            }
-            
-            pb.finish_and_clear();
-            bytes_vec
-        } else {
-            // Download silently without any output
-            response.bytes().await?.to_vec()
-        };
+        let mut downloaded: u64 = 0;
+        let mut bytes_vec = Vec::new();
+        let mut stream = response.bytes_stream();
         
-        // Write to cache
-        std::fs::write(&cache_file, &bytes_vec)?;
-    }
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| KhazaurError::Config(format!("Download error: {}", e)))?;
+            bytes_vec.extend_from_slice(&chunk);
+            downloaded += chunk.len() as u64;
+            pb.set_position(downloaded);
+        }
+        
+        pb.finish_and_clear();
+        bytes_vec
+    } else {
+        // Download silently without any output
+        response.bytes().await?.to_vec()
+    };
     
-    // Read from cache and decompress
-    let bytes = std::fs::read(&cache_file)?;
+    if let Some(release) = release {
+        let (hash, size) = release.get(path).ok_or_else(|| {
+            KhazaurError::Verification(format!("{} is not listed in the Release file of {}", path, source.label()))
+        })?;
+        verify::check_sha256(&url, &bytes_vec, hash, Some(*size))?;
+    }
+
+    // Write to cache
+    std::fs::write(cache_file, &bytes_vec)?;
+    Ok(())
+}
+
+/// Load the parsed cache of an index file, parsing the index again when the cache
+/// is missing or was built from an older copy
+fn cached_index(index_file: &Path, source: &DebianSource, component: &str, arch: &str) -> Result<cache::IndexCache> {
+    let cache_file = cache::cache_path(index_file);
+    let stamp = cache::stamp(index_file, source, component).unwrap_or_default();
+    if let Some(index) = cache::IndexCache::load(&cache_file, &stamp) {
+        return Ok(index);
+    }
+
+    let index = cache::IndexCache::build(parse_index_file(index_file, source, component, arch)?, stamp);
+    if let Err(e) = index.save(&cache_file) {
+        tracing::warn!("Could not cache Debian index {}: {}", cache_file.display(), e);
+    }
+    Ok(index)
+}
+
+fn parse_index_file(cache_file: &Path, source: &DebianSource, component: &str, arch: &str) -> Result<Vec<DebianPackage>> {
+    let bytes = std::fs::read(cache_file)?;
     let decoder = GzDecoder::new(&bytes[..]);
-    let reader = BufReader::new(decoder);
-    
-    // Parse packages
+    parse_index(BufReader::new(decoder), source, component, arch)
+}
+
+/// Parse the stanzas of a Packages index
+fn parse_index(reader: impl BufRead, source: &DebianSource, component: &str, arch: &str) -> Result<Vec<DebianPackage>> {
     let mut packages = Vec::new();
     let mut current_package = None::<DebianPackage>;
     
@@ -142,12 +303,12 @@ async fn fetch_and_parse_index(show_progress: bool) -> Result<Vec<DebianPackage>
                 "Package" => {
                     current_package = Some(DebianPackage {
                         name: value.to_string(),
-                        version: String::new(),
-                        description: String::new(),
-                        filename: String::new(),
-                        md5sum: String::new(),
-                        architecture: arch_mapped.to_string(),
-                        maintainer: None,
+                        architecture: arch.to_string(),
+                        mirror: source.base_url().to_string(),
+                        suite: source.label().to_string(),
+                        component: component.to_string(),
+                        priority: source.priority,
+                        ..Default::default()
                     });
                 }
                 "Version" => {
@@ -165,9 +326,9 @@ async fn fetch_and_parse_index(show_progress: bool) -> Result<Vec<DebianPackage>
                         pkg.filename = value.to_string();
                     }
                 }
-                "MD5sum" => {
+                "SHA256" => {
                     if let Some(ref mut pkg) = current_package {
-                        pkg.md5sum = value.to_string();
+                        pkg.sha256 = value.to_string();
                     }
                 }
                 "Maintainer" => {
@@ -175,6 +336,21 @@ async fn fetch_and_parse_index(show_progress: bool) -> Result<Vec<DebianPackage>
                         pkg.maintainer = Some(value.to_string());
                     }
                 }
+                "Pre-Depends" => {
+                    if let Some(ref mut pkg) = current_package {
+                        pkg.pre_depends = relations::parse_depends(value);
+                    }
+                }
+                "Depends" => {
+                    if let Some(ref mut pkg) = current_package {
+                        pkg.depends = relations::parse_depends(value);
+                    }
+                }
+                "Provides" => {
+                    if let Some(ref mut pkg) = current_package {
+                        pkg.provides = relations::parse_provides(value);
+                    }
+                }
                 _ => {}
             }
         }
@@ -189,54 +365,35 @@ async fn fetch_and_parse_index(show_progress: bool) -> Result<Vec<DebianPackage>
 }
 
 /// Update Debian package index (with progress bar)
-pub async fn update_index() -> Result<()> {
-    fetch_and_parse_index(true).await?;
+pub async fn update_index(config: &Config) -> Result<()> {
+    fetch_and_parse_index(config, true).await?;
     Ok(())
 }
 
-/// Check if Debian index needs updating
-pub fn index_needs_update() -> bool {
-    let cache_dir = match dirs::cache_dir() {
-        Some(dir) => dir.join("khazaur").join("debian"),
-        None => return true,
-    };
-    
-    let arch = get_system_arch();
-    let arch_mapped = match arch.as_str() {
-        "x86_64" => "amd64",
-        "aarch64" => "arm64",
-        _ => &arch,
-    };
-    
-    let cache_file = cache_dir.join(format!("Packages-{}-{}.gz", RELEASE, arch_mapped));
-    
-    if !cache_file.exists() {
-        return true;
-    }
-    
-    // Check if cache is older than 24 hours
-    if let Ok(metadata) = std::fs::metadata(&cache_file) {
-        if let Ok(modified) = metadata.modified() {
-            if let Ok(elapsed) = modified.elapsed() {
-                return elapsed.as_secs() > 86400;
-            }
-        }
-    }
-    
-    false
+/// Every package of the configured sources, one per name as chosen by the suite policy
+pub async fn load_index(config: &Config) -> Result<Vec<DebianPackage>> {
+    Ok(config.debian_suite_policy.select(fetch_and_parse_index(config, false).await?))
 }
 
-/// Search for Debian packages matching a query
-pub async fn search_debian(query: &str) -> Result<Vec<DebianPackage>> {
-    let all_packages = fetch_and_parse_index(false).await?;
-    let query_lower = query.to_lowercase();
-    
-    let matches: Vec<DebianPackage> = all_packages
-        .into_iter()
-        .filter(|pkg| pkg.name.to_lowercase().contains(&query_lower))
-        .collect();
-    
-    Ok(matches)
+/// Search for Debian packages whose name contains the query or whose description has
+/// all of its words. Every source's match is returned, use the suite policy to pick one per name.
+pub async fn search_debian(query: &str, config: &Config) -> Result<Vec<DebianPackage>> {
+    let indexes = load_indexes(config, false).await?;
+    Ok(indexes
+        .iter()
+        .flat_map(|index| index.search(query))
+        .cloned()
+        .collect())
+}
+
+/// Search for Debian packages whose name contains the query
+pub async fn search_debian_names(query: &str, config: &Config) -> Result<Vec<DebianPackage>> {
+    let indexes = load_indexes(config, false).await?;
+    Ok(indexes
+        .iter()
+        .flat_map(|index| index.search_names(query))
+        .cloned()
+        .collect())
 }
 
 /// Download a Debian package and verify its checksum
@@ -245,20 +402,17 @@ pub async fn download_debian(package: &DebianPackage) -> Result<PathBuf> {
     use std::io::Write;
     
     // Create cache directory
-    let cache_dir = dirs::cache_dir()
-        .ok_or_else(|| KhazaurError::Config("Could not find cache directory".to_string()))?
-        .join("khazaur")
-        .join("debian");
+    let cache_dir = crate::dirs::cache_dir()?.join("debian");
     
     fs::create_dir_all(&cache_dir)?;
     
-    let download_url = format!("{}/{}", DEBIAN_MIRROR, package.filename);
-    let filename = package.filename.split('/').last().unwrap_or(&package.filename);
+    let download_url = format!("{}/{}", package.mirror, package.filename);
+    let filename = package.filename.rsplit('/').next().unwrap_or(&package.filename);
     let output_path = cache_dir.join(filename);
     
     // Download
-    println!("Downloading {} from Debian repository...", package.name);
-    let response = reqwest::get(&download_url).await
+    println!("Downloading {} from {}...", package.name, package.suite);
+    let response = crate::net::get(&download_url).await
         .map_err(|e| KhazaurError::Config(format!("Failed to download package: {}", e)))?;
     
     if !response.status().is_success() {
@@ -271,123 +425,249 @@ pub async fn download_debian(package: &DebianPackage) -> Result<PathBuf> {
     let bytes = response.bytes().await
         .map_err(|e| KhazaurError::Config(format!("Failed to read package data: {}", e)))?;
     
+    // Verify against the SHA256 from the index before anything is written
+    if package.sha256.is_empty() {
+        return Err(KhazaurError::Verification(format!(
+            "{} has no SHA256 in the Debian index",
+            package.name
+        )));
+    }
+    verify::check_sha256(filename, &bytes, &package.sha256, None)?;
+    
     // Write to file
     let mut file = fs::File::create(&output_path)?;
     file.write_all(&bytes)?;
     
-    // Verify MD5 checksum
-    let calculated_md5 = format!("{:x}", md5::compute(&bytes));
-    
-    if calculated_md5 != package.md5sum {
-        fs::remove_file(&output_path)?;
-        return Err(KhazaurError::Config(format!(
-            "MD5 checksum mismatch! Expected: {}, Got: {}",
-            package.md5sum, calculated_md5
-        )));
-    }
-    
     println!("✓ Package downloaded and verified: {}", output_path.display());
     Ok(output_path)
 }
 
-/// Get the path to the Debian tracking file
-fn get_tracking_file() -> Result<PathBuf> {
-    let config_dir = dirs::config_dir()
-        .ok_or_else(|| KhazaurError::Config("Could not find config directory".to_string()))?
-        .join("khazaur");
-    
-    std::fs::create_dir_all(&config_dir)?;
-    Ok(config_dir.join("debian_packages.txt"))
+/// Download several Debian packages, at most `concurrent` at a time.
+/// The results are in the order of `packages`.
+pub async fn download_debians(packages: &[&DebianPackage], concurrent: usize) -> Vec<Result<PathBuf>> {
+    use futures_util::stream::{self, StreamExt};
+
+    stream::iter(packages)
+        .map(|package| download_debian(package))
+        .buffered(concurrent.max(1))
+        .collect()
+        .await
 }
 
-/// Track a package as installed from Debian
-pub fn track_debian_package(package_name: &str) -> Result<()> {
-    let tracking_file = get_tracking_file()?;
-    let mut packages = load_tracked_packages()?;
-    packages.insert(package_name.to_string());
-    
-    let mut file = std::fs::File::create(tracking_file)?;
-    for pkg in packages {
-        writeln!(file, "{}", pkg)?;
+/// Whether Debian packages can be installed with the configured converter
+pub fn converter_available(config: &Config) -> bool {
+    match config.debian_converter {
+        convert::Converter::Builtin => true,
+        convert::Converter::Debtap => crate::debtap::is_available(),
     }
-    Ok(())
 }
 
-/// Load tracked Debian packages
-fn load_tracked_packages() -> Result<HashSet<String>> {
-    let tracking_file = get_tracking_file()?;
-    
-    if !tracking_file.exists() {
-        return Ok(HashSet::new());
+/// Convert a .deb to a pacman package, install it and record it in the tracking
+/// database, returning the entry. `origin` is the index entry the file was downloaded
+/// for, None for local files. `depends` are the pacman dependencies from an install plan;
+/// without one the dependencies are mapped to Arch packages and the unmapped ones dropped.
+/// The built-in converter falls back to debtap when it can't convert the package.
+pub async fn install_deb(
+    path: &Path,
+    origin: Option<&DebianPackage>,
+    depends: Option<&[String]>,
+    reason: crate::reasons::InstallReason,
+    config: &Config,
+) -> Result<tracking::TrackedPackage> {
+    if config.debian_converter == convert::Converter::Debtap {
+        return install_with_debtap(path, origin).await;
     }
-    
-    let content = std::fs::read_to_string(tracking_file)?;
-    Ok(content.lines().map(|s| s.to_string()).collect())
+
+    let converted = convert::DebFile::open(path).and_then(|mut deb| {
+        let depends = match depends {
+            Some(depends) => depends.to_vec(),
+            None => {
+                let plan = plan::InstallPlan::for_file(&deb.package)?;
+                if !plan.unresolved.is_empty() {
+                    eprintln!(
+                        "{}",
+                        crate::ui::warning(&format!("Leaving out dependencies with no Arch package: {}", plan.unresolved.join(", ")))
+                    );
+                }
+                plan.debian.into_iter().next().map(|planned| planned.depends).unwrap_or_default()
+            }
+        };
+        deb.convert(&depends, &crate::dirs::cache_dir()?.join("debian"))
+    });
+
+    let converted = match converted {
+        Ok(converted) => converted,
+        Err(e) if crate::debtap::is_available() => {
+            eprintln!("{}", crate::ui::warning(&format!("{}, falling back to debtap", e)));
+            return install_with_debtap(path, origin).await;
+        }
+        Err(e) => return Err(e),
+    };
+
+    println!("{}", crate::ui::success(&format!("Converted {} {}", converted.name, converted.version)));
+    if !converted.skipped_scripts.is_empty() {
+        eprintln!(
+            "{}",
+            crate::ui::warning(&format!(
+                "Left out maintainer scripts of {} that need Debian tools: {}",
+                converted.name,
+                converted.skipped_scripts.join(", ")
+            ))
+        );
+    }
+
+    let extra_args = match reason {
+        crate::reasons::InstallReason::Dependency => vec!["--asdeps".to_string()],
+        crate::reasons::InstallReason::Explicit => Vec::new(),
+    };
+    crate::pacman::install_local_package(&converted.path.to_string_lossy(), &extra_args)?;
+
+    let tracked = tracking::TrackedPackage {
+        debian_name: converted.name.clone(),
+        arch_name: converted.name,
+        version: converted.debian_version,
+        arch_version: converted.version,
+        ..origin_fields(path, origin)
+    };
+    record_tracked(&tracked);
+    Ok(tracked)
 }
 
-/// Check if a package was installed from Debian
-#[allow(dead_code)]
+/// Install a .deb with debtap, which names the package after the Debian one
+async fn install_with_debtap(path: &Path, origin: Option<&DebianPackage>) -> Result<tracking::TrackedPackage> {
+    let debian = convert::DebFile::open(path).ok().map(|deb| deb.package);
+    crate::debtap::install_deb(&path.to_string_lossy()).await?;
+
+    let name = debian.as_ref().map_or_else(|| package_name(path), |pkg| pkg.name.clone());
+    let arch_version = crate::pacman::get_installed_packages()
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|(installed, version)| (installed == name).then_some(version))
+        .unwrap_or_default();
+    let tracked = tracking::TrackedPackage {
+        debian_name: name.clone(),
+        arch_name: name,
+        version: debian.map(|pkg| pkg.version).unwrap_or_default(),
+        arch_version,
+        ..origin_fields(path, origin)
+    };
+    record_tracked(&tracked);
+    Ok(tracked)
+}
+
+/// Where a converted package came from, with the names and versions left empty
+fn origin_fields(path: &Path, origin: Option<&DebianPackage>) -> tracking::TrackedPackage {
+    let (suite, source_url) = match origin {
+        Some(pkg) => (pkg.suite.clone(), format!("{}/{}", pkg.mirror, pkg.filename)),
+        None => (
+            String::new(),
+            std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().into_owned(),
+        ),
+    };
+    tracking::TrackedPackage {
+        debian_name: String::new(),
+        arch_name: String::new(),
+        version: String::new(),
+        arch_version: String::new(),
+        suite,
+        source_url,
+        converted_at: chrono::Local::now().to_rfc3339(),
+    }
+}
+
+/// The package is installed either way, so a tracking failure only warns
+fn record_tracked(tracked: &tracking::TrackedPackage) {
+    if let Err(e) = tracking::record(tracked.clone()) {
+        tracing::warn!("Could not record {} as a Debian package: {}", tracked.arch_name, e);
+    }
+}
+
+/// Name of the Debian package in a .deb, from its control file, or from the file
+/// name (`name_version_arch.deb`) when that can't be read
+pub fn package_name(path: &Path) -> String {
+    convert::DebFile::open(path).map(|deb| deb.package.name).unwrap_or_else(|_| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.split('_').next())
+            .unwrap_or_default()
+            .to_string()
+    })
+}
+
+/// pacman names of the packages converted from Debian
+pub fn load_tracked_packages() -> Result<HashSet<String>> {
+    Ok(tracking::TrackingDb::load()?
+        .packages()
+        .map(|tracked| tracked.arch_name.clone())
+        .collect())
+}
+
+/// Check if a pacman package was converted from Debian
 pub fn is_debian_package(package_name: &str) -> bool {
-    load_tracked_packages()
-        .map(|packages| packages.contains(package_name))
+    tracking::TrackingDb::load()
+        .map(|db| db.contains(package_name))
         .unwrap_or(false)
 }
 
-/// Check for Debian package updates
+/// Check for Debian package updates of the tracked packages that are still installed.
 /// Returns list of (package_name, installed_version, debian_version, debian_package)
-pub async fn check_debian_updates() -> Result<Vec<(String, String, String, DebianPackage)>> {
-    // Get tracked Debian packages
-    let tracked_packages = load_tracked_packages()?;
-    
-    // Get all installed packages that might be from Debian
-    let installed = crate::pacman::get_installed_packages()?;
+pub async fn check_debian_updates(config: &Config) -> Result<Vec<(String, String, String, DebianPackage)>> {
+    // Drop tracked packages that were removed in the meantime, before upgrading the rest
+    let (tracked, reconciled) = tracking::reconcile()?;
+    for entry in &reconciled.dropped {
+        tracing::info!("{} is no longer installed, untracking it", entry.arch_name);
+    }
+    if !reconciled.is_empty() {
+        tracked.save()?;
+    }
+    let debian_names: HashSet<&str> = tracked.packages().map(|entry| entry.debian_name.as_str()).collect();
+
+    let installed: std::collections::HashMap<String, String> =
+        crate::pacman::get_installed_packages()?.into_iter().collect();
     
-    // Fetch Debian package index
-    let debian_packages = fetch_and_parse_index(true).await?;
+    // Fetch Debian package index, keeping the tracked packages the suite policy prefers
+    let candidates: Vec<DebianPackage> = fetch_and_parse_index(config, true)
+        .await?
+        .into_iter()
+        .filter(|pkg| debian_names.contains(pkg.name.as_str()))
+        .collect();
+    let debian_packages: std::collections::HashMap<String, DebianPackage> = config
+        .debian_suite_policy
+        .select(candidates)
+        .into_iter()
+        .map(|pkg| (pkg.name.clone(), pkg))
+        .collect();
     
     let mut updates = Vec::new();
     
-    for (pkg_name, installed_version) in installed {
-        // Only check packages that were tracked as Debian packages
-        if !tracked_packages.contains(&pkg_name) {
+    for entry in tracked.packages() {
+        let (Some(installed_version), Some(debian_pkg)) =
+            (installed.get(&entry.arch_name), debian_packages.get(&entry.debian_name))
+        else {
             continue;
-        }
-        
-        // Find matching Debian package
-        if let Some(debian_pkg) = debian_packages.iter().find(|p| p.name == pkg_name) {
-            // Compare versions using vercmp
-            if needs_update(&installed_version, &debian_pkg.version)? {
-                updates.push((
-                    pkg_name,
-                    installed_version,
-                    debian_pkg.version.clone(),
-                    debian_pkg.clone(),
-                ));
-            }
+        };
+
+        // Compare Debian versions when the converted one is known, otherwise the
+        // installed version with vercmp, in the form the converter gives it
+        let newer = if entry.version.is_empty() {
+            let available = match config.debian_converter {
+                convert::Converter::Builtin => convert::pacman_version(&debian_pkg.version),
+                convert::Converter::Debtap => debian_pkg.version.clone(),
+            };
+            crate::pacman::vercmp(installed_version, &available) == std::cmp::Ordering::Less
+        } else {
+            version::compare(&debian_pkg.version, &entry.version) == std::cmp::Ordering::Greater
+        };
+
+        if newer {
+            updates.push((
+                entry.arch_name.clone(),
+                installed_version.clone(),
+                debian_pkg.version.clone(),
+                debian_pkg.clone(),
+            ));
         }
     }
     
     Ok(updates)
 }
-
-/// Check if a package needs an update by comparing versions
-fn needs_update(installed_version: &str, available_version: &str) -> Result<bool> {
-    use std::process::Command;
-    
-    let output = Command::new("vercmp")
-        .arg(installed_version)
-        .arg(available_version)
-        .output()?;
-    
-    if !output.status.success() {
-        return Ok(false);
-    }
-    
-    let result = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    
-    // vercmp returns:
-    // -1 if installed < available (update needed)
-    //  0 if installed == available (no update)
-    //  1 if installed > available (downgrade, no update)
-    Ok(result == "-1")
-}
@@ -0,0 +1,220 @@
+use super::mapping::{ArchMap, Mapped};
+use super::{DebianPackage, Relation};
+use crate::config::Config;
+use crate::error::Result;
+use crate::pacman::db::PackageDb;
+use crate::reasons::InstallReason;
+use std::collections::{HashMap, HashSet};
+
+/// A Debian package to convert and install
+#[derive(Debug, Clone)]
+pub struct PlannedPackage {
+    pub package: DebianPackage,
+    pub reason: InstallReason,
+    /// Dependencies as pacman dependency strings, for the converted package
+    pub depends: Vec<String>,
+}
+
+/// Everything needed to install some Debian packages: Arch packages from the
+/// repositories, then Debian packages in dependency order
+#[derive(Debug, Default)]
+pub struct InstallPlan {
+    pub debian: Vec<PlannedPackage>,
+    /// Arch packages standing in for Debian dependencies that are not installed yet
+    pub arch: Vec<String>,
+    /// Dependencies with no Arch or Debian package, as `a | b (required by c)`
+    pub unresolved: Vec<String>,
+}
+
+impl InstallPlan {
+    /// Plan the installation of `roots`. Each dependency is satisfied by an Arch package
+    /// when one of its alternatives maps to one, and otherwise by a package of `index`
+    /// having its name or providing it. `find_arch` looks a name up in the pacman
+    /// databases, returning the package and whether it is installed.
+    pub fn build(
+        roots: &[DebianPackage],
+        index: &[DebianPackage],
+        map: &ArchMap,
+        find_arch: &dyn Fn(&str) -> Option<(String, bool)>,
+    ) -> Self {
+        let mut planner = Planner {
+            packages: HashMap::new(),
+            providers: HashMap::new(),
+            roots: roots.iter().map(|pkg| pkg.name.as_str()).collect(),
+            map,
+            find_arch,
+            visited: HashSet::new(),
+            plan: InstallPlan::default(),
+        };
+
+        // Requested packages take precedence over the index's version of them
+        for pkg in index.iter().chain(roots) {
+            planner.packages.insert(pkg.name.as_str(), pkg);
+            for provide in &pkg.provides {
+                planner.providers.entry(provide.name.as_str()).or_insert(pkg);
+            }
+        }
+
+        for pkg in roots {
+            planner.visit(pkg);
+        }
+
+        planner.plan
+    }
+
+    /// Plan against the configured Debian sources, the Arch name mapping and the pacman databases
+    pub async fn for_packages(roots: &[DebianPackage], config: &Config) -> Result<Self> {
+        let index = super::load_index(config).await?;
+        Self::with_databases(roots, &index)
+    }
+
+    /// Plan a single package, e.g. from a .deb file, against the Arch name mapping and
+    /// the pacman databases only: Debian dependencies are not pulled in
+    pub fn for_file(package: &DebianPackage) -> Result<Self> {
+        Self::with_databases(std::slice::from_ref(package), &[])
+    }
+
+    fn with_databases(roots: &[DebianPackage], index: &[DebianPackage]) -> Result<Self> {
+        let map = ArchMap::load()?;
+        let local = PackageDb::load_local().unwrap_or_default();
+        let sync = PackageDb::load_sync().unwrap_or_default();
+
+        let find_arch = |name: &str| {
+            local
+                .resolve(name)
+                .map(|pkg| (pkg.name.clone(), true))
+                .or_else(|| sync.resolve(name).map(|pkg| (pkg.name.clone(), false)))
+        };
+        Ok(Self::build(roots, index, &map, &find_arch))
+    }
+
+    /// Debian packages installed to satisfy dependencies
+    pub fn dependencies(&self) -> impl Iterator<Item = &PlannedPackage> {
+        self.debian.iter().filter(|planned| planned.reason == InstallReason::Dependency)
+    }
+}
+
+struct Planner<'a> {
+    packages: HashMap<&'a str, &'a DebianPackage>,
+    providers: HashMap<&'a str, &'a DebianPackage>,
+    roots: HashSet<&'a str>,
+    map: &'a ArchMap,
+    find_arch: &'a dyn Fn(&str) -> Option<(String, bool)>,
+    /// Packages planned or being planned, which breaks dependency cycles
+    visited: HashSet<&'a str>,
+    plan: InstallPlan,
+}
+
+impl<'a> Planner<'a> {
+    /// Plan a package after its dependencies
+    fn visit(&mut self, pkg: &'a DebianPackage) {
+        if !self.visited.insert(pkg.name.as_str()) {
+            return;
+        }
+
+        let mut depends: Vec<String> = Vec::new();
+        for group in pkg.pre_depends.iter().chain(&pkg.depends) {
+            match self.satisfy(group) {
+                Some(Some(dep)) => {
+                    if !depends.contains(&dep) {
+                        depends.push(dep);
+                    }
+                }
+                Some(None) => {}
+                None => {
+                    let names: Vec<&str> = group.iter().map(|rel| rel.name.as_str()).collect();
+                    self.plan
+                        .unresolved
+                        .push(format!("{} (required by {})", names.join(" | "), pkg.name));
+                }
+            }
+        }
+
+        let reason = if self.roots.contains(pkg.name.as_str()) {
+            InstallReason::Explicit
+        } else {
+            InstallReason::Dependency
+        };
+        self.plan.debian.push(PlannedPackage {
+            package: pkg.clone(),
+            reason,
+            depends,
+        });
+    }
+
+    /// The pacman dependency satisfying a group of alternatives, None when nothing
+    /// does and Some(None) when nothing is needed on Arch
+    fn satisfy(&mut self, group: &[Relation]) -> Option<Option<String>> {
+        for rel in group {
+            match self.map.map(&rel.name, self.find_arch) {
+                Mapped::Ignored => return Some(None),
+                // Debian version constraints don't apply to Arch versions
+                Mapped::Arch { name, installed } => {
+                    if !installed && !self.plan.arch.contains(&name) {
+                        self.plan.arch.push(name.clone());
+                    }
+                    return Some(Some(name));
+                }
+                Mapped::Unmapped => {}
+            }
+        }
+
+        for rel in group {
+            if let Some(&dep) = self.packages.get(rel.name.as_str()) {
+                self.visit(dep);
+                return Some(Some(rel.pacman_dep(&dep.name)));
+            }
+            if let Some(&dep) = self.providers.get(rel.name.as_str()) {
+                self.visit(dep);
+                return Some(Some(dep.name.clone()));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debian::relations::{parse_depends, parse_provides};
+
+    fn package(name: &str, depends: &str, provides: &str) -> DebianPackage {
+        DebianPackage {
+            name: name.to_string(),
+            version: "1.0-1".to_string(),
+            depends: parse_depends(depends),
+            provides: parse_provides(provides),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let index = vec![
+            package("libfoo1", "libc6, libbar2", ""),
+            package("tinymta", "libfoo1", "mail-transport-agent"),
+            package("libbar2", "", ""),
+        ];
+        let roots = vec![package(
+            "app",
+            "libc6 (>= 2.34), libfoo1 (>= 1.0) | libfoo-alt, mail-transport-agent, debconf, missing",
+            "",
+        )];
+        let arch = |name: &str| match name {
+            "glibc" => Some(("glibc".to_string(), true)),
+            "libbar" => Some(("libbar".to_string(), false)),
+            _ => None,
+        };
+
+        let plan = InstallPlan::build(&roots, &index, &ArchMap::default(), &arch);
+        let order: Vec<&str> = plan.debian.iter().map(|planned| planned.package.name.as_str()).collect();
+        assert_eq!(order, vec!["libfoo1", "tinymta", "app"]);
+        assert_eq!(plan.debian[0].depends, vec!["glibc", "libbar"]);
+        assert_eq!(plan.debian[2].depends, vec!["glibc", "libfoo1>=1.0", "tinymta"]);
+        assert_eq!(plan.debian[2].reason, InstallReason::Explicit);
+        assert_eq!(plan.dependencies().count(), 2);
+        assert_eq!(plan.arch, vec!["libbar"]);
+        assert_eq!(plan.unresolved, vec!["missing (required by app)"]);
+    }
+}
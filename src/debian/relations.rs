@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+
+/// Version operator of a Debian relation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Op {
+    /// `<<`
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    /// `>>`
+    Greater,
+}
+
+impl Op {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "<<" => Some(Op::Less),
+            // `<` and `>` are deprecated spellings of `<=` and `>=`
+            "<=" | "<" => Some(Op::LessEqual),
+            "=" => Some(Op::Equal),
+            ">=" | ">" => Some(Op::GreaterEqual),
+            ">>" => Some(Op::Greater),
+            _ => None,
+        }
+    }
+
+    /// pacman's spelling
+    pub fn pacman(&self) -> &'static str {
+        match self {
+            Op::Less => "<",
+            Op::LessEqual => "<=",
+            Op::Equal => "=",
+            Op::GreaterEqual => ">=",
+            Op::Greater => ">",
+        }
+    }
+}
+
+/// One package in a Depends/Pre-Depends/Provides field: `libc6 (>= 2.34)`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Relation {
+    pub name: String,
+    pub version: Option<(Op, String)>,
+}
+
+impl Relation {
+    fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (name, version) = match text.split_once('(') {
+            Some((name, rest)) => {
+                let rest = rest.trim_end().strip_suffix(')')?.trim();
+                let split = rest.find(|c: char| !"<>=".contains(c))?;
+                let op = Op::parse(rest[..split].trim())?;
+                (name.trim(), Some((op, rest[split..].trim().to_string())))
+            }
+            None => (text, None),
+        };
+
+        // `python3:any`, `libc6:amd64`
+        let name = name.split(':').next().unwrap_or(name).trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            name: name.to_string(),
+            version,
+        })
+    }
+
+    /// pacman dependency string for a package named `name`, keeping the version constraint
+    pub fn pacman_dep(&self, name: &str) -> String {
+        match &self.version {
+            Some((op, version)) => format!("{}{}{}", name, op.pacman(), version),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// Parse a Depends-style field into groups that must all be satisfied,
+/// each satisfied by any one of its alternatives: `a, b | c` -> [[a], [b, c]]
+pub fn parse_depends(field: &str) -> Vec<Vec<Relation>> {
+    field
+        .split(',')
+        .map(|group| group.split('|').filter_map(Relation::parse).collect::<Vec<_>>())
+        .filter(|group| !group.is_empty())
+        .collect()
+}
+
+/// Parse a Provides field, which has no alternatives
+pub fn parse_provides(field: &str) -> Vec<Relation> {
+    field.split(',').filter_map(Relation::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_depends() {
+        let depends = parse_depends("libc6 (>= 2.34), libfoo1 | libbar2 (<< 3~), python3:any, debconf (>= 0.5) | debconf-2.0");
+        assert_eq!(depends.len(), 4);
+        assert_eq!(depends[0][0].name, "libc6");
+        assert_eq!(depends[0][0].version, Some((Op::GreaterEqual, "2.34".to_string())));
+        assert_eq!(depends[1].len(), 2);
+        assert_eq!(depends[1][1].pacman_dep("libbar2"), "libbar2<3~");
+        assert_eq!(depends[2][0].name, "python3");
+        assert_eq!(depends[3][1].name, "debconf-2.0");
+
+        let provides = parse_provides("libfoo-abi-2, mail-transport-agent (= 1.0)");
+        assert_eq!(provides.len(), 2);
+        assert_eq!(provides[1].pacman_dep("mail-transport-agent"), "mail-transport-agent=1.0");
+    }
+}
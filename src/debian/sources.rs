@@ -0,0 +1,183 @@
+use super::DebianPackage;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+
+/// A Debian-style archive: `deb <url> <suite> <components...>`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebianSource {
+    /// Label shown in search results, the suite when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Mirror base URL, e.g. http://deb.debian.org/debian
+    pub url: String,
+    /// Suite or codename, e.g. bookworm, trixie, bookworm-backports, noble
+    pub suite: String,
+    #[serde(default = "default_components")]
+    pub components: Vec<String>,
+    /// Sources with a higher priority win under the `priority` policy
+    #[serde(default = "default_priority")]
+    pub priority: i64,
+    /// Keyring the InRelease signature is checked against, `debian_keyring` when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyring: Option<PathBuf>,
+    /// Skip signature and hash checks, for local archives without a signed Release
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trusted: bool,
+}
+
+fn default_components() -> Vec<String> {
+    vec!["main".to_string()]
+}
+
+fn default_priority() -> i64 {
+    500
+}
+
+/// Sources used when the configuration doesn't list any
+pub fn default_sources() -> Vec<DebianSource> {
+    vec![DebianSource {
+        name: None,
+        url: "http://deb.debian.org/debian".to_string(),
+        suite: "bookworm".to_string(),
+        components: default_components(),
+        priority: default_priority(),
+        keyring: None,
+        trusted: false,
+    }]
+}
+
+impl DebianSource {
+    pub fn label(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.suite)
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+
+    /// URL of the signed Release file
+    pub fn inrelease_url(&self) -> String {
+        format!("{}/dists/{}/InRelease", self.base_url(), self.suite)
+    }
+
+    /// Path of a component's index as listed in the Release file
+    pub fn index_path(component: &str, arch: &str) -> String {
+        format!("{}/binary-{}/Packages.gz", component, arch)
+    }
+
+    /// URL of a file listed in the Release file
+    pub fn dists_url(&self, path: &str) -> String {
+        format!("{}/dists/{}/{}", self.base_url(), self.suite, path)
+    }
+
+    /// Path of a component's Contents index as listed in the Release file; `arch` may be `all`
+    pub fn contents_path(component: &str, arch: &str) -> String {
+        format!("{}/Contents-{}.gz", component, arch)
+    }
+
+    /// Cache file name of one component's index, unique per mirror, suite and component
+    pub fn cache_name(&self, component: &str, arch: &str) -> String {
+        let mirror = self
+            .base_url()
+            .split_once("://")
+            .map_or(self.base_url(), |(_, rest)| rest);
+        let slug: String = format!("{}-{}-{}", mirror, self.suite, component)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        format!("Packages-{}-{}.gz", slug, arch)
+    }
+
+    /// Cache file name of one component's Contents index
+    pub fn contents_cache_name(&self, component: &str, arch: &str) -> String {
+        self.cache_name(component, arch).replacen("Packages-", "Contents-", 1)
+    }
+}
+
+/// Which suite's package is used when several sources have the same package
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SuitePolicy {
+    /// The source with the highest priority wins, the newer version breaks ties
+    #[default]
+    Priority,
+    /// The newest version wins, priority breaks ties
+    Newest,
+}
+
+impl SuitePolicy {
+    pub const NAMES: &'static [&'static str] = &["priority", "newest"];
+
+    /// Whether `a` is preferred over `b`
+    pub fn prefers(&self, a: &DebianPackage, b: &DebianPackage) -> bool {
+        let by_version = super::version::compare(&a.version, &b.version);
+        let by_priority = a.priority.cmp(&b.priority);
+        let order = match self {
+            SuitePolicy::Priority => by_priority.then(by_version),
+            SuitePolicy::Newest => by_version.then(by_priority),
+        };
+        order == Ordering::Greater
+    }
+
+    /// Keep the preferred package for every name, in the order names first appear
+    pub fn select(&self, packages: Vec<DebianPackage>) -> Vec<DebianPackage> {
+        let mut selected: Vec<DebianPackage> = Vec::new();
+        let mut index = std::collections::HashMap::new();
+
+        for pkg in packages {
+            match index.get(&pkg.name) {
+                Some(&i) => {
+                    if self.prefers(&pkg, &selected[i]) {
+                        selected[i] = pkg;
+                    }
+                }
+                None => {
+                    index.insert(pkg.name.clone(), selected.len());
+                    selected.push(pkg);
+                }
+            }
+        }
+
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(suite: &str, version: &str, priority: i64) -> DebianPackage {
+        DebianPackage {
+            name: "foo".to_string(),
+            version: version.to_string(),
+            suite: suite.to_string(),
+            priority,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy() {
+        let packages = vec![
+            package("bookworm", "1.2-1", 500),
+            package("bookworm-backports", "1.4-1~bpo12+1", 100),
+        ];
+
+        let selected = SuitePolicy::Priority.select(packages.clone());
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].suite, "bookworm");
+
+        let selected = SuitePolicy::Newest.select(packages);
+        assert_eq!(selected[0].suite, "bookworm-backports");
+    }
+
+    #[test]
+    fn test_cache_name() {
+        let source = &default_sources()[0];
+        assert_eq!(
+            source.cache_name("main", "amd64"),
+            "Packages-deb.debian.org_debian-bookworm-main-amd64.gz"
+        );
+    }
+}
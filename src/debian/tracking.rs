@@ -0,0 +1,232 @@
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+/// A pacman package khazaur converted from a Debian package and installed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedPackage {
+    /// Name of the Debian package
+    pub debian_name: String,
+    /// Name of the pacman package the conversion produced
+    pub arch_name: String,
+    /// Debian version that was converted, empty when unknown
+    pub version: String,
+    /// pacman version of the converted package, empty when unknown
+    #[serde(default)]
+    pub arch_version: String,
+    /// Label of the source it came from, empty for local .deb files
+    #[serde(default)]
+    pub suite: String,
+    /// URL the .deb was downloaded from, or the path of a local file
+    #[serde(default)]
+    pub source_url: String,
+    /// When the package was converted, RFC 3339
+    #[serde(default)]
+    pub converted_at: String,
+}
+
+impl TrackedPackage {
+    /// Entry for a package from the list kept before entries had fields
+    fn legacy(name: &str) -> Self {
+        Self {
+            debian_name: name.to_string(),
+            arch_name: name.to_string(),
+            version: String::new(),
+            arch_version: String::new(),
+            suite: String::new(),
+            source_url: String::new(),
+            converted_at: String::new(),
+        }
+    }
+}
+
+/// Debian packages installed through khazaur, keyed by pacman package name.
+/// Stored in ~/.local/share/khazaur/debian_packages.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrackingDb {
+    #[serde(default)]
+    packages: BTreeMap<String, TrackedPackage>,
+}
+
+impl TrackingDb {
+    /// Read the database, importing the plain name list of older versions
+    /// (~/.config/khazaur/debian_packages.txt) the first time
+    pub fn load() -> Result<Self> {
+        let path = db_path()?;
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            return Ok(serde_json::from_str(&contents)?);
+        }
+
+        let mut db = Self::default();
+        let Some(legacy) = legacy_path().filter(|legacy| legacy.exists()) else {
+            return Ok(db);
+        };
+        for name in std::fs::read_to_string(&legacy)?.lines().map(str::trim).filter(|name| !name.is_empty()) {
+            db.insert(TrackedPackage::legacy(name));
+        }
+        db.save()?;
+        std::fs::remove_file(&legacy)?;
+        Ok(db)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Entry of a pacman package
+    pub fn get(&self, arch_name: &str) -> Option<&TrackedPackage> {
+        self.packages.get(arch_name)
+    }
+
+    /// Entry of a pacman package, or of the package converted from a Debian one
+    pub fn find(&self, name: &str) -> Option<&TrackedPackage> {
+        self.get(name)
+            .or_else(|| self.packages.values().find(|tracked| tracked.debian_name == name))
+    }
+
+    pub fn contains(&self, arch_name: &str) -> bool {
+        self.packages.contains_key(arch_name)
+    }
+
+    pub fn insert(&mut self, tracked: TrackedPackage) {
+        self.packages.insert(tracked.arch_name.clone(), tracked);
+    }
+
+    pub fn remove(&mut self, arch_name: &str) -> Option<TrackedPackage> {
+        self.packages.remove(arch_name)
+    }
+
+    pub fn packages(&self) -> impl Iterator<Item = &TrackedPackage> {
+        self.packages.values()
+    }
+
+    /// Drop entries of packages that are no longer installed and bring the recorded
+    /// pacman version up to date for the rest, e.g. after a reinstall outside khazaur.
+    /// `installed` maps installed pacman packages to their versions.
+    pub fn reconcile(&mut self, installed: &HashMap<String, String>) -> Reconciled {
+        let mut reconciled = Reconciled::default();
+        self.packages.retain(|_, tracked| match installed.get(&tracked.arch_name) {
+            None => {
+                reconciled.dropped.push(tracked.clone());
+                false
+            }
+            Some(version) => {
+                if tracked.arch_version != *version {
+                    tracked.arch_version = version.clone();
+                    reconciled.refreshed.push(tracked.arch_name.clone());
+                }
+                true
+            }
+        });
+        reconciled
+    }
+}
+
+/// What reconciling the database with pacman changed
+#[derive(Debug, Default)]
+pub struct Reconciled {
+    /// Entries of packages that are no longer installed
+    pub dropped: Vec<TrackedPackage>,
+    /// Packages whose recorded pacman version was updated
+    pub refreshed: Vec<String>,
+}
+
+impl Reconciled {
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty() && self.refreshed.is_empty()
+    }
+}
+
+/// Record a converted package after pacman installed it
+pub fn record(tracked: TrackedPackage) -> Result<()> {
+    let mut db = TrackingDb::load()?;
+    db.insert(tracked);
+    db.save()
+}
+
+/// Forget packages after they have been removed
+pub fn forget(arch_names: &[String]) -> Result<()> {
+    let mut db = TrackingDb::load()?;
+    let before = db.packages.len();
+    for name in arch_names {
+        db.remove(name);
+    }
+    if db.packages.len() != before {
+        db.save()?;
+    }
+    Ok(())
+}
+
+/// Load the database and reconcile it with what pacman has installed, returning it
+/// with what changed. Nothing is written; callers that change the system save it.
+pub fn reconcile() -> Result<(TrackingDb, Reconciled)> {
+    let mut db = TrackingDb::load()?;
+    if db.packages.is_empty() {
+        return Ok((db, Reconciled::default()));
+    }
+
+    let installed: HashMap<String, String> = crate::pacman::get_installed_packages()?.into_iter().collect();
+    // pacman couldn't be queried; dropping everything would lose the whole database
+    if installed.is_empty() {
+        return Ok((db, Reconciled::default()));
+    }
+    let reconciled = db.reconcile(&installed);
+    Ok((db, reconciled))
+}
+
+fn db_path() -> Result<PathBuf> {
+    let mut path = dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?;
+    path.push("khazaur");
+    path.push("debian_packages.json");
+    Ok(path)
+}
+
+fn legacy_path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("khazaur").join("debian_packages.txt"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracked(debian_name: &str, arch_name: &str, arch_version: &str) -> TrackedPackage {
+        TrackedPackage {
+            arch_name: arch_name.to_string(),
+            arch_version: arch_version.to_string(),
+            ..TrackedPackage::legacy(debian_name)
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut db = TrackingDb::default();
+        db.insert(tracked("hello", "hello", "2.10-3"));
+        db.insert(tracked("libfoo1", "libfoo1", "1.0-1"));
+        db.insert(tracked("Old-Tool", "old-tool", ""));
+        db.insert(tracked("gone", "gone", "1-1"));
+
+        let installed: HashMap<String, String> = [("hello", "2.10-3"), ("libfoo1", "1.2-1"), ("old-tool", "0.9-1")]
+            .into_iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect();
+
+        let reconciled = db.reconcile(&installed);
+        let dropped: Vec<String> = reconciled.dropped.into_iter().map(|t| t.arch_name).collect();
+        assert_eq!(dropped, vec!["gone"]);
+        assert_eq!(reconciled.refreshed, vec!["libfoo1", "old-tool"]);
+        assert!(db.contains("hello"));
+        assert_eq!(db.get("libfoo1").map(|t| t.arch_version.as_str()), Some("1.2-1"));
+        assert_eq!(db.find("Old-Tool").map(|t| t.arch_version.as_str()), Some("0.9-1"));
+
+        assert!(db.reconcile(&installed).is_empty());
+    }
+}
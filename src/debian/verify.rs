@@ -0,0 +1,117 @@
+use crate::error::{KhazaurError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Check the OpenPGP signature of an InRelease file against a keyring with gpgv,
+/// returning the signed Release text
+pub fn verify_inrelease(inrelease: &Path, keyring: &Path) -> Result<String> {
+    if !keyring.exists() {
+        return Err(KhazaurError::Verification(format!(
+            "Archive keyring {} not found (install debian-archive-keyring, or set `keyring` for the source)",
+            keyring.display()
+        )));
+    }
+
+    let output = Command::new("gpgv")
+        .arg("--keyring")
+        .arg(keyring)
+        .arg("--output")
+        .arg("-")
+        .arg(inrelease)
+        .output()
+        .map_err(|e| KhazaurError::Verification(format!("Failed to run gpgv: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(KhazaurError::Verification(format!(
+            "Bad or missing signature: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files listed in the SHA256 section of a Release file: path -> (hash, size)
+pub fn release_sha256(release: &str) -> HashMap<String, (String, u64)> {
+    let mut files = HashMap::new();
+    let mut in_section = false;
+
+    for line in release.lines() {
+        if !line.starts_with(' ') {
+            in_section = line.trim_end() == "SHA256:";
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        if let (Some(hash), Some(size), Some(path)) = (fields.next(), fields.next(), fields.next())
+            && let Ok(size) = size.parse()
+        {
+            files.insert(path.to_string(), (hash.to_lowercase(), size));
+        }
+    }
+
+    files
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Fail unless data has the expected SHA256 (and size, when known)
+pub fn check_sha256(what: &str, data: &[u8], expected: &str, size: Option<u64>) -> Result<()> {
+    if let Some(size) = size
+        && data.len() as u64 != size
+    {
+        return Err(KhazaurError::Verification(format!(
+            "{}: size mismatch, expected {} bytes, got {}",
+            what,
+            size,
+            data.len()
+        )));
+    }
+
+    let actual = sha256_hex(data);
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(KhazaurError::Verification(format!(
+            "{}: SHA256 mismatch, expected {}, got {}",
+            what, expected, actual
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_sha256() {
+        let release = "\
+Origin: Debian
+Suite: stable
+MD5Sum:
+ 0123 100 main/binary-amd64/Packages.gz
+SHA256:
+ AB12cd 1234 main/binary-amd64/Packages.gz
+ ef56 99 contrib/binary-amd64/Packages
+Acquire-By-Hash: yes
+";
+        let files = release_sha256(release);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files["main/binary-amd64/Packages.gz"], ("ab12cd".to_string(), 1234));
+
+        let hash = sha256_hex(b"abc");
+        assert_eq!(hash, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        assert!(check_sha256("abc", b"abc", &hash, Some(3)).is_ok());
+        assert!(check_sha256("abc", b"abd", &hash, None).is_err());
+    }
+}
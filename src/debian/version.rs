@@ -0,0 +1,86 @@
+use std::cmp::Ordering;
+
+/// Compare two Debian versions (`[epoch:]upstream[-revision]`) the way dpkg does
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (a_epoch, a_upstream, a_revision) = split(a);
+    let (b_epoch, b_upstream, b_revision) = split(b);
+
+    a_epoch
+        .cmp(&b_epoch)
+        .then_with(|| compare_part(a_upstream, b_upstream))
+        .then_with(|| compare_part(a_revision, b_revision))
+}
+
+/// Epoch, upstream version and revision
+pub(super) fn split(version: &str) -> (u64, &str, &str) {
+    let (epoch, rest) = match version.split_once(':') {
+        Some((epoch, rest)) => (epoch.parse().unwrap_or(0), rest),
+        None => (0, version),
+    };
+    match rest.rsplit_once('-') {
+        Some((upstream, revision)) => (epoch, upstream, revision),
+        None => (epoch, rest, ""),
+    }
+}
+
+/// Alternate between non-digit runs, compared with `~` sorting first and letters
+/// before other characters, and digit runs, compared numerically
+fn compare_part(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.as_bytes(), b.as_bytes());
+
+    while !a.is_empty() || !b.is_empty() {
+        let a_text = a.iter().take_while(|c| !c.is_ascii_digit()).count();
+        let b_text = b.iter().take_while(|c| !c.is_ascii_digit()).count();
+        let order = compare_text(&a[..a_text], &b[..b_text]);
+        if order != Ordering::Equal {
+            return order;
+        }
+        (a, b) = (&a[a_text..], &b[b_text..]);
+
+        let a_digits = a.iter().take_while(|c| c.is_ascii_digit()).count();
+        let b_digits = b.iter().take_while(|c| c.is_ascii_digit()).count();
+        let order = number(&a[..a_digits]).cmp(&number(&b[..b_digits]));
+        if order != Ordering::Equal {
+            return order;
+        }
+        (a, b) = (&a[a_digits..], &b[b_digits..]);
+    }
+
+    Ordering::Equal
+}
+
+fn compare_text(a: &[u8], b: &[u8]) -> Ordering {
+    let weight = |c: Option<&u8>| match c {
+        Some(b'~') => -1,
+        None => 0,
+        Some(c) if c.is_ascii_alphabetic() => *c as i32,
+        Some(c) => *c as i32 + 256,
+    };
+
+    for i in 0..a.len().max(b.len()) {
+        let order = weight(a.get(i)).cmp(&weight(b.get(i)));
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    Ordering::Equal
+}
+
+fn number(digits: &[u8]) -> u128 {
+    digits.iter().fold(0u128, |n, d| n.saturating_mul(10).saturating_add(u128::from(d - b'0')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare("1.2-1", "1.10-1"), Ordering::Less);
+        assert_eq!(compare("1:0.9", "2.0"), Ordering::Greater);
+        assert_eq!(compare("1.0~rc1-1", "1.0-1"), Ordering::Less);
+        assert_eq!(compare("1.4-1~bpo12+1", "1.4-1"), Ordering::Less);
+        assert_eq!(compare("1.0a", "1.0+"), Ordering::Less);
+        assert_eq!(compare("2.36-9+deb12u4", "2.36-9+deb12u4"), Ordering::Equal);
+    }
+}
@@ -1,21 +1,54 @@
 use crate::error::Result;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-/// Get the khazaur cache directory
-pub fn cache_dir() -> Result<PathBuf> {
+/// Directories from the loaded configuration
+struct Configured {
+    cache: PathBuf,
+    clone: PathBuf,
+    pkg: PathBuf,
+}
+
+static CONFIGURED: OnceLock<Configured> = OnceLock::new();
+
+/// Remember the directories the configuration resolved to, so code without
+/// access to the config uses them too. The first loaded configuration wins.
+pub fn init(cache: &Path, clone: &Path, pkg: &Path) {
+    let _ = CONFIGURED.set(Configured {
+        cache: cache.to_path_buf(),
+        clone: clone.to_path_buf(),
+        pkg: pkg.to_path_buf(),
+    });
+}
+
+/// Cache directory used when the configuration doesn't set one (~/.cache/khazaur)
+pub fn default_cache_dir() -> Result<PathBuf> {
     Ok(dirs::cache_dir()
         .ok_or_else(|| crate::error::KhazaurError::Config("Could not determine cache directory".to_string()))?
         .join("khazaur"))
 }
 
+/// Get the khazaur cache directory
+pub fn cache_dir() -> Result<PathBuf> {
+    match CONFIGURED.get() {
+        Some(dirs) => Ok(dirs.cache.clone()),
+        None => default_cache_dir(),
+    }
+}
+
 /// Get the clone directory for PKGBUILDs
-#[allow(dead_code)]
 pub fn clone_dir() -> Result<PathBuf> {
-    Ok(cache_dir()?.join("clone"))
+    match CONFIGURED.get() {
+        Some(dirs) => Ok(dirs.clone.clone()),
+        None => Ok(cache_dir()?.join("clone")),
+    }
 }
 
 /// Get the package cache directory
 #[allow(dead_code)]
 pub fn pkg_dir() -> Result<PathBuf> {
-    Ok(cache_dir()?.join("pkg"))
+    match CONFIGURED.get() {
+        Some(dirs) => Ok(dirs.pkg.clone()),
+        None => Ok(cache_dir()?.join("pkg")),
+    }
 }
@@ -20,6 +20,33 @@ pub enum KhazaurError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("News feed error: {0}")]
+    News(String),
+
+    #[error("Security tracker error: {0}")]
+    Security(String),
+
+    #[error("Verification failed: {0}")]
+    Verification(String),
+
+    #[error("Package conversion failed: {0}")]
+    Conversion(String),
+
+    #[error("AppImage error: {0}")]
+    AppImage(String),
+
+    #[error("History error: {0}")]
+    History(String),
+
+    #[error("Rollback incomplete: {0}")]
+    Rollback(String),
+
+    #[error("pacdiff error: {0}")]
+    Pacdiff(String),
+
+    #[error("Backend plugin error: {0}")]
+    Plugin(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -117,3 +117,204 @@ pub fn uninstall_flatpak(app_id: &str) -> Result<()> {
     
     Ok(())
 }
+
+/// Check if a flatpak ref (application or runtime) is installed
+pub fn is_flatpak_ref_installed(ref_id: &str) -> Result<bool> {
+    if !super::is_available() {
+        return Ok(false);
+    }
+
+    let output = Command::new("flatpak")
+        .args(["info", ref_id])
+        .output()?;
+
+    Ok(output.status.success())
+}
+
+/// Get the ids of installed runtimes
+pub fn get_installed_runtimes() -> Result<Vec<String>> {
+    if !super::is_available() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("flatpak")
+        .args(["list", "--runtime", "--columns=application"])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect())
+}
+
+/// Get installed applications with the runtime each one uses, as (app_id, runtime_id)
+pub fn get_app_runtimes() -> Result<Vec<(String, String)>> {
+    let apps = get_app_runtime_refs()?
+        .into_iter()
+        .filter_map(|(app, runtime)| {
+            let id = runtime.split('/').next()?.to_string();
+            Some((app, id))
+        })
+        .collect();
+
+    Ok(apps)
+}
+
+/// Get installed applications with the full runtime ref each one uses,
+/// as (app_id, runtime_ref) where runtime_ref looks like org.gnome.Platform/x86_64/46
+pub fn get_app_runtime_refs() -> Result<Vec<(String, String)>> {
+    if !super::is_available() {
+        return Ok(Vec::new());
+    }
+
+    let output = Command::new("flatpak")
+        .args(["list", "--app", "--columns=application,runtime"])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let apps = stdout
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let app = parts.next()?.trim();
+            let runtime = parts.next()?.trim();
+            if runtime.is_empty() {
+                return None;
+            }
+            Some((app.to_string(), runtime.to_string()))
+        })
+        .collect();
+
+    Ok(apps)
+}
+
+/// Get the installed size of a flatpak ref in bytes
+pub fn get_installed_size(ref_id: &str) -> Option<u64> {
+    let output = Command::new("flatpak")
+        .args(["info", "--show-size", ref_id])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_size(String::from_utf8_lossy(&output.stdout).trim())
+}
+
+/// Sizes and runtime of a ref available from a remote
+#[derive(Debug, Clone, Default)]
+pub struct RemoteRefInfo {
+    pub download_size: Option<u64>,
+    pub installed_size: Option<u64>,
+    /// Runtime ref, only set for applications
+    pub runtime: Option<String>,
+}
+
+/// Look up a ref on the flathub remote without installing it
+pub fn remote_info(ref_id: &str) -> Option<RemoteRefInfo> {
+    let output = Command::new("flatpak")
+        .args(["remote-info", "flathub", ref_id])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut info = RemoteRefInfo::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "Download" => info.download_size = parse_size(value),
+            "Installed" => info.installed_size = parse_size(value),
+            "Runtime" => info.runtime = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Some(info)
+}
+
+/// Parse a size as printed by flatpak: plain bytes or a GLib formatted size like "1.2 GB"
+fn parse_size(text: &str) -> Option<u64> {
+    if let Ok(bytes) = text.parse() {
+        return Some(bytes);
+    }
+
+    let (number, unit) = text.split_once(char::is_whitespace)?;
+    let number: f64 = number.replace(',', ".").parse().ok()?;
+    // GLib uses SI units by default
+    let multiplier = match unit.trim() {
+        "bytes" | "byte" | "B" => 1.0,
+        "kB" | "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+
+    Some((number * multiplier) as u64)
+}
+
+/// Version and deployed commit of an installed flatpak ref
+pub fn installed_commit(ref_id: &str) -> Option<(Option<String>, String)> {
+    let output = Command::new("flatpak")
+        .args(["info", ref_id])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut version = None;
+    let mut commit = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        match key.trim() {
+            "Version" => version = Some(value.trim().to_string()),
+            "Commit" => commit = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    Some((version, commit?))
+}
+
+/// Deploy a specific commit of a ref, installing it from flathub first if needed
+pub fn deploy_commit(ref_id: &str, commit: &str) -> Result<()> {
+    if !is_flatpak_ref_installed(ref_id)? {
+        let status = Command::new("flatpak")
+            .args(["install", "-y", "flathub", ref_id])
+            .status()?;
+        if !status.success() {
+            return Err(KhazaurError::Config(format!("Failed to install flatpak: {}", ref_id)));
+        }
+    }
+
+    let status = Command::new("flatpak")
+        .args(["update", "-y", &format!("--commit={}", commit), ref_id])
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::Config(format!("Failed to deploy commit {} of {}", commit, ref_id)));
+    }
+
+    Ok(())
+}
@@ -89,7 +89,7 @@ pub async fn fetch_suggested_remotes() -> Result<Vec<SuggestedRemote>> {
     // URL containing the list of remotes
     let url = "https://raw.githubusercontent.com/os-guy-original/flatpak-remotes/main/README.md";
     
-    let response = reqwest::get(url).await
+    let response = crate::net::get(url).await
         .map_err(|e| KhazaurError::Config(format!("Failed to fetch remotes list: {}", e)))?
         .text().await
         .map_err(|e| KhazaurError::Config(format!("Failed to read response body: {}", e)))?;
@@ -1,28 +1,250 @@
+use crate::cli::SourceKind;
 use crate::error::Result;
-use chrono::Local;
+use crate::pacman::db::PackageDb;
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Display;
 use std::fs::OpenOptions;
-use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HistoryEntry {
+    /// Transaction id, 0 for entries written before ids were recorded
+    #[serde(default)]
+    pub id: u64,
     pub timestamp: String,
     pub action: String,
     pub packages: Vec<String>,
     pub success: bool,
+    /// Versions before and after the transaction, per package
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<PackageChange>,
+    /// khazaur command line that ran the transaction
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub command: String,
+    #[serde(default)]
+    pub duration_ms: u64,
+    /// Why the transaction failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Origin::is_khazaur")]
+    pub origin: Origin,
 }
 
-pub fn log_action(action: &str, packages: &[String], success: bool) -> Result<()> {
-    let entry = HistoryEntry {
-        timestamp: Local::now().to_rfc3339(),
-        action: action.to_string(),
-        packages: packages.to_vec(),
-        success,
+/// Who recorded a history entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    #[default]
+    Khazaur,
+    /// Imported from pacman.log, for operations run with plain pacman
+    Pacman,
+}
+
+impl Origin {
+    fn is_khazaur(&self) -> bool {
+        *self == Origin::Khazaur
+    }
+}
+
+impl HistoryEntry {
+    /// Local time the transaction was recorded at
+    pub fn time(&self) -> Option<DateTime<Local>> {
+        DateTime::parse_from_rfc3339(&self.timestamp)
+            .ok()
+            .map(|dt| dt.with_timezone(&Local))
+    }
+
+    /// Whether the transaction touched a package, by name
+    pub fn involves(&self, package: &str) -> bool {
+        self.packages.iter().any(|p| p == package) || self.changes.iter().any(|c| c.name == package)
+    }
+}
+
+/// Criteria for selecting history entries; unset fields match everything
+#[derive(Debug, Default, Clone)]
+pub struct HistoryFilter {
+    pub package: Option<String>,
+    pub source: Option<SourceKind>,
+    pub action: Option<String>,
+    /// First day to include
+    pub since: Option<NaiveDate>,
+    /// Last day to include
+    pub until: Option<NaiveDate>,
+    pub failed_only: bool,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        if self.failed_only && entry.success {
+            return false;
+        }
+        if self.package.as_ref().is_some_and(|package| !entry.involves(package)) {
+            return false;
+        }
+        if self.source.is_some_and(|source| !entry.changes.iter().any(|c| c.source == source)) {
+            return false;
+        }
+        if self.action.as_ref().is_some_and(|action| !entry.action.eq_ignore_ascii_case(action)) {
+            return false;
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(date) = entry.time().map(|t| t.date_naive()) else {
+                return false;
+            };
+            if self.since.is_some_and(|since| date < since) || self.until.is_some_and(|until| date > until) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// An installed version of a package
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledVersion {
+    pub version: String,
+    /// Flatpak commit or snap revision the version was deployed as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+}
+
+/// State of one package around a transaction. `None` means not installed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageChange {
+    pub source: SourceKind,
+    pub name: String,
+    pub before: Option<InstalledVersion>,
+    pub after: Option<InstalledVersion>,
+}
+
+impl PackageChange {
+    pub fn changed(&self) -> bool {
+        self.before != self.after
+    }
+}
+
+/// A transaction being recorded. Versions are captured when it begins and again
+/// when it finishes, so history can show and roll back what actually changed.
+pub struct Transaction {
+    action: String,
+    packages: Vec<(SourceKind, String)>,
+    before: Vec<Option<InstalledVersion>>,
+    started: Instant,
+}
+
+impl Transaction {
+    /// Begin a transaction on packages from a single source
+    pub fn begin(action: &str, source: SourceKind, names: &[String]) -> Self {
+        Self::begin_mixed(action, names.iter().map(|name| (source, name.clone())).collect())
+    }
+
+    /// Begin a transaction on packages from several sources
+    pub fn begin_mixed(action: &str, packages: Vec<(SourceKind, String)>) -> Self {
+        let before = snapshot(&packages);
+        Self {
+            action: action.to_string(),
+            packages,
+            before,
+            started: Instant::now(),
+        }
+    }
+
+    /// Record the transaction in the history and return its id
+    pub fn finish(self, success: bool) -> Result<u64> {
+        self.record(success, None)
+    }
+
+    /// Record the transaction as failed with the error that stopped it
+    pub fn fail(self, error: &impl Display) -> Result<u64> {
+        self.record(false, Some(error.to_string()))
+    }
+
+    fn record(self, success: bool, error: Option<String>) -> Result<u64> {
+        let duration_ms = self.started.elapsed().as_millis() as u64;
+        let after = snapshot(&self.packages);
+        let changes = self
+            .packages
+            .iter()
+            .zip(self.before)
+            .zip(after)
+            .map(|(((source, name), before), after)| PackageChange {
+                source: *source,
+                name: name.clone(),
+                before,
+                after,
+            })
+            .collect();
+
+        let id = last_id()? + 1;
+        let entry = HistoryEntry {
+            id,
+            timestamp: Local::now().to_rfc3339(),
+            action: self.action,
+            packages: self.packages.into_iter().map(|(_, name)| name).collect(),
+            success,
+            changes,
+            command: std::env::args().collect::<Vec<_>>().join(" "),
+            duration_ms,
+            error,
+            origin: Origin::Khazaur,
+        };
+        append(&entry)?;
+
+        Ok(id)
+    }
+}
+
+/// Installed version of each package, in order
+fn snapshot(packages: &[(SourceKind, String)]) -> Vec<Option<InstalledVersion>> {
+    let local = if packages.iter().any(|(source, _)| source.is_pacman()) {
+        PackageDb::load_local().ok()
+    } else {
+        None
     };
 
+    packages
+        .iter()
+        .map(|(source, name)| match source {
+            SourceKind::Repo | SourceKind::Aur | SourceKind::Debian => {
+                local.as_ref()?.get(name).map(|pkg| InstalledVersion {
+                    version: pkg.version.clone(),
+                    revision: None,
+                })
+            }
+            SourceKind::Flatpak => {
+                let (version, commit) = crate::flatpak::install::installed_commit(name)?;
+                Some(InstalledVersion {
+                    version: version.unwrap_or_else(|| commit.chars().take(12).collect()),
+                    revision: Some(commit),
+                })
+            }
+            SourceKind::Snap => {
+                let (version, revision) = crate::snap::installed_revision(name)?;
+                Some(InstalledVersion {
+                    version,
+                    revision: Some(revision),
+                })
+            }
+            SourceKind::AppImage => {
+                let (version, sha1) = crate::appimage::installed_version(name)?;
+                Some(InstalledVersion {
+                    version,
+                    revision: Some(sha1),
+                })
+            }
+            // Plugins are only asked for versions when listing
+            SourceKind::Plugin => None,
+        })
+        .collect()
+}
+
+fn append(entry: &HistoryEntry) -> Result<()> {
     let log_path = get_history_path()?;
-    
+
     // Ensure directory exists
     if let Some(parent) = log_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -33,39 +255,252 @@ pub fn log_action(action: &str, packages: &[String], success: bool) -> Result<()
         .append(true)
         .open(log_path)?;
 
-    let json = serde_json::to_string(&entry)?;
+    let json = serde_json::to_string(entry)?;
     writeln!(file, "{}", json)?;
 
     Ok(())
 }
 
-pub fn get_history(limit: usize) -> Result<Vec<HistoryEntry>> {
+/// Stream the history file, oldest entry first, skipping lines that don't parse
+fn for_each_entry(mut f: impl FnMut(HistoryEntry) -> bool) -> Result<()> {
     let log_path = get_history_path()?;
     if !log_path.exists() {
-        return Ok(Vec::new());
+        return Ok(());
     }
 
-    let file = std::fs::File::open(log_path)?;
-    let reader = BufReader::new(file);
-    let mut entries = Vec::new();
+    let reader = BufReader::new(std::fs::File::open(log_path)?);
+    for line in reader.lines().map_while(std::result::Result::ok) {
+        if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&line)
+            && !f(entry)
+        {
+            break;
+        }
+    }
 
-    for line in reader.lines() {
-        if let Ok(l) = line {
-            if let Ok(entry) = serde_json::from_str::<HistoryEntry>(&l) {
-                entries.push(entry);
-            }
+    Ok(())
+}
+
+/// Id of the newest transaction. Ids only grow, so reading the tail of the file is enough.
+fn last_id() -> Result<u64> {
+    const TAIL: u64 = 64 * 1024;
+
+    let log_path = get_history_path()?;
+    if !log_path.exists() {
+        return Ok(0);
+    }
+
+    let mut file = std::fs::File::open(&log_path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL)))?;
+    // The seek may land inside a character, the partial first line is skipped anyway
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    let tail = String::from_utf8_lossy(&tail);
+
+    let last = tail
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .map(|e| e.id)
+        .find(|id| *id > 0);
+
+    match last {
+        Some(id) => Ok(id),
+        // Only old entries without ids near the end, or an unusually long line
+        None if len > TAIL => {
+            let mut max = 0;
+            for_each_entry(|e| {
+                max = max.max(e.id);
+                true
+            })?;
+            Ok(max)
         }
+        None => Ok(0),
     }
+}
+
+/// Entries matching the filter, newest first, at most `limit` of them
+pub fn query(filter: &HistoryFilter, limit: Option<usize>) -> Result<Vec<HistoryEntry>> {
+    let mut entries = VecDeque::new();
+    for_each_entry(|entry| {
+        if filter.matches(&entry) {
+            push_limited(&mut entries, entry, limit);
+        }
+        true
+    })?;
 
-    // Return last 'limit' entries
-    Ok(entries.into_iter().rev().take(limit).collect())
+    Ok(entries.into_iter().rev().collect())
+}
+
+/// Append an entry, keeping only the last `limit` ones
+fn push_limited<T>(entries: &mut VecDeque<T>, entry: T, limit: Option<usize>) {
+    match limit {
+        Some(0) => {}
+        Some(limit) if entries.len() == limit => {
+            entries.pop_front();
+            entries.push_back(entry);
+        }
+        _ => entries.push_back(entry),
+    }
+}
+
+/// Like `query`, with the transactions from a pacman log merged in by time.
+/// Log transactions khazaur recorded itself are left out.
+pub fn query_merged(filter: &HistoryFilter, limit: Option<usize>, pacman_log: &Path) -> Result<Vec<HistoryEntry>> {
+    let own = query(filter, limit)?;
+
+    // When and on what khazaur ran pacman, to recognize its transactions in the log
+    let mut windows = Vec::new();
+    for_each_entry(|entry| {
+        let names: HashSet<String> = entry
+            .changes
+            .iter()
+            .filter(|c| c.source.is_pacman())
+            .map(|c| c.name.clone())
+            .collect();
+        if let Some(end) = entry.time().filter(|_| !names.is_empty()) {
+            let start = end - chrono::Duration::milliseconds(entry.duration_ms as i64);
+            windows.push((start, end, names));
+        }
+        true
+    })?;
+
+    let foreign: HashSet<String> = crate::pacman::get_installed_aur_packages()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    let debian = crate::debian::load_tracked_packages().unwrap_or_default();
+    let source_of = |name: &str| {
+        if debian.contains(name) {
+            SourceKind::Debian
+        } else if foreign.contains(name) {
+            SourceKind::Aur
+        } else {
+            SourceKind::Repo
+        }
+    };
+
+    // Allow for the clock moving between pacman logging and khazaur recording
+    let slack = chrono::Duration::seconds(5);
+    let mut imported = VecDeque::new();
+    for tx in crate::pacman::log::read_transactions(pacman_log)? {
+        let recorded = windows.iter().any(|(start, end, names)| {
+            tx.time >= *start - slack
+                && tx.time <= *end + slack
+                && tx.events.iter().any(|e| names.contains(&e.name))
+        });
+        if recorded {
+            continue;
+        }
+
+        let entry = from_log(tx, &source_of);
+        if filter.matches(&entry) {
+            push_limited(&mut imported, entry, limit);
+        }
+    }
+
+    // Both lists are sorted, merge them newest first
+    let mut merged = Vec::with_capacity(own.len() + imported.len());
+    let mut own = own.into_iter().peekable();
+    let mut imported = imported.into_iter().rev().peekable();
+    while limit.is_none_or(|limit| merged.len() < limit) {
+        let take_own = match (own.peek(), imported.peek()) {
+            (Some(a), Some(b)) => a.time() >= b.time(),
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        let next = if take_own { own.next() } else { imported.next() };
+        merged.extend(next);
+    }
+
+    Ok(merged)
+}
+
+/// A pacman log transaction as a history entry, named after its main effect
+fn from_log(tx: crate::pacman::log::LogTransaction, source_of: &impl Fn(&str) -> SourceKind) -> HistoryEntry {
+    use crate::pacman::log::LogAction;
+
+    let has = |action: LogAction| tx.events.iter().any(|e| e.action == action);
+    let action = if has(LogAction::Upgraded) {
+        "update"
+    } else if has(LogAction::Downgraded) {
+        "downgrade"
+    } else if has(LogAction::Installed) || has(LogAction::Reinstalled) {
+        "install"
+    } else {
+        "remove"
+    };
+
+    let version = |v: Option<String>| v.map(|version| InstalledVersion { version, revision: None });
+    let packages = tx.events.iter().map(|e| e.name.clone()).collect();
+    let changes = tx
+        .events
+        .into_iter()
+        .map(|e| PackageChange {
+            source: source_of(&e.name),
+            name: e.name,
+            before: version(e.old_version),
+            after: version(e.new_version),
+        })
+        .collect();
+
+    HistoryEntry {
+        id: 0,
+        timestamp: tx.time.to_rfc3339(),
+        action: action.to_string(),
+        packages,
+        success: tx.success,
+        changes,
+        command: tx.command.unwrap_or_default(),
+        duration_ms: 0,
+        error: None,
+        origin: Origin::Pacman,
+    }
+}
+
+/// Look up a transaction by id
+pub fn get_entry(id: u64) -> Result<Option<HistoryEntry>> {
+    let mut found = None;
+    if id != 0 {
+        for_each_entry(|entry| {
+            if entry.id == id {
+                found = Some(entry);
+                return false;
+            }
+            true
+        })?;
+    }
+    Ok(found)
 }
 
 fn get_history_path() -> Result<PathBuf> {
     // Use data_local_dir for persistent history storage (~/.local/share/khazaur/history.jsonl)
-    
+
     let mut path = dirs::data_local_dir().ok_or(crate::error::KhazaurError::Config("Could not determine data directory".into()))?;
     path.push("khazaur");
     path.push("history.jsonl");
     Ok(path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pushed(limit: Option<usize>) -> Vec<u32> {
+        let mut entries = VecDeque::new();
+        for entry in 1..=5 {
+            push_limited(&mut entries, entry, limit);
+        }
+        entries.into_iter().collect()
+    }
+
+    #[test]
+    fn limits_keep_the_latest_entries() {
+        assert_eq!(pushed(None), vec![1, 2, 3, 4, 5]);
+        assert_eq!(pushed(Some(2)), vec![4, 5]);
+        assert_eq!(pushed(Some(10)), vec![1, 2, 3, 4, 5]);
+        assert!(pushed(Some(0)).is_empty());
+    }
+}
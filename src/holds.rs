@@ -0,0 +1,145 @@
+use crate::cli::SourceKind;
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Packages held at their installed version, per source.
+/// Stored in ~/.local/share/khazaur/holds.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct HoldDb {
+    #[serde(default)]
+    packages: BTreeMap<SourceKind, BTreeSet<String>>,
+}
+
+impl HoldDb {
+    pub fn load() -> Result<Self> {
+        let path = db_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_held(&self, source: SourceKind, name: &str) -> bool {
+        self.packages.get(&source).is_some_and(|names| names.contains(name))
+    }
+
+    pub fn add(&mut self, source: SourceKind, name: &str) {
+        self.packages.entry(source).or_default().insert(name.to_string());
+    }
+
+    pub fn remove(&mut self, source: SourceKind, name: &str) {
+        if let Some(names) = self.packages.get_mut(&source) {
+            names.remove(name);
+        }
+    }
+
+    /// Every hold as (source, name), grouped by source
+    pub fn all(&self) -> Vec<(SourceKind, String)> {
+        self.packages
+            .iter()
+            .flat_map(|(source, names)| names.iter().map(|name| (*source, name.clone())))
+            .collect()
+    }
+}
+
+/// Hold a package at its installed version.
+/// The hold is also applied to the source's own tool so upgrades run outside
+/// khazaur respect it: IgnorePkg for pacman, `flatpak mask` and `snap refresh --hold`.
+pub fn hold(source: SourceKind, name: &str) -> Result<()> {
+    apply(source, name, true)?;
+
+    let mut db = HoldDb::load()?;
+    db.add(source, name);
+    db.save()
+}
+
+/// Release a held package
+pub fn unhold(source: SourceKind, name: &str) -> Result<()> {
+    apply(source, name, false)?;
+
+    let mut db = HoldDb::load()?;
+    db.remove(source, name);
+    db.save()
+}
+
+fn apply(source: SourceKind, name: &str, held: bool) -> Result<()> {
+    let mut command = match source {
+        SourceKind::Repo | SourceKind::Aur | SourceKind::Debian => {
+            return crate::pacman::repos::set_ignored(name, held);
+        }
+        SourceKind::Flatpak => {
+            let mut cmd = Command::new("flatpak");
+            cmd.arg("mask");
+            if !held {
+                cmd.arg("--remove");
+            }
+            cmd.arg(name);
+            cmd
+        }
+        // Only khazaur updates AppImages and plugin packages, its own record is enough
+        SourceKind::AppImage | SourceKind::Plugin => return Ok(()),
+        SourceKind::Snap => {
+            let mut cmd = Command::new("sudo");
+            cmd.args(["snap", "refresh", if held { "--hold" } else { "--unhold" }, name]);
+            cmd
+        }
+    };
+
+    let status = command.status()?;
+    if !status.success() {
+        return Err(KhazaurError::Config(format!(
+            "Failed to update the {} hold for {}",
+            source.label(),
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn db_path() -> Result<PathBuf> {
+    let mut path = dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?;
+    path.push("khazaur");
+    path.push("holds.json");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove_holds() {
+        let mut db = HoldDb::default();
+        db.add(SourceKind::Repo, "linux");
+        db.add(SourceKind::Flatpak, "org.mozilla.firefox");
+        db.add(SourceKind::Repo, "linux");
+
+        assert!(db.is_held(SourceKind::Repo, "linux"));
+        assert!(!db.is_held(SourceKind::Aur, "linux"));
+        assert_eq!(db.all(), vec![
+            (SourceKind::Repo, "linux".to_string()),
+            (SourceKind::Flatpak, "org.mozilla.firefox".to_string()),
+        ]);
+
+        db.remove(SourceKind::Repo, "linux");
+        db.remove(SourceKind::Snap, "discord");
+        assert!(!db.is_held(SourceKind::Repo, "linux"));
+        assert_eq!(db.all(), vec![(SourceKind::Flatpak, "org.mozilla.firefox".to_string())]);
+    }
+}
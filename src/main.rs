@@ -11,7 +11,14 @@ mod flatpak;
 mod snap;
 mod debtap;
 mod debian;
+mod appimage;
+mod plugin;
 mod history;
+mod news;
+mod net;
+mod security;
+mod reasons;
+mod holds;
 
 use anyhow::Result;
 use clap::Parser;
@@ -0,0 +1,165 @@
+use crate::aur::retry::{retry_request, RetryConfig};
+use crate::config::Config;
+use crate::error::{KhazaurError, Result};
+use reqwest::{Certificate, Client, Proxy, Response};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Network settings from the configuration, shared by every HTTP client
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// AUR base URL, without a trailing slash
+    pub aur_url: String,
+    /// HTTP(S) or SOCKS proxy; None uses the *_proxy environment variables
+    pub proxy: Option<String>,
+    /// PEM bundle trusted in addition to the system certificates
+    pub ca_bundle: Option<PathBuf>,
+    pub connect_timeout: Duration,
+    /// Longest wait for data before a request is abandoned
+    pub read_timeout: Duration,
+    pub retry: RetryConfig,
+    pub max_concurrent_requests: usize,
+    pub request_delay_ms: u64,
+}
+
+impl Settings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            aur_url: config.aur_url.trim_end_matches('/').to_string(),
+            proxy: config.proxy.clone().filter(|p| !p.is_empty()),
+            ca_bundle: config.ca_bundle.clone(),
+            connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+            read_timeout: Duration::from_secs(config.read_timeout_secs),
+            retry: RetryConfig {
+                max_retries: config.max_retries,
+                initial_backoff_ms: config.retry_backoff_ms,
+                ..RetryConfig::default()
+            },
+            max_concurrent_requests: config.max_concurrent_requests,
+            request_delay_ms: config.request_delay_ms,
+        }
+    }
+
+    /// Build an HTTP client using the proxy, CA bundle and timeouts
+    pub fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .connect_timeout(self.connect_timeout)
+            .read_timeout(self.read_timeout)
+            .user_agent(format!("khazaur/{}", env!("CARGO_PKG_VERSION")));
+
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|e| KhazaurError::Config(format!("Invalid proxy '{}': {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.ca_bundle {
+            for cert in read_ca_bundle(path)? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| KhazaurError::Config(format!("Failed to create HTTP client: {}", e)))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            aur_url: crate::config::DEFAULT_AUR_URL.to_string(),
+            proxy: None,
+            ca_bundle: None,
+            connect_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(30),
+            retry: RetryConfig::default(),
+            max_concurrent_requests: 10,
+            request_delay_ms: 100,
+        }
+    }
+}
+
+fn read_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .map_err(|e| KhazaurError::Config(format!("Failed to read CA bundle {}: {}", path.display(), e)))?;
+    Certificate::from_pem_bundle(&pem)
+        .map_err(|e| KhazaurError::Config(format!("Invalid CA bundle {}: {}", path.display(), e)))
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Remember the network settings of the loaded configuration. The first loaded
+/// configuration wins.
+pub fn init(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+/// Settings of the loaded configuration, or the defaults
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// The shared HTTP client, built on first use
+pub fn client() -> Result<Client> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client.clone());
+    }
+
+    let client = settings().build_client()?;
+    Ok(CLIENT.get_or_init(|| client).clone())
+}
+
+/// GET a URL with the shared client, retrying network errors and temporary failures.
+/// Unsuccessful responses are returned for the caller to report.
+pub async fn get(url: &str) -> Result<Response> {
+    let client = client()?;
+    retry_request(|| client.get(url).send(), &settings().retry)
+        .await
+        .map_err(|e| KhazaurError::DownloadFailed(format!("{}: {}", url, e)))
+}
+
+/// Read a remote or local resource: an HTTP(S) URL, a `file://` URL or a plain path.
+/// Used for feeds whose location is configurable so they can point at a local copy.
+pub async fn read_source(source: &str) -> Result<Vec<u8>> {
+    if source.starts_with("http://") || source.starts_with("https://") {
+        let response = get(source).await?;
+        if !response.status().is_success() {
+            return Err(KhazaurError::DownloadFailed(format!(
+                "{}: HTTP {}",
+                source,
+                response.status()
+            )));
+        }
+        return Ok(response.bytes().await?.to_vec());
+    }
+
+    let path = source.strip_prefix("file://").unwrap_or(source);
+    Ok(std::fs::read(path)?)
+}
+
+/// git fetch options using the configured proxy, or git's own proxy settings
+pub fn fetch_options<'a>() -> git2::FetchOptions<'a> {
+    let mut proxy = git2::ProxyOptions::new();
+    match &settings().proxy {
+        Some(url) => {
+            proxy.url(url);
+        }
+        None => {
+            proxy.auto();
+        }
+    }
+
+    let mut options = git2::FetchOptions::new();
+    options.proxy_options(proxy);
+    options
+}
+
+/// Clone a git repository through the configured proxy
+pub fn clone_repo(url: &str, path: &Path) -> std::result::Result<git2::Repository, git2::Error> {
+    git2::build::RepoBuilder::new()
+        .fetch_options(fetch_options())
+        .clone(url, path)
+}
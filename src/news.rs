@@ -0,0 +1,268 @@
+use crate::error::{KhazaurError, Result};
+use chrono::{DateTime, Duration, FixedOffset, Local, Utc};
+use colored::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// Default Arch Linux news feed
+pub const DEFAULT_NEWS_URL: &str = "https://archlinux.org/feeds/news/";
+
+/// How far back to look for unread news when no upgrade has been recorded yet
+const FIRST_RUN_WINDOW_DAYS: i64 = 14;
+
+/// A single item from the news feed
+#[derive(Debug, Clone)]
+pub struct NewsItem {
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub published: Option<DateTime<FixedOffset>>,
+    pub body: String,
+}
+
+/// Persistent news state (~/.local/share/khazaur/news.json)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NewsState {
+    #[serde(default)]
+    pub seen: BTreeSet<String>,
+    #[serde(default)]
+    pub last_upgrade: Option<DateTime<Utc>>,
+}
+
+impl NewsState {
+    pub fn load() -> Result<Self> {
+        let path = state_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents).unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = state_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn is_seen(&self, item: &NewsItem) -> bool {
+        self.seen.contains(&item.id)
+    }
+
+    pub fn mark_seen<'a>(&mut self, items: impl IntoIterator<Item = &'a NewsItem>) {
+        for item in items {
+            self.seen.insert(item.id.clone());
+        }
+    }
+
+    /// Unread items published since the last recorded upgrade
+    pub fn unread_since_last_upgrade<'a>(&self, items: &'a [NewsItem]) -> Vec<&'a NewsItem> {
+        let cutoff = self
+            .last_upgrade
+            .unwrap_or_else(|| Utc::now() - Duration::days(FIRST_RUN_WINDOW_DAYS));
+
+        items
+            .iter()
+            .filter(|item| !self.is_seen(item))
+            .filter(|item| match item.published {
+                Some(date) => date.with_timezone(&Utc) >= cutoff,
+                // Undated items can't be placed in time, show them until read
+                None => true,
+            })
+            .collect()
+    }
+}
+
+/// Fetch and parse the news feed from a URL, `file://` URL or local path
+pub async fn fetch(source: &str) -> Result<Vec<NewsItem>> {
+    let bytes = crate::net::read_source(source).await?;
+    parse_feed(&bytes)
+}
+
+/// Parse an RSS document into news items, newest first
+pub fn parse_feed(bytes: &[u8]) -> Result<Vec<NewsItem>> {
+    let channel = rss::Channel::read_from(bytes)
+        .map_err(|e| KhazaurError::News(e.to_string()))?;
+
+    let mut items: Vec<NewsItem> = channel
+        .items()
+        .iter()
+        .filter_map(|item| {
+            let title = item.title()?.trim().to_string();
+            let link = item.link().map(|l| l.trim().to_string());
+            let id = item
+                .guid()
+                .map(|g| g.value().trim().to_string())
+                .or_else(|| link.clone())
+                .unwrap_or_else(|| title.clone());
+            let published = item
+                .pub_date()
+                .and_then(|d| DateTime::parse_from_rfc2822(d.trim()).ok());
+            let body = item.description().map(html_to_text).unwrap_or_default();
+
+            Some(NewsItem { id, title, link, published, body })
+        })
+        .collect();
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.published));
+    Ok(items)
+}
+
+/// Reduce the HTML of a news item to readable plain text
+pub fn html_to_text(html: &str) -> String {
+    let breaks = Regex::new(r"(?i)<br\s*/?>|</p>|</li>|</h\d>|</pre>").unwrap();
+    let bullets = Regex::new(r"(?i)<li[^>]*>").unwrap();
+    let tags = Regex::new(r"<[^>]+>").unwrap();
+
+    let text = breaks.replace_all(html, "\n");
+    let text = bullets.replace_all(&text, "\n• ");
+    let text = tags.replace_all(&text, "");
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        // Collapse runs of blank lines into a single paragraph break
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Print a news item
+pub fn print_item(item: &NewsItem, unread: bool) {
+    let date = item
+        .published
+        .map(|d| d.with_timezone(&Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "unknown date".to_string());
+
+    let marker = if unread { "NEW".bright_yellow().bold() } else { "   ".normal() };
+
+    println!("\n{} {} {} {}", "::".bright_blue().bold(), marker, date.dimmed(), item.title.bold());
+
+    if !item.body.is_empty() {
+        for line in item.body.lines() {
+            println!("   {}", line);
+        }
+    }
+
+    if let Some(ref link) = item.link {
+        println!("   {}", link.bright_black());
+    }
+}
+
+/// Show unread news published since the last upgrade.
+/// Returns the number of items shown; fetch errors are reported but never fatal.
+pub async fn show_unread_before_upgrade(source: &str) -> usize {
+    let spinner = crate::ui::spinner("Checking Arch Linux news...");
+    let items = match fetch(source).await {
+        Ok(items) => {
+            spinner.finish_and_clear();
+            items
+        }
+        Err(e) => {
+            spinner.finish_and_clear();
+            eprintln!("{}", crate::ui::warning(&format!("Could not check Arch news: {}", e)));
+            return 0;
+        }
+    };
+
+    let mut state = NewsState::load().unwrap_or_default();
+    let unread = state.unread_since_last_upgrade(&items);
+    if unread.is_empty() {
+        return 0;
+    }
+
+    println!(
+        "\n{} {}",
+        "::".bright_yellow().bold(),
+        format!("Unread Arch Linux news ({}) - manual intervention may be required:", unread.len()).bold()
+    );
+    // Oldest first so instructions read in the order they were published
+    for item in unread.iter().rev() {
+        print_item(item, true);
+    }
+
+    let count = unread.len();
+    state.mark_seen(unread);
+    if let Err(e) = state.save() {
+        eprintln!("{}", crate::ui::warning(&format!("Failed to save news state: {}", e)));
+    }
+
+    count
+}
+
+/// Remember that the system was just upgraded
+pub fn record_upgrade() -> Result<()> {
+    let mut state = NewsState::load()?;
+    state.last_upgrade = Some(Utc::now());
+    state.save()
+}
+
+fn state_path() -> Result<PathBuf> {
+    let mut path = dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?;
+    path.push("khazaur");
+    path.push("news.json");
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEED: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<rss version="2.0"><channel><title>Arch Linux: Recent news updates</title>
+<link>https://archlinux.org/news/</link><description>News</description>
+<item><title>Older item</title><link>https://archlinux.org/news/older/</link>
+<description>&lt;p&gt;Nothing to do.&lt;/p&gt;</description>
+<pubDate>Mon, 01 Jan 2024 10:00:00 +0000</pubDate><guid isPermaLink="false">tag:older</guid></item>
+<item><title>Manual intervention required</title><link>https://archlinux.org/news/manual/</link>
+<description>&lt;p&gt;Run &lt;code&gt;pacman -Syu --overwrite '*'&lt;/code&gt; &amp;amp; reboot.&lt;/p&gt;&lt;ul&gt;&lt;li&gt;one&lt;/li&gt;&lt;/ul&gt;</description>
+<pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate><guid isPermaLink="false">tag:manual</guid></item>
+</channel></rss>"#;
+
+    #[test]
+    fn test_parse_feed_sorts_newest_first() {
+        let items = parse_feed(FEED.as_bytes()).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].id, "tag:manual");
+        assert_eq!(items[0].body, "Run pacman -Syu --overwrite '*' & reboot.\n\n• one");
+        assert_eq!(items[1].title, "Older item");
+    }
+
+    #[test]
+    fn test_unread_since_last_upgrade() {
+        let items = parse_feed(FEED.as_bytes()).unwrap();
+        let mut state = NewsState {
+            seen: BTreeSet::new(),
+            last_upgrade: Some("2024-01-01T12:00:00Z".parse().unwrap()),
+        };
+
+        let unread = state.unread_since_last_upgrade(&items);
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].id, "tag:manual");
+
+        state.mark_seen(unread);
+        assert!(state.unread_since_last_upgrade(&items).is_empty());
+    }
+}
@@ -0,0 +1,377 @@
+use crate::error::Result;
+use crate::reasons::InstallReason;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+/// Default pacman database location
+pub const DB_PATH: &str = "/var/lib/pacman";
+
+/// A package entry read from a pacman database `desc` file
+#[derive(Debug, Clone, Default)]
+pub struct DbPackage {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub depends: Vec<String>,
+    pub optdepends: Vec<String>,
+    pub provides: Vec<String>,
+    /// Only set for installed packages
+    pub reason: Option<InstallReason>,
+    /// Only set for sync database packages
+    pub repo: Option<String>,
+    pub installed_size: u64,
+    /// Package file size, only set for sync database packages
+    pub download_size: u64,
+}
+
+impl DbPackage {
+    pub fn is_explicit(&self) -> bool {
+        self.reason == Some(InstallReason::Explicit)
+    }
+}
+
+/// Parse the `%FIELD%` blocks of a pacman `desc` file
+pub fn parse_desc(contents: &str, installed: bool) -> DbPackage {
+    let mut pkg = DbPackage::default();
+    // Local packages without %REASON% were installed explicitly
+    if installed {
+        pkg.reason = Some(InstallReason::Explicit);
+    }
+
+    let mut field = "";
+    for line in contents.lines() {
+        if line.starts_with('%') && line.ends_with('%') && line.len() > 2 {
+            field = line;
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        match field {
+            "%NAME%" => pkg.name = line.to_string(),
+            "%VERSION%" => pkg.version = line.to_string(),
+            "%DESC%" => pkg.description = line.to_string(),
+            "%DEPENDS%" => pkg.depends.push(line.to_string()),
+            "%OPTDEPENDS%" => pkg.optdepends.push(line.to_string()),
+            "%PROVIDES%" => pkg.provides.push(line.to_string()),
+            "%REASON%" if installed && line == "1" => pkg.reason = Some(InstallReason::Dependency),
+            // Local databases use %SIZE%, sync databases %ISIZE%
+            "%SIZE%" | "%ISIZE%" => pkg.installed_size = line.parse().unwrap_or(0),
+            "%CSIZE%" => pkg.download_size = line.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    pkg
+}
+
+/// Parse the `desc` entries of a sync database archive. repo-add writes gzip by
+/// default but can use zstd or xz, or leave the tar uncompressed.
+fn read_sync_archive(bytes: &[u8]) -> Result<Vec<DbPackage>> {
+    let reader: Box<dyn Read> = if bytes.starts_with(&[0x1f, 0x8b]) {
+        Box::new(flate2::read::GzDecoder::new(bytes))
+    } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Box::new(zstd::stream::read::Decoder::new(bytes)?)
+    } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Box::new(xz2::read::XzDecoder::new(bytes))
+    } else {
+        Box::new(bytes)
+    };
+
+    let mut packages = Vec::new();
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.path()?.ends_with("desc") {
+            continue;
+        }
+
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents)?;
+        packages.push(parse_desc(&contents, false));
+    }
+
+    Ok(packages)
+}
+
+/// Strip version constraints and optdepend descriptions: `foo>=1.0` / `foo: for bar` -> `foo`
+pub fn dep_name(dep: &str) -> &str {
+    let dep = dep.split(": ").next().unwrap_or(dep);
+    let end = dep.find(['<', '>', '=']).unwrap_or(dep.len());
+    dep[..end].trim()
+}
+
+/// A set of packages from pacman databases, indexed by name and provides
+#[derive(Debug, Default)]
+pub struct PackageDb {
+    packages: HashMap<String, DbPackage>,
+    providers: HashMap<String, Vec<String>>,
+}
+
+impl PackageDb {
+    /// Read the installed package database (/var/lib/pacman/local)
+    pub fn load_local() -> Result<Self> {
+        Self::load_local_from(Path::new(DB_PATH))
+    }
+
+    /// Read the local database below a pacman DBPath
+    pub fn load_local_from(db_path: &Path) -> Result<Self> {
+        let mut db = Self::default();
+
+        for entry in std::fs::read_dir(db_path.join("local"))? {
+            let entry = entry?;
+            let desc = entry.path().join("desc");
+            // ALPM_DB_VERSION and other non-package files have no desc
+            let Ok(contents) = std::fs::read_to_string(&desc) else {
+                continue;
+            };
+
+            db.insert(parse_desc(&contents, true));
+        }
+
+        Ok(db)
+    }
+
+    /// Read all sync databases configured in pacman.conf.
+    /// Repositories are read in pacman.conf order and earlier ones win, as in pacman.
+    pub fn load_sync() -> Result<Self> {
+        let mut db = Self::default();
+        let sync_dir = Path::new(DB_PATH).join("sync");
+
+        for repo in super::repos::list_repos()? {
+            let path = sync_dir.join(format!("{}.db", repo.name));
+            // Repositories that were never synced have no database yet
+            let Ok(bytes) = std::fs::read(&path) else {
+                continue;
+            };
+
+            // A damaged database only costs its own repository
+            let packages = match read_sync_archive(&bytes) {
+                Ok(packages) => packages,
+                Err(e) => {
+                    tracing::warn!("Skipping sync database {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+
+            for mut pkg in packages {
+                if db.packages.contains_key(&pkg.name) {
+                    continue;
+                }
+                pkg.repo = Some(repo.name.clone());
+                db.insert(pkg);
+            }
+        }
+
+        Ok(db)
+    }
+
+    pub fn insert(&mut self, pkg: DbPackage) {
+        for provide in &pkg.provides {
+            self.providers
+                .entry(dep_name(provide).to_string())
+                .or_default()
+                .push(pkg.name.clone());
+        }
+        self.packages.insert(pkg.name.clone(), pkg);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DbPackage> {
+        self.packages.get(name)
+    }
+
+    /// Find the installed package satisfying a dependency, by name or provides
+    pub fn resolve(&self, dep: &str) -> Option<&DbPackage> {
+        let name = dep_name(dep);
+        self.packages.get(name).or_else(|| {
+            self.providers
+                .get(name)
+                .and_then(|providers| providers.first())
+                .and_then(|provider| self.packages.get(provider))
+        })
+    }
+
+    /// Map every installed package to the packages that depend on it,
+    /// as (dependent, optional) pairs
+    pub fn reverse_dependencies(&self) -> HashMap<String, Vec<(String, bool)>> {
+        let mut reverse: HashMap<String, Vec<(String, bool)>> = HashMap::new();
+
+        for pkg in self.packages.values() {
+            let required = pkg.depends.iter().map(|d| (d, false));
+            let optional = pkg.optdepends.iter().map(|d| (d, true));
+
+            for (dep, is_optional) in required.chain(optional) {
+                if let Some(target) = self.resolve(dep) {
+                    if target.name == pkg.name {
+                        continue;
+                    }
+                    let dependents = reverse.entry(target.name.clone()).or_default();
+                    if !dependents.iter().any(|(name, _)| name == &pkg.name) {
+                        dependents.push((pkg.name.clone(), is_optional));
+                    }
+                }
+            }
+        }
+
+        reverse
+    }
+
+    /// Dependencies that become unneeded when `targets` are removed, like `pacman -Rs`:
+    /// packages installed as dependencies whose every dependent is being removed too.
+    /// Returned in discovery order, without the targets themselves.
+    pub fn removal_cascade(&self, targets: &[String]) -> Vec<String> {
+        let reverse = self.reverse_dependencies();
+        let mut removing: HashSet<&str> = targets.iter().map(String::as_str).collect();
+        let mut cascade: Vec<String> = Vec::new();
+
+        // A dependency shared by two removed packages only frees up once both are
+        // in the set, so repeat until nothing changes
+        loop {
+            let mut added = Vec::new();
+
+            for name in targets.iter().chain(&cascade) {
+                let Some(pkg) = self.packages.get(name) else {
+                    continue;
+                };
+
+                for dep in &pkg.depends {
+                    let Some(candidate) = self.resolve(dep) else {
+                        continue;
+                    };
+                    if removing.contains(candidate.name.as_str()) || candidate.is_explicit() {
+                        continue;
+                    }
+
+                    let still_needed = reverse
+                        .get(&candidate.name)
+                        .into_iter()
+                        .flatten()
+                        .any(|(dependent, optional)| !optional && !removing.contains(dependent.as_str()));
+                    if !still_needed {
+                        removing.insert(&candidate.name);
+                        added.push(candidate.name.clone());
+                    }
+                }
+            }
+
+            if added.is_empty() {
+                break;
+            }
+            cascade.extend(added);
+        }
+
+        cascade
+    }
+
+    /// Installed packages outside `removing` that require something in it,
+    /// as (dependent, required package) pairs
+    pub fn broken_by_removal(&self, removing: &[String]) -> Vec<(String, String)> {
+        let reverse = self.reverse_dependencies();
+        let set: HashSet<&str> = removing.iter().map(String::as_str).collect();
+        let mut broken = Vec::new();
+
+        for name in removing {
+            for (dependent, optional) in reverse.get(name).into_iter().flatten() {
+                if !optional && !set.contains(dependent.as_str()) {
+                    broken.push((dependent.clone(), name.clone()));
+                }
+            }
+        }
+
+        broken.sort();
+        broken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESC: &str = "%NAME%\nlibfoo\n\n%VERSION%\n1.2-1\n\n%DESC%\nFoo library\n\n\
+        %SIZE%\n2048\n\n%REASON%\n1\n\n%DEPENDS%\nglibc>=2.38\nsh\n\n\
+        %OPTDEPENDS%\npython: for bindings\n\n%PROVIDES%\nlibfoo.so=1-64\n";
+
+    #[test]
+    fn test_parse_desc() {
+        let pkg = parse_desc(DESC, true);
+        assert_eq!(pkg.name, "libfoo");
+        assert_eq!(pkg.version, "1.2-1");
+        assert_eq!(pkg.installed_size, 2048);
+        assert_eq!(pkg.reason, Some(InstallReason::Dependency));
+        assert_eq!(pkg.depends, vec!["glibc>=2.38", "sh"]);
+        assert_eq!(dep_name(&pkg.optdepends[0]), "python");
+        assert_eq!(dep_name(&pkg.provides[0]), "libfoo.so");
+    }
+
+    /// A sync database tar with a single package entry
+    fn sync_tar() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(DESC.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, "libfoo-1.2-1/desc", DESC.as_bytes()).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_read_sync_archive_compressions() {
+        use std::io::Write;
+
+        let tar = sync_tar();
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(&tar).unwrap();
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(&tar).unwrap();
+
+        let archives = [
+            tar.clone(),
+            gzip.finish().unwrap(),
+            zstd::encode_all(tar.as_slice(), 0).unwrap(),
+            xz.finish().unwrap(),
+        ];
+        for archive in archives {
+            let packages = read_sync_archive(&archive).unwrap();
+            assert_eq!(packages.len(), 1);
+            assert_eq!(packages[0].name, "libfoo");
+        }
+
+        assert!(read_sync_archive(&[0x28, 0xb5, 0x2f, 0xfd, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_reverse_dependencies_resolve_provides() {
+        let mut db = PackageDb::default();
+        db.insert(parse_desc(DESC, true));
+        db.insert(parse_desc("%NAME%\nbash\n%VERSION%\n5.2-1\n%PROVIDES%\nsh\n", true));
+        db.insert(parse_desc("%NAME%\napp\n%VERSION%\n1-1\n%DEPENDS%\nlibfoo.so=1-64\n", true));
+
+        let reverse = db.reverse_dependencies();
+        assert_eq!(reverse["bash"], vec![("libfoo".to_string(), false)]);
+        assert_eq!(reverse["libfoo"], vec![("app".to_string(), false)]);
+        assert!(db.get("app").unwrap().is_explicit());
+    }
+
+    #[test]
+    fn test_removal_cascade() {
+        let mut db = PackageDb::default();
+        db.insert(parse_desc("%NAME%\napp\n%VERSION%\n1-1\n%DEPENDS%\nlibfoo\nlibshared\n", true));
+        db.insert(parse_desc("%NAME%\nlibfoo\n%VERSION%\n1-1\n%REASON%\n1\n%DEPENDS%\nlibbar\n", true));
+        db.insert(parse_desc("%NAME%\nlibbar\n%VERSION%\n1-1\n%REASON%\n1\n", true));
+        db.insert(parse_desc("%NAME%\nlibshared\n%VERSION%\n1-1\n%REASON%\n1\n", true));
+        db.insert(parse_desc("%NAME%\nother\n%VERSION%\n1-1\n%DEPENDS%\nlibshared\n", true));
+
+        let targets = vec!["app".to_string()];
+        let mut cascade = db.removal_cascade(&targets);
+        cascade.sort();
+        assert_eq!(cascade, vec!["libbar", "libfoo"]);
+
+        let broken = db.broken_by_removal(&["libshared".to_string()]);
+        assert_eq!(broken, vec![
+            ("app".to_string(), "libshared".to_string()),
+            ("other".to_string(), "libshared".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::error::Result;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use std::io::BufRead;
+use std::path::Path;
+
+pub const DEFAULT_LOG_PATH: &str = "/var/log/pacman.log";
+
+/// What happened to a package, as logged by libalpm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogAction {
+    Installed,
+    Upgraded,
+    Downgraded,
+    Reinstalled,
+    Removed,
+}
+
+/// One package event from the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEvent {
+    pub action: LogAction,
+    pub name: String,
+    /// Version before the event, None for installs
+    pub old_version: Option<String>,
+    /// Version after the event, None for removals
+    pub new_version: Option<String>,
+}
+
+/// Package events of one pacman transaction
+#[derive(Debug, Clone)]
+pub struct LogTransaction {
+    pub time: DateTime<Local>,
+    /// pacman command line that started the transaction, when logged
+    pub command: Option<String>,
+    pub success: bool,
+    pub events: Vec<LogEvent>,
+}
+
+/// A parsed log line
+#[derive(Debug, PartialEq)]
+enum Line {
+    Command(String),
+    Started,
+    /// Completed (true), failed or interrupted (false)
+    Finished(bool),
+    Event(LogEvent),
+}
+
+/// Read every transaction from a pacman log, oldest first.
+/// A missing log yields nothing.
+pub fn read_transactions(path: &Path) -> Result<Vec<LogTransaction>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = std::fs::File::open(path)?;
+    Ok(parse_transactions(std::io::BufReader::new(file)))
+}
+
+/// Group log lines into transactions. Events logged outside of transaction markers
+/// (logs from before pacman 4.1) each become their own transaction.
+pub fn parse_transactions(reader: impl BufRead) -> Vec<LogTransaction> {
+    let mut transactions = Vec::new();
+    let mut command = None;
+    let mut current: Option<LogTransaction> = None;
+
+    for raw in reader.split(b'\n').map_while(std::result::Result::ok) {
+        // Package scriptlets can log anything, including invalid UTF-8
+        let raw = String::from_utf8_lossy(&raw);
+        let Some((time, line)) = parse_line(&raw) else {
+            continue;
+        };
+
+        match line {
+            Line::Command(cmd) => command = Some(cmd),
+            Line::Started => {
+                if let Some(tx) = current.take().filter(|tx| !tx.events.is_empty()) {
+                    transactions.push(tx);
+                }
+                current = Some(LogTransaction {
+                    time,
+                    command: command.take(),
+                    success: true,
+                    events: Vec::new(),
+                });
+            }
+            Line::Finished(success) => {
+                if let Some(mut tx) = current.take() {
+                    tx.success = success;
+                    if !tx.events.is_empty() {
+                        transactions.push(tx);
+                    }
+                }
+            }
+            Line::Event(event) => match current.as_mut() {
+                Some(tx) => tx.events.push(event),
+                None => transactions.push(LogTransaction {
+                    time,
+                    command: command.take(),
+                    success: true,
+                    events: vec![event],
+                }),
+            },
+        }
+    }
+
+    // A transaction still running or cut off by a crash
+    if let Some(tx) = current.filter(|tx| !tx.events.is_empty()) {
+        transactions.push(tx);
+    }
+
+    transactions
+}
+
+/// Parse `[time] [ALPM] upgraded name (old -> new)` and the other lines that matter
+fn parse_line(line: &str) -> Option<(DateTime<Local>, Line)> {
+    let rest = line.strip_prefix('[')?;
+    let (time, rest) = rest.split_once(']')?;
+    let time = parse_time(time)?;
+    let rest = rest.trim_start();
+
+    // Logs from before pacman 4.1 have no [ALPM]/[PACMAN] tag
+    let (tag, message) = match rest.strip_prefix('[') {
+        Some(tagged) => {
+            let (tag, message) = tagged.split_once(']')?;
+            (tag, message.trim())
+        }
+        None => ("ALPM", rest.trim()),
+    };
+
+    let parsed = match tag {
+        "PACMAN" => Line::Command(message.strip_prefix("Running '")?.strip_suffix('\'')?.to_string()),
+        "ALPM" => match message {
+            "transaction started" => Line::Started,
+            "transaction completed" => Line::Finished(true),
+            "transaction failed" | "transaction interrupted" => Line::Finished(false),
+            _ => Line::Event(parse_event(message)?),
+        },
+        _ => return None,
+    };
+
+    Some((time, parsed))
+}
+
+fn parse_event(message: &str) -> Option<LogEvent> {
+    let (verb, rest) = message.split_once(' ')?;
+    let action = match verb {
+        "installed" => LogAction::Installed,
+        "upgraded" => LogAction::Upgraded,
+        "downgraded" => LogAction::Downgraded,
+        "reinstalled" => LogAction::Reinstalled,
+        "removed" => LogAction::Removed,
+        _ => return None,
+    };
+
+    let (name, versions) = rest.split_once(" (")?;
+    let versions = versions.strip_suffix(')')?;
+    let (old_version, new_version) = match action {
+        LogAction::Installed => (None, Some(versions.to_string())),
+        LogAction::Removed => (Some(versions.to_string()), None),
+        LogAction::Reinstalled => (Some(versions.to_string()), Some(versions.to_string())),
+        LogAction::Upgraded | LogAction::Downgraded => {
+            let (old, new) = versions.split_once(" -> ")?;
+            (Some(old.to_string()), Some(new.to_string()))
+        }
+    };
+
+    Some(LogEvent {
+        action,
+        name: name.to_string(),
+        old_version,
+        new_version,
+    })
+}
+
+/// `2024-05-01T10:22:33+0200`, or `2019-01-01 10:22` in local time for older logs
+fn parse_time(text: &str) -> Option<DateTime<Local>> {
+    if let Ok(time) = DateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%z") {
+        return Some(time.with_timezone(&Local));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M").ok()?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = "\
+[2026-10-01T09:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-10-01T09:00:05+0000] [PACMAN] synchronizing package lists
+[2026-10-01T09:00:10+0000] [ALPM] transaction started
+[2026-10-01T09:00:11+0000] [ALPM] upgraded firefox (130.0-1 -> 131.0-1)
+[2026-10-01T09:00:11+0000] [ALPM-SCRIPTLET] some output
+[2026-10-01T09:00:12+0000] [ALPM] installed nss (3.104-1)
+[2026-10-01T09:00:13+0000] [ALPM] transaction completed
+[2026-10-02T10:00:10+0000] [PACMAN] Running 'pacman -R foo'
+[2026-10-02T10:00:10+0000] [ALPM] transaction started
+[2026-10-02T10:00:11+0000] [ALPM] removed foo (1.0-2)
+[2026-10-02T10:00:12+0000] [ALPM] transaction interrupted
+[2019-01-01 10:22] upgraded bash (4.4-1 -> 5.0-1)
+";
+
+    #[test]
+    fn test_parse_transactions() {
+        let transactions = parse_transactions(LOG.as_bytes());
+        assert_eq!(transactions.len(), 3);
+
+        let first = &transactions[0];
+        assert_eq!(first.command.as_deref(), Some("pacman -Syu"));
+        assert!(first.success);
+        assert_eq!(first.events.len(), 2);
+        assert_eq!(first.events[0], LogEvent {
+            action: LogAction::Upgraded,
+            name: "firefox".to_string(),
+            old_version: Some("130.0-1".to_string()),
+            new_version: Some("131.0-1".to_string()),
+        });
+        assert_eq!(first.events[1].old_version, None);
+
+        assert!(!transactions[1].success);
+        assert_eq!(transactions[1].events[0].action, LogAction::Removed);
+
+        assert_eq!(transactions[2].events[0].name, "bash");
+        assert_eq!(transactions[2].command, None);
+    }
+}
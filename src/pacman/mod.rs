@@ -1,6 +1,10 @@
 pub mod query;
 pub mod operations;
 pub mod repos;
+pub mod version;
+pub mod db;
+pub mod log;
 
 pub use query::*;
 pub use operations::*;
+pub use version::vercmp;
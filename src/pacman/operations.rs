@@ -17,6 +17,21 @@ pub fn sync_databases() -> Result<()> {
     Ok(())
 }
 
+/// Sync pacman's files databases, used to find which package owns a file
+pub fn sync_files_databases() -> Result<()> {
+    info!("Syncing files databases...");
+
+    let status = Command::new("sudo")
+        .args(["pacman", "-Fy"])
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::PacmanFailed("Files database sync failed".to_string()));
+    }
+
+    Ok(())
+}
+
 /// Install packages from official repositories
 pub fn install_packages(package_names: &[String], extra_args: &[String]) -> Result<()> {
     if package_names.is_empty() {
@@ -104,4 +119,51 @@ pub fn install_local_package(file_path: &str, extra_args: &[String]) -> Result<(
     Ok(())
 }
 
+/// Change the install reason of installed packages (pacman -D)
+pub fn set_install_reason(package_names: &[String], reason: crate::reasons::InstallReason) -> Result<()> {
+    if package_names.is_empty() {
+        return Ok(());
+    }
+
+    let flag = match reason {
+        crate::reasons::InstallReason::Explicit => "--asexplicit",
+        crate::reasons::InstallReason::Dependency => "--asdeps",
+    };
 
+    let status = Command::new("sudo")
+        .args(["pacman", "-D", flag])
+        .args(package_names)
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::PacmanFailed("Failed to change install reason".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Get the install reason of an installed package from the local database
+pub fn get_install_reason(package_name: &str) -> Result<Option<crate::reasons::InstallReason>> {
+    let output = Command::new("pacman")
+        .args(["-Qi", package_name])
+        .env("LC_ALL", "C")
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let reason = stdout
+        .lines()
+        .find(|line| line.starts_with("Install Reason"))
+        .map(|line| {
+            if line.contains("dependency") {
+                crate::reasons::InstallReason::Dependency
+            } else {
+                crate::reasons::InstallReason::Explicit
+            }
+        });
+
+    Ok(reason)
+}
@@ -1,5 +1,6 @@
-use crate::error::Result;
+use crate::error::{KhazaurError, Result};
 use std::process::Command;
+use std::collections::HashSet;
 
 /// Check if a package is installed
 pub fn is_installed(package_name: &str) -> Result<bool> {
@@ -41,6 +42,50 @@ pub fn get_repo_info(package_name: &str) -> Result<Option<String>> {
     Ok(Some(String::from_utf8_lossy(&output.stdout).to_string()))
 }
 
+/// A file of a repository package, from pacman's files databases
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileOwner {
+    pub repository: String,
+    pub name: String,
+    pub version: String,
+    /// Path without the leading slash
+    pub path: String,
+}
+
+/// Find the repository packages owning a file name or path (pacman -F)
+pub fn search_files(query: &str) -> Result<Vec<FileOwner>> {
+    let output = Command::new("pacman")
+        .args(["-F", "--machinereadable", query])
+        .env("LC_ALL", "C")
+        .output()?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("-Fy") {
+        return Err(KhazaurError::PacmanFailed(
+            "pacman's files databases are missing, run 'khazaur -Fy' to download them".to_string(),
+        ));
+    }
+
+    // pacman exits with 1 when nothing owns the file
+    Ok(parse_files_output(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `repo\0name\0version\0path` lines of pacman -F --machinereadable
+fn parse_files_output(output: &str) -> Vec<FileOwner> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\0');
+            Some(FileOwner {
+                repository: fields.next()?.to_string(),
+                name: fields.next()?.to_string(),
+                version: fields.next()?.to_string(),
+                path: fields.next()?.to_string(),
+            })
+        })
+        .collect()
+}
+
 /// Simple package information from repo search
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct RepoPackage {
@@ -141,28 +186,6 @@ pub fn get_package_details(package_name: &str) -> Result<Option<RepoPackage>> {
 }
 
 
-/// Search for installed packages matching a query (fuzzy search)
-pub fn search_installed_packages(query: &str) -> Result<Vec<String>> {
-    let output = Command::new("pacman")
-        .args(["-Qq"])
-        .output()?;
-    
-    if !output.status.success() {
-        return Ok(Vec::new());
-    }
-    
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let query_lower = query.to_lowercase();
-    
-    let matches: Vec<String> = stdout
-        .lines()
-        .filter(|line| line.to_lowercase().contains(&query_lower))
-        .map(|s| s.to_string())
-        .collect();
-    
-    Ok(matches)
-}
-
 /// Get all installed packages with their versions
 pub fn get_installed_packages() -> Result<Vec<(String, String)>> {
     let output = Command::new("pacman")
@@ -189,6 +212,19 @@ pub fn get_installed_packages() -> Result<Vec<(String, String)>> {
     Ok(packages)
 }
 
+/// Names of packages installed as dependencies
+pub fn get_dependency_packages() -> Result<HashSet<String>> {
+    let output = Command::new("pacman")
+        .args(["-Qqd"])
+        .output()?;
+
+    if !output.status.success() {
+        return Ok(HashSet::new());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect())
+}
+
 /// Get all installed AUR packages (packages not in official repos)
 pub fn get_installed_aur_packages() -> Result<Vec<(String, String)>> {
     let output = Command::new("pacman")
@@ -155,6 +155,97 @@ pub fn remove_repo(name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Add a package to (or remove it from) IgnorePkg in the [options] section of /etc/pacman.conf
+pub fn set_ignored(name: &str, ignored: bool) -> Result<()> {
+    let contents = std::fs::read_to_string(PACMAN_CONF)
+        .map_err(|e| KhazaurError::Config(format!("Failed to open {}: {}", PACMAN_CONF, e)))?;
+    let updated = edit_ignore_pkg(&contents, name, ignored);
+    if updated == contents {
+        return Ok(());
+    }
+
+    // A fresh private file, so nobody else can put their content in its place
+    let mut temp_file = tempfile::NamedTempFile::new()
+        .map_err(|e| KhazaurError::Config(format!("Failed to create temp file: {}", e)))?;
+    temp_file.write_all(updated.as_bytes())?;
+    temp_file.flush()?;
+
+    let status = Command::new("sudo")
+        .arg("cp")
+        .arg(temp_file.path())
+        .arg(PACMAN_CONF)
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::Config("Failed to update pacman.conf".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Rewrite pacman.conf contents with `name` added to or removed from IgnorePkg.
+/// Edits the first active IgnorePkg line in [options], or adds one right after the header.
+fn edit_ignore_pkg(contents: &str, name: &str, ignored: bool) -> String {
+    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+    let mut in_options = false;
+    let mut options_header = None;
+    let mut ignore_line = None;
+
+    for (idx, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            in_options = trimmed == "[options]";
+            if in_options {
+                options_header = Some(idx);
+            }
+            continue;
+        }
+        if in_options && trimmed.split('=').next().map(str::trim) == Some("IgnorePkg") {
+            ignore_line = Some(idx);
+            break;
+        }
+    }
+
+    match ignore_line {
+        Some(idx) => {
+            let current = lines[idx].split_once('=').map(|(_, v)| v).unwrap_or("");
+            let mut names: Vec<&str> = current.split_whitespace().collect();
+            let present = names.contains(&name);
+            if ignored == present {
+                return contents.to_string();
+            }
+            if ignored {
+                names.push(name);
+            } else {
+                names.retain(|n| *n != name);
+            }
+
+            lines[idx] = if names.is_empty() {
+                "#IgnorePkg   =".to_string()
+            } else {
+                format!("IgnorePkg   = {}", names.join(" "))
+            };
+        }
+        None if ignored => {
+            let line = format!("IgnorePkg   = {}", name);
+            match options_header {
+                Some(idx) => lines.insert(idx + 1, line),
+                None => {
+                    lines.insert(0, line);
+                    lines.insert(0, "[options]".to_string());
+                }
+            }
+        }
+        None => return contents.to_string(),
+    }
+
+    let mut out = lines.join("\n");
+    if contents.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
 pub struct SuggestedRepo {
     pub name: String,
     pub server: String,
@@ -166,7 +257,7 @@ pub async fn fetch_suggested_repos() -> Result<Vec<SuggestedRepo>> {
     
     let url = "https://wiki.archlinux.org/title/Unofficial_user_repositories";
     
-    let response = reqwest::get(url).await
+    let response = crate::net::get(url).await
         .map_err(|e| KhazaurError::Config(format!("Failed to fetch repos list: {}", e)))?
         .text().await
         .map_err(|e| KhazaurError::Config(format!("Failed to read response: {}", e)))?;
@@ -231,3 +322,38 @@ pub async fn fetch_suggested_repos() -> Result<Vec<SuggestedRepo>> {
     
     Ok(suggestions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_ignore_pkg() {
+        let conf = "[options]\nHoldPkg = pacman\n#IgnorePkg   =\n\n[core]\nInclude = /etc/pacman.d/mirrorlist\n";
+
+        let added = edit_ignore_pkg(conf, "firefox", true);
+        assert!(added.starts_with("[options]\nIgnorePkg   = firefox\nHoldPkg"));
+
+        let both = edit_ignore_pkg(&added, "linux", true);
+        assert!(both.contains("IgnorePkg   = firefox linux\n"));
+        assert_eq!(edit_ignore_pkg(&both, "linux", true), both);
+
+        let removed = edit_ignore_pkg(&edit_ignore_pkg(&both, "firefox", false), "linux", false);
+        assert!(!removed.contains("IgnorePkg   = "));
+        assert!(removed.ends_with("mirrorlist\n"));
+    }
+
+    #[test]
+    fn test_edit_existing_ignore_pkg_line() {
+        let conf = "#\n# /etc/pacman.conf\n#\n[options]\nArchitecture = auto\nIgnorePkg   = linux nvidia\n#IgnoreGroup =\n\n[extra]\nIgnorePkg = firefox\nInclude = /etc/pacman.d/mirrorlist\n";
+
+        let added = edit_ignore_pkg(conf, "mesa", true);
+        assert!(added.contains("[options]\nArchitecture = auto\nIgnorePkg   = linux nvidia mesa\n#IgnoreGroup"));
+        // Only [options] is edited
+        assert!(added.contains("[extra]\nIgnorePkg = firefox\n"));
+
+        let removed = edit_ignore_pkg(&added, "nvidia", false);
+        assert!(removed.contains("IgnorePkg   = linux mesa\n"));
+        assert_eq!(edit_ignore_pkg(&removed, "firefox", false), removed);
+    }
+}
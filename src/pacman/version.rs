@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+
+/// Compare two package versions the way pacman's `vercmp` does
+/// (`[epoch:]pkgver[-pkgrel]`), without spawning a process.
+pub fn vercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let (epoch_a, ver_a, rel_a) = parse_evr(a);
+    let (epoch_b, ver_b, rel_b) = parse_evr(b);
+
+    rpmvercmp(epoch_a, epoch_b)
+        .then_with(|| rpmvercmp(ver_a, ver_b))
+        .then_with(|| match (rel_a, rel_b) {
+            (Some(ra), Some(rb)) => rpmvercmp(ra, rb),
+            _ => Ordering::Equal,
+        })
+}
+
+/// Split `[epoch:]version[-release]` into its parts
+fn parse_evr(evr: &str) -> (&str, &str, Option<&str>) {
+    let digits = evr.bytes().take_while(u8::is_ascii_digit).count();
+
+    let (epoch, rest) = if evr.as_bytes().get(digits) == Some(&b':') {
+        let epoch = &evr[..digits];
+        (if epoch.is_empty() { "0" } else { epoch }, &evr[digits + 1..])
+    } else {
+        ("0", evr)
+    };
+
+    match rest.rfind('-') {
+        Some(idx) => (epoch, &rest[..idx], Some(&rest[idx + 1..])),
+        None => (epoch, rest, None),
+    }
+}
+
+/// Segment-wise comparison used by rpm and libalpm
+fn rpmvercmp(a: &str, b: &str) -> Ordering {
+    if a == b {
+        return Ordering::Equal;
+    }
+
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let (mut one, mut two) = (0, 0);
+
+    while one < a.len() || two < b.len() {
+        let sep_one = one;
+        let sep_two = two;
+        while one < a.len() && !a[one].is_ascii_alphanumeric() {
+            one += 1;
+        }
+        while two < b.len() && !b[two].is_ascii_alphanumeric() {
+            two += 1;
+        }
+
+        if one >= a.len() || two >= b.len() {
+            break;
+        }
+
+        // A longer run of separators sorts higher
+        if one - sep_one != two - sep_two {
+            return (one - sep_one).cmp(&(two - sep_two));
+        }
+
+        let is_num = a[one].is_ascii_digit();
+        let class: fn(&u8) -> bool = if is_num { u8::is_ascii_digit } else { u8::is_ascii_alphabetic };
+
+        let end_one = one + a[one..].iter().take_while(|c| class(c)).count();
+        let end_two = two + b[two..].iter().take_while(|c| class(c)).count();
+
+        let mut seg_one = &a[one..end_one];
+        let mut seg_two = &b[two..end_two];
+
+        // Segments of different types: numeric is always newer
+        if seg_two.is_empty() {
+            return if is_num { Ordering::Greater } else { Ordering::Less };
+        }
+
+        if is_num {
+            while seg_one.first() == Some(&b'0') {
+                seg_one = &seg_one[1..];
+            }
+            while seg_two.first() == Some(&b'0') {
+                seg_two = &seg_two[1..];
+            }
+            match seg_one.len().cmp(&seg_two.len()) {
+                Ordering::Equal => {}
+                other => return other,
+            }
+        }
+
+        match seg_one.cmp(seg_two) {
+            Ordering::Equal => {}
+            other => return other,
+        }
+
+        one = end_one;
+        two = end_two;
+    }
+
+    let rest_one = a.get(one);
+    let rest_two = b.get(two);
+
+    match (rest_one, rest_two) {
+        (None, None) => Ordering::Equal,
+        // Whatever is left decides: an alpha suffix (1.0a) is older, anything else is newer
+        (None, Some(c)) if !c.is_ascii_alphabetic() => Ordering::Less,
+        (Some(c), _) if c.is_ascii_alphabetic() => Ordering::Less,
+        _ => Ordering::Greater,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vercmp_matches_pacman() {
+        let cases = [
+            ("1.0", "1.0", Ordering::Equal),
+            ("1.0", "1.1", Ordering::Less),
+            ("1.0-1", "1.0-2", Ordering::Less),
+            ("1.0", "1.0-1", Ordering::Equal),
+            ("1:1.0", "2.0", Ordering::Greater),
+            ("1.0a", "1.0", Ordering::Less),
+            ("1.0alpha", "1.0beta", Ordering::Less),
+            ("1.0.1", "1.0", Ordering::Greater),
+            ("1.0", "1.0.a", Ordering::Greater),
+            ("1.0.a", "1.0.1", Ordering::Less),
+            ("1..0", "1.0", Ordering::Greater),
+            ("1.010", "1.9", Ordering::Greater),
+            ("2.38.1-1", "2.38.0-3", Ordering::Greater),
+            ("r1234.abcdef-1", "r1235.000000-1", Ordering::Less),
+        ];
+
+        for (a, b, expected) in cases {
+            assert_eq!(vercmp(a, b), expected, "vercmp({}, {})", a, b);
+            assert_eq!(vercmp(b, a), expected.reverse(), "vercmp({}, {})", b, a);
+        }
+    }
+}
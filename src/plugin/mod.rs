@@ -0,0 +1,346 @@
+//! External backends: executables named `khazaur-backend-<id>` that answer
+//! JSON requests on stdin/stdout. The protocol is described in docs/PLUGINS.md.
+
+use crate::error::{KhazaurError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Version of the protocol khazaur speaks, sent with every request
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// File name prefix of backend executables
+const EXECUTABLE_PREFIX: &str = "khazaur-backend-";
+
+/// Ids the built-in sources use as `source/package` prefixes
+const RESERVED_IDS: &[&str] = &["repo", "aur", "flatpak", "snap", "debian", "appimage", "core", "extra", "multilib", "community"];
+
+static PLUGINS: OnceLock<Vec<Plugin>> = OnceLock::new();
+
+/// A package offered by a backend plugin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginPackage {
+    /// Id of the plugin the package comes from (`cargo` for khazaur-backend-cargo)
+    #[serde(default)]
+    pub backend: String,
+    /// Display name of that plugin
+    #[serde(default)]
+    pub source: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// A package a plugin reports as installed
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginInstalled {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub origin: Option<String>,
+    #[serde(default)]
+    pub dependency: bool,
+}
+
+/// A newer version a plugin reports for an installed package
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginUpdate {
+    pub name: String,
+    #[serde(default)]
+    pub current_version: String,
+    pub new_version: String,
+}
+
+/// Answer to an info request: the package and extra fields to show, in order
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginInfo {
+    pub package: Option<PluginPackage>,
+    #[serde(default)]
+    pub details: Vec<(String, String)>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum Request<'a> {
+    Describe,
+    Search { query: &'a str },
+    Info { name: &'a str },
+    Install { packages: &'a [String], noconfirm: bool, upgrade: bool },
+    Remove { packages: &'a [String] },
+    List,
+    Updates,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    protocol: u32,
+    #[serde(flatten)]
+    request: Request<'a>,
+}
+
+#[derive(Deserialize)]
+struct Description {
+    #[serde(default)]
+    protocol: Option<u32>,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default = "default_available")]
+    available: bool,
+}
+
+fn default_available() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct Packages<T> {
+    #[serde(default = "Vec::new")]
+    packages: Vec<T>,
+}
+
+#[derive(Deserialize)]
+struct Updates {
+    #[serde(default)]
+    updates: Vec<PluginUpdate>,
+}
+
+#[derive(Deserialize)]
+struct Ack {}
+
+/// A discovered backend plugin
+#[derive(Debug, Clone)]
+pub struct Plugin {
+    /// The part of the file name after `khazaur-backend-`, used as its `source/package` prefix
+    pub id: String,
+    pub path: PathBuf,
+    /// Name used in messages ("Cargo")
+    pub name: String,
+    /// Heading for lists of its packages ("Cargo Crates")
+    pub title: String,
+    /// Whether the tools the plugin drives are installed, as it reported
+    pub available: bool,
+}
+
+/// Directory searched for plugins besides PATH (~/.config/khazaur/backends)
+pub fn plugin_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("khazaur").join("backends"))
+}
+
+/// Backend plugins in the plugin directory and on PATH, asked to describe themselves
+/// once per run. When two share an id the one found first wins, the plugin directory first.
+pub fn discover() -> &'static [Plugin] {
+    PLUGINS.get_or_init(|| {
+        let mut dirs: Vec<PathBuf> = plugin_dir().into_iter().collect();
+        if let Some(path) = std::env::var_os("PATH") {
+            dirs.extend(std::env::split_paths(&path));
+        }
+
+        let mut seen = HashSet::new();
+        let mut plugins = Vec::new();
+        for (id, path) in dirs.iter().flat_map(|dir| executables(dir)) {
+            if RESERVED_IDS.contains(&id.as_str()) {
+                tracing::warn!("Ignoring backend plugin {}: '{}' is a built-in source", path.display(), id);
+                continue;
+            }
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            match describe(&id, &path) {
+                Ok(plugin) => plugins.push(plugin),
+                Err(e) => tracing::warn!("Ignoring backend plugin {}: {}", path.display(), e),
+            }
+        }
+        plugins
+    })
+}
+
+/// `khazaur-backend-*` executables in a directory, with their ids, sorted by id
+fn executables(dir: &Path) -> Vec<(String, PathBuf)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let id = file_name.strip_prefix(EXECUTABLE_PREFIX)?;
+            if !valid_id(id) {
+                return None;
+            }
+            let path = entry.path();
+            let meta = std::fs::metadata(&path).ok()?;
+            (meta.is_file() && meta.permissions().mode() & 0o111 != 0).then(|| (id.to_string(), path))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Ids are used as `id/package` prefixes, so they are kept to plain lowercase words
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+}
+
+fn describe(id: &str, path: &Path) -> Result<Plugin> {
+    let description: Description = call(path, Request::Describe, false)?;
+    if let Some(protocol) = description.protocol
+        && protocol > PROTOCOL_VERSION
+    {
+        return Err(KhazaurError::Plugin(format!(
+            "speaks protocol {}, khazaur supports {}",
+            protocol, PROTOCOL_VERSION
+        )));
+    }
+
+    let name = description.name.unwrap_or_else(|| id.to_string());
+    Ok(Plugin {
+        id: id.to_string(),
+        path: path.to_path_buf(),
+        title: description.title.unwrap_or_else(|| format!("{} Packages", name)),
+        name,
+        available: description.available,
+    })
+}
+
+impl Plugin {
+    /// Packages matching a query, by name or description
+    pub fn search(&self, query: &str) -> Result<Vec<PluginPackage>> {
+        let found: Packages<PluginPackage> = call(&self.path, Request::Search { query }, false)?;
+        Ok(found.packages.into_iter().map(|pkg| self.own(pkg)).collect())
+    }
+
+    /// A package and the details the plugin shows for it
+    pub fn info(&self, name: &str) -> Result<PluginInfo> {
+        let mut info: PluginInfo = call(&self.path, Request::Info { name }, false)?;
+        info.package = info.package.map(|pkg| self.own(pkg));
+        Ok(info)
+    }
+
+    /// Install packages by name, or bring installed ones to their newest version
+    pub fn install(&self, packages: &[String], noconfirm: bool, upgrade: bool) -> Result<()> {
+        let _: Ack = call(&self.path, Request::Install { packages, noconfirm, upgrade }, true)?;
+        Ok(())
+    }
+
+    pub fn remove(&self, packages: &[String]) -> Result<()> {
+        let _: Ack = call(&self.path, Request::Remove { packages }, true)?;
+        Ok(())
+    }
+
+    /// Everything installed through the plugin
+    pub fn list(&self) -> Result<Vec<PluginInstalled>> {
+        let installed: Packages<PluginInstalled> = call(&self.path, Request::List, false)?;
+        Ok(installed.packages)
+    }
+
+    /// Installed packages with a newer version available
+    pub fn updates(&self) -> Result<Vec<PluginUpdate>> {
+        let updates: Updates = call(&self.path, Request::Updates, false)?;
+        Ok(updates.updates)
+    }
+
+    fn own(&self, mut pkg: PluginPackage) -> PluginPackage {
+        pkg.backend = self.id.clone();
+        pkg.source = self.name.clone();
+        pkg
+    }
+}
+
+/// Run a plugin with one request and read its answer. Commands that change the
+/// system let the plugin print progress to the terminal on stderr.
+fn call<T: DeserializeOwned>(path: &Path, request: Request, interactive: bool) -> Result<T> {
+    let envelope = Envelope { protocol: PROTOCOL_VERSION, request };
+    let input = serde_json::to_vec(&envelope)?;
+
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(if interactive { Stdio::inherit() } else { Stdio::piped() })
+        .spawn()
+        .map_err(|e| KhazaurError::Plugin(format!("could not run {}: {}", path.display(), e)))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A plugin may exit without reading the request, e.g. on an unknown command
+        let _ = stdin.write_all(&input);
+    }
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let answer: serde_json::Value = if stdout.trim().is_empty() {
+        serde_json::Value::Object(Default::default())
+    } else {
+        serde_json::from_str(stdout.trim())
+            .map_err(|e| KhazaurError::Plugin(format!("invalid answer from {}: {}", path.display(), e)))?
+    };
+
+    if let Some(message) = answer.get("error").and_then(|error| error.as_str()) {
+        return Err(KhazaurError::Plugin(message.to_string()));
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} exited with {}", path.display(), output.status));
+        return Err(KhazaurError::Plugin(message));
+    }
+
+    serde_json::from_value(answer)
+        .map_err(|e| KhazaurError::Plugin(format!("invalid answer from {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_carry_the_command_and_protocol() {
+        let packages = vec!["ripgrep".to_string()];
+        let envelope = Envelope {
+            protocol: PROTOCOL_VERSION,
+            request: Request::Install { packages: &packages, noconfirm: true, upgrade: false },
+        };
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["protocol"], 1);
+        assert_eq!(json["command"], "install");
+        assert_eq!(json["packages"][0], "ripgrep");
+
+        let json = serde_json::to_value(Envelope { protocol: 1, request: Request::List }).unwrap();
+        assert_eq!(json, serde_json::json!({"protocol": 1, "command": "list"}));
+    }
+
+    #[test]
+    fn answers_fill_in_optional_fields() {
+        let found: Packages<PluginPackage> =
+            serde_json::from_str(r#"{"packages": [{"name": "ripgrep", "version": "14.1.0"}]}"#).unwrap();
+        assert_eq!(found.packages[0].name, "ripgrep");
+        assert!(found.packages[0].description.is_empty());
+
+        let info: PluginInfo =
+            serde_json::from_str(r#"{"package": null, "details": [["License", "MIT"]]}"#).unwrap();
+        assert!(info.package.is_none());
+        assert_eq!(info.details, vec![("License".to_string(), "MIT".to_string())]);
+    }
+
+    #[test]
+    fn ids_are_plain_words() {
+        assert!(valid_id("cargo"));
+        assert!(valid_id("npm-global"));
+        assert!(!valid_id(""));
+        assert!(!valid_id("Cargo"));
+        assert!(!valid_id("a/b"));
+    }
+}
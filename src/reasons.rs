@@ -0,0 +1,108 @@
+use crate::cli::SourceKind;
+use crate::error::{KhazaurError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Why a package is installed, mirroring pacman's install reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallReason {
+    Explicit,
+    Dependency,
+}
+
+impl InstallReason {
+    pub fn label(&self) -> &'static str {
+        match self {
+            InstallReason::Explicit => "explicitly installed",
+            InstallReason::Dependency => "installed as a dependency",
+        }
+    }
+}
+
+/// Install reasons for packages pacman doesn't know about (Flatpak, Snap, AppImage)
+/// or that khazaur converted itself (Debian).
+/// Stored in ~/.local/share/khazaur/install_reasons.json
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ReasonDb {
+    #[serde(default)]
+    packages: BTreeMap<SourceKind, BTreeMap<String, InstallReason>>,
+}
+
+impl ReasonDb {
+    pub fn load() -> Result<Self> {
+        let path = db_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = db_path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, source: SourceKind, name: &str) -> Option<InstallReason> {
+        self.packages.get(&source)?.get(name).copied()
+    }
+
+    pub fn set(&mut self, source: SourceKind, name: &str, reason: InstallReason) {
+        self.packages
+            .entry(source)
+            .or_default()
+            .insert(name.to_string(), reason);
+    }
+
+    pub fn remove(&mut self, source: SourceKind, name: &str) {
+        if let Some(entries) = self.packages.get_mut(&source) {
+            entries.remove(name);
+        }
+    }
+
+    /// Names recorded for a source with the given reason
+    pub fn with_reason(&self, source: SourceKind, reason: InstallReason) -> Vec<String> {
+        self.packages
+            .get(&source)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter(|(_, r)| **r == reason)
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Record the install reason of a package
+pub fn record(source: SourceKind, name: &str, reason: InstallReason) -> Result<()> {
+    let mut db = ReasonDb::load()?;
+    db.set(source, name, reason);
+    db.save()
+}
+
+/// Forget a package after it has been removed
+pub fn forget(source: SourceKind, names: &[String]) -> Result<()> {
+    let mut db = ReasonDb::load()?;
+    for name in names {
+        db.remove(source, name);
+    }
+    db.save()
+}
+
+fn db_path() -> Result<PathBuf> {
+    let mut path = dirs::data_local_dir()
+        .ok_or(KhazaurError::Config("Could not determine data directory".into()))?;
+    path.push("khazaur");
+    path.push("install_reasons.json");
+    Ok(path)
+}
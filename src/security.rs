@@ -0,0 +1,190 @@
+use crate::error::{KhazaurError, Result};
+use crate::pacman::vercmp;
+use colored::*;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Default Arch Linux security tracker feed
+pub const DEFAULT_TRACKER_URL: &str = "https://security.archlinux.org/issues/all.json";
+
+/// An Arch Vulnerability Group (AVG) as published by the security tracker
+#[derive(Debug, Clone, Deserialize)]
+pub struct Advisory {
+    pub name: String,
+    pub packages: Vec<String>,
+    pub status: String,
+    pub severity: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub fixed: Option<String>,
+    #[serde(default)]
+    pub issues: Vec<String>,
+}
+
+impl Advisory {
+    /// Whether `version` is still vulnerable.
+    /// Like arch-audit, every version below the fixed one counts as affected.
+    pub fn affects(&self, version: &str) -> bool {
+        if self.status.eq_ignore_ascii_case("Not affected") {
+            return false;
+        }
+
+        match self.fixed.as_deref().filter(|f| !f.is_empty()) {
+            Some(fixed) => vercmp(version, fixed) == Ordering::Less,
+            None => true,
+        }
+    }
+
+    /// Whether `version` contains the fix for this advisory
+    pub fn is_fixed_in(&self, version: &str) -> bool {
+        self.fixed
+            .as_deref()
+            .filter(|f| !f.is_empty())
+            .is_some_and(|fixed| vercmp(version, fixed) != Ordering::Less)
+    }
+
+    /// Rank used to sort by severity, most severe first
+    pub fn severity_rank(&self) -> u8 {
+        match self.severity.to_ascii_lowercase().as_str() {
+            "critical" => 0,
+            "high" => 1,
+            "medium" => 2,
+            "low" => 3,
+            _ => 4,
+        }
+    }
+
+    pub fn severity_colored(&self) -> ColoredString {
+        match self.severity_rank() {
+            0 => self.severity.bright_red().bold(),
+            1 => self.severity.red(),
+            2 => self.severity.yellow(),
+            3 => self.severity.green(),
+            _ => self.severity.dimmed(),
+        }
+    }
+}
+
+/// An installed package affected by an advisory
+#[derive(Debug)]
+pub struct Finding<'a> {
+    pub package: String,
+    pub installed_version: String,
+    pub advisory: &'a Advisory,
+}
+
+/// Download and parse the tracker feed from a URL, `file://` URL or local path
+pub async fn fetch(source: &str) -> Result<Vec<Advisory>> {
+    let bytes = crate::net::read_source(source).await?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| KhazaurError::Security(format!("Invalid security tracker data: {}", e)))
+}
+
+/// Match advisories against installed `(name, version)` pairs, most severe first
+pub fn find_affected<'a>(advisories: &'a [Advisory], installed: &[(String, String)]) -> Vec<Finding<'a>> {
+    let installed: HashMap<&str, &str> = installed
+        .iter()
+        .map(|(name, version)| (name.as_str(), version.as_str()))
+        .collect();
+
+    let mut findings: Vec<Finding> = advisories
+        .iter()
+        .flat_map(|advisory| {
+            let installed = &installed;
+            advisory.packages.iter().filter_map(move |pkg| {
+                let version = installed.get(pkg.as_str())?;
+                advisory.affects(version).then(|| Finding {
+                    package: pkg.clone(),
+                    installed_version: version.to_string(),
+                    advisory,
+                })
+            })
+        })
+        .collect();
+
+    findings.sort_by(|a, b| {
+        a.advisory
+            .severity_rank()
+            .cmp(&b.advisory.severity_rank())
+            .then_with(|| a.package.cmp(&b.package))
+    });
+    findings
+}
+
+/// Warn about pending repository updates that fix known advisories.
+/// Runs during `-Syu`; a tracker that can't be reached is not an error.
+pub async fn warn_fixed_by_updates(source: &str, updates: &[(String, String, String)]) {
+    if updates.is_empty() {
+        return;
+    }
+
+    let advisories = match fetch(source).await {
+        Ok(advisories) => advisories,
+        Err(e) => {
+            tracing::debug!("Skipping security check: {}", e);
+            return;
+        }
+    };
+
+    let installed: Vec<(String, String)> = updates
+        .iter()
+        .map(|(name, old_ver, _)| (name.clone(), old_ver.clone()))
+        .collect();
+    let new_versions: HashMap<&str, &str> = updates
+        .iter()
+        .map(|(name, _, new_ver)| (name.as_str(), new_ver.as_str()))
+        .collect();
+
+    let findings = find_affected(&advisories, &installed);
+    let fixed = findings.iter().filter(|f| {
+        new_versions
+            .get(f.package.as_str())
+            .is_some_and(|new_ver| f.advisory.is_fixed_in(new_ver))
+    });
+
+    for finding in fixed {
+        println!(
+            "{} {}",
+            "::".bright_red().bold(),
+            format!(
+                "Security: updating {} fixes {} ({}, {})",
+                finding.package, finding.advisory.name, finding.advisory.kind, finding.advisory.severity
+            )
+            .bold()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACKER: &str = r#"[
+        {"name": "AVG-1", "packages": ["openssl", "lib32-openssl"], "status": "Fixed", "severity": "High",
+         "type": "denial of service", "affected": "3.1.0-1", "fixed": "3.1.1-1", "issues": ["CVE-2023-0001"]},
+        {"name": "AVG-2", "packages": ["vim"], "status": "Vulnerable", "severity": "Medium",
+         "type": "arbitrary code execution", "affected": "9.0.1000-1", "fixed": null, "issues": []},
+        {"name": "AVG-3", "packages": ["curl"], "status": "Not affected", "severity": "Low",
+         "type": "information disclosure", "affected": "8.0.0-1", "fixed": null, "issues": []}
+    ]"#;
+
+    #[test]
+    fn test_find_affected() {
+        let advisories: Vec<Advisory> = serde_json::from_str(TRACKER).unwrap();
+        let installed = vec![
+            ("openssl".to_string(), "3.1.0-1".to_string()),
+            ("lib32-openssl".to_string(), "1:3.1.0-1".to_string()),
+            ("vim".to_string(), "9.0.1000-1".to_string()),
+            ("curl".to_string(), "8.0.0-1".to_string()),
+        ];
+
+        let findings = find_affected(&advisories, &installed);
+        let names: Vec<&str> = findings.iter().map(|f| f.package.as_str()).collect();
+        // lib32-openssl carries an epoch, so it is newer than the fixed version
+        assert_eq!(names, vec!["openssl", "vim"]);
+        assert!(findings[0].advisory.is_fixed_in("3.1.1-1"));
+        assert!(!findings[1].advisory.is_fixed_in("9.1.0-1"));
+    }
+}
@@ -163,6 +163,71 @@ pub fn get_installed_snaps(query: &str) -> Result<Vec<String>> {
     Ok(matches)
 }
 
+/// Get the snaps an installed snap relies on: its base and content providers,
+/// read from the snap's own metadata
+pub fn get_snap_dependencies(package_name: &str) -> Vec<String> {
+    let meta = std::path::Path::new("/snap")
+        .join(package_name)
+        .join("current/meta/snap.yaml");
+
+    let Ok(contents) = std::fs::read_to_string(meta) else {
+        return Vec::new();
+    };
+
+    let mut deps = Vec::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        let value = line
+            .strip_prefix("base:")
+            .or_else(|| line.strip_prefix("default-provider:"));
+
+        if let Some(value) = value {
+            // default-provider may be written as snap:slot
+            let dep = value.trim().trim_matches('"').split(':').next().unwrap_or("").trim();
+            if !dep.is_empty() && !deps.iter().any(|d| d == dep) {
+                deps.push(dep.to_string());
+            }
+        }
+    }
+
+    deps
+}
+
+/// Get the size of the current revision of an installed snap (its squashfs image)
+pub fn get_snap_size(package_name: &str) -> Option<u64> {
+    let revision = std::fs::read_link(std::path::Path::new("/snap").join(package_name).join("current")).ok()?;
+    let image = format!("/var/lib/snapd/snaps/{}_{}.snap", package_name, revision.display());
+    std::fs::metadata(image).ok().map(|m| m.len())
+}
+
+/// Get the download size of a snap's stable channel from the Snap Store
+pub async fn get_download_size(package_name: &str) -> Option<u64> {
+    let arch = match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        other => other,
+    };
+
+    let response = crate::net::client()
+        .ok()?
+        .get(format!("https://api.snapcraft.io/v2/snaps/info/{}", package_name))
+        .header("Snap-Device-Series", "16")
+        .timeout(std::time::Duration::from_secs(10))
+        .send()
+        .await
+        .ok()?;
+    let info: serde_json::Value = response.error_for_status().ok()?.json().await.ok()?;
+
+    info["channel-map"].as_array()?.iter().find_map(|entry| {
+        let channel = &entry["channel"];
+        if channel["name"] == "stable" && channel["architecture"] == arch {
+            entry["download"]["size"].as_u64()
+        } else {
+            None
+        }
+    })
+}
+
 /// Uninstall a snap package
 pub fn uninstall_snap(package_name: &str) -> Result<()> {
     if !is_available() {
@@ -184,6 +249,43 @@ pub fn uninstall_snap(package_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Version and revision of an installed snap, from `snap list`
+pub fn installed_revision(package_name: &str) -> Option<(String, String)> {
+    let output = Command::new("snap")
+        .args(["list", package_name])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    // Columns: Name Version Rev Tracking Publisher Notes
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.lines().nth(1)?.split_whitespace().skip(1);
+    Some((fields.next()?.to_string(), fields.next()?.to_string()))
+}
+
+/// Whether snapd still keeps a revision on disk so it can be reverted to
+pub fn has_revision(package_name: &str, revision: &str) -> bool {
+    std::path::Path::new(&format!("/var/lib/snapd/snaps/{}_{}.snap", package_name, revision)).exists()
+}
+
+/// Revert a snap to an earlier revision kept by snapd
+pub fn revert(package_name: &str, revision: &str) -> Result<()> {
+    let status = Command::new("sudo")
+        .args(["snap", "revert", package_name, &format!("--revision={}", revision)])
+        .status()?;
+
+    if !status.success() {
+        return Err(KhazaurError::Config(
+            format!("Failed to revert {} to revision {}", package_name, revision)
+        ));
+    }
+
+    Ok(())
+}
+
 /// Parse snap info output to extract installed and available versions
 /// Returns (installed_version, available_version) or None if parsing fails
 fn parse_snap_versions(snap_name: &str) -> Option<(String, String)> {
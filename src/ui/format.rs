@@ -3,6 +3,9 @@ use crate::pacman::RepoPackage;
 use crate::flatpak::FlatpakPackage;
 use crate::snap::SnapPackage;
 use crate::debian::DebianPackage;
+use crate::appimage::AppImagePackage;
+use crate::plugin::PluginPackage;
+use crate::cli::PackageSource;
 use colored::*;
 
 /// Format a section header
@@ -10,6 +13,23 @@ pub fn section_header(title: &str) -> String {
     format!("\n{}\n{}", title.bright_cyan().bold(), "─".repeat(title.len()).bright_black())
 }
 
+/// Format a byte count with binary units
+pub fn format_size(bytes: u64) -> String {
+    const KB: u64 = 1024;
+    const MB: u64 = KB * 1024;
+    const GB: u64 = MB * 1024;
+    
+    if bytes >= GB {
+        format!("{:.2} GB", bytes as f64 / GB as f64)
+    } else if bytes >= MB {
+        format!("{:.2} MB", bytes as f64 / MB as f64)
+    } else if bytes >= KB {
+        format!("{:.2} KB", bytes as f64 / KB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
 
 
 /// Format package list from AUR
@@ -196,9 +216,10 @@ pub fn format_debian_packages(packages: &[DebianPackage]) -> String {
         let maintainer_clean = maintainer_raw.split('<').next().unwrap_or(maintainer_raw).trim();
         let maintainer = maintainer_clean.bright_green();
         
+        let suite = if pkg.suite.is_empty() { "debian" } else { &pkg.suite };
         output.push_str(&format!(
             "{}/{} {} ({})\n",
-            "debian".bright_red(), // Debian red
+            suite.bright_red(), // Debian red
             name,
             version,
             maintainer
@@ -210,3 +231,116 @@ pub fn format_debian_packages(packages: &[DebianPackage]) -> String {
     output
 }
 
+/// Format AppImages from the catalog
+pub fn format_appimage_packages(packages: &[AppImagePackage]) -> String {
+    if packages.is_empty() {
+        return "No packages found".dimmed().to_string();
+    }
+
+    let mut output = String::new();
+
+    for pkg in packages {
+        output.push_str(&format!(
+            "{}/{} {}\n",
+            "appimage".bright_cyan(),
+            pkg.name.bright_white(),
+            pkg.version.bright_blue()
+        ));
+
+        if !pkg.description.is_empty() {
+            output.push_str(&format!("    {}\n", pkg.description.dimmed()));
+        }
+    }
+
+    output
+}
+
+/// Format packages of a backend plugin
+pub fn format_plugin_packages(packages: &[PluginPackage]) -> String {
+    if packages.is_empty() {
+        return "No packages found".dimmed().to_string();
+    }
+
+    let mut output = String::new();
+
+    for pkg in packages {
+        output.push_str(&format!(
+            "{}/{} {}\n",
+            pkg.backend.bright_cyan(),
+            pkg.name.bright_white(),
+            pkg.version.bright_blue()
+        ));
+
+        if !pkg.description.is_empty() {
+            output.push_str(&format!("    {}\n", pkg.description.dimmed()));
+        }
+    }
+
+    output
+}
+
+/// Format search results from one source, with that source's layout
+pub fn format_search_results(results: &[PackageSource]) -> String {
+    fn of<T: Clone>(results: &[PackageSource], pick: fn(&PackageSource) -> Option<&T>) -> Vec<T> {
+        results.iter().filter_map(pick).cloned().collect()
+    }
+
+    match results.first() {
+        None => "No packages found".dimmed().to_string(),
+        Some(PackageSource::Repo(_)) => format_repo_packages(&of(results, |source| match source {
+            PackageSource::Repo(pkg) => Some(pkg),
+            _ => None,
+        })),
+        // Skip the slow is_installed checks during search
+        Some(PackageSource::Aur(_)) => format_aur_packages(
+            &of(results, |source| match source {
+                PackageSource::Aur(pkg) => Some(pkg),
+                _ => None,
+            }),
+            false,
+        ),
+        Some(PackageSource::Flatpak(_)) => format_flatpak_packages(&of(results, |source| match source {
+            PackageSource::Flatpak(pkg) => Some(pkg),
+            _ => None,
+        })),
+        Some(PackageSource::Snap(_)) => format_snap_packages(&of(results, |source| match source {
+            PackageSource::Snap(pkg) => Some(pkg),
+            _ => None,
+        })),
+        Some(PackageSource::Debian(_)) => format_debian_packages(&of(results, |source| match source {
+            PackageSource::Debian(pkg) => Some(pkg),
+            _ => None,
+        })),
+        Some(PackageSource::AppImage(_)) => format_appimage_packages(&of(results, |source| match source {
+            PackageSource::AppImage(pkg) => Some(pkg),
+            _ => None,
+        })),
+        Some(PackageSource::Plugin(_)) => format_plugin_packages(&of(results, |source| match source {
+            PackageSource::Plugin(pkg) => Some(pkg),
+            _ => None,
+        })),
+    }
+}
+
+/// Format package info for sources without a detailed view of their own
+pub fn format_source_info(pkg: &PackageSource) -> String {
+    let mut output = String::new();
+
+    output.push_str(&section_header(&format!("{} Package: {}", pkg.source_type(), pkg.name())));
+    output.push('\n');
+
+    // Plugins go by their own id rather than the shared kind
+    let source = match pkg {
+        PackageSource::Plugin(pkg) => pkg.backend.as_str(),
+        _ => pkg.kind().label(),
+    };
+    output.push_str(&format!("{:<15} {}\n", "Source:".bold(), source.bright_yellow()));
+    output.push_str(&format!("{:<15} {}\n", "Name:".bold(), pkg.name().bright_white()));
+    output.push_str(&format!("{:<15} {}\n", "Version:".bold(), pkg.version().bright_blue()));
+
+    if let Some(desc) = pkg.description().filter(|desc| !desc.is_empty()) {
+        output.push_str(&format!("{:<15} {}\n", "Description:".bold(), desc));
+    }
+
+    output
+}